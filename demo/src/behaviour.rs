@@ -36,7 +36,8 @@ fn normalize_vector(vec: (f32, f32)) -> (f32, f32) {
 
 /// Updates the game state for one simulation step.
 ///
-/// Handles player movement based on input and mob behaviour.
+/// Moves the player by input and pushes mobs the player runs into back.
+/// Mobs move by themselves only through their behaviours in `State::tick`.
 ///
 /// # Arguments
 /// * `curr_state` - Mutable reference to the current game state
@@ -56,24 +57,16 @@ pub fn make_step(curr_state: &mut State, input_state: &InputSnapshot) {
     let norm = normalize_vector(player_move_vec);
     player.x += norm.0 * player_speed;
     player.y += norm.1 * player_speed;
+    let player = player.clone();
 
-    // make that mob go to player
-    for mob in &mut curr_state.mobs {
-        let vec_to = (player.x - mob.x, player.y - mob.y);
-        if abs_vector(vec_to) <= collision_distance {
-            let vec_from = (mob.x - player.x, mob.y - player.y);
+    // keep mobs out of the player
+    for mob in curr_state.mobs_mut() {
+        let vec_from = (mob.x - player.x, mob.y - player.y);
+        if abs_vector(vec_from) <= collision_distance {
             let norm = normalize_vector(vec_from);
             mob.x = player.x + norm.0 * collision_distance;
             mob.y = player.y + norm.1 * collision_distance;
-            continue;
         }
-        let norm = normalize_vector(vec_to);
-        // length of vec_move is |speed|
-        let mob_speed = (if mob.x_speed != 0. { mob.x_speed } else { mob.y_speed }).abs();
-        let vec_move = (norm.0 * mob_speed, norm.1 * mob_speed);
-
-        mob.x += vec_move.0;
-        mob.y += vec_move.1;
     }
 }
 
@@ -122,14 +115,8 @@ mod tests {
         state.player.x_speed = 0.0;
         state.player.y_speed = 0.0;
 
-        if state.mobs.is_empty() {
-            state.mobs.push(crate::world::Unit {
-                x: 100.0,
-                y: 0.0,
-                x_speed: -0.5,
-                y_speed: 0.0,
-                ..Default::default()
-            });
+        if state.mob_count() == 0 {
+            state.spawn_unit(crate::world::Unit { x: 100.0, y: 0.0, x_speed: -0.5, y_speed: 0.0 });
         }
 
         state
@@ -175,12 +162,13 @@ mod tests {
     }
 
     #[test]
-    fn test_mob_moves_toward_player() {
+    fn test_distant_mob_is_left_to_its_behaviour() {
         let mut state = make_test_state();
-        state.mobs[0].x = 50.0;
-        state.mobs[0].y = 0.0;
-        state.mobs[0].x_speed = -0.5;
-        state.mobs[0].y_speed = 0.0;
+        let mob = state.mobs_mut().next().unwrap();
+        mob.x = 50.0;
+        mob.y = 0.0;
+        mob.x_speed = -0.5;
+        mob.y_speed = 0.0;
 
         let input = crate::input::InputSnapshot {
             up: false,
@@ -192,16 +180,17 @@ mod tests {
 
         make_step(&mut state, &input);
 
-        assert!(state.mobs[0].x < 50.0);
-        assert!(state.mobs[0].y.abs() < 1e-3);
+        let mob = state.mobs().next().unwrap();
+        assert_eq!((mob.x, mob.y), (50.0, 0.0));
     }
 
     #[test]
     fn test_collision_pushes_mob_back() {
         let mut state = make_test_state();
 
-        state.mobs[0].x = 2.0;
-        state.mobs[0].y = 0.0;
+        let mob = state.mobs_mut().next().unwrap();
+        mob.x = 2.0;
+        mob.y = 0.0;
 
         let input = crate::input::InputSnapshot {
             up: false,
@@ -213,7 +202,8 @@ mod tests {
 
         make_step(&mut state, &input);
 
-        let vec_from = (state.mobs[0].x - state.player.x, state.mobs[0].y - state.player.y);
+        let mob = state.mobs().next().unwrap();
+        let vec_from = (mob.x - state.player.x, mob.y - state.player.y);
         let dist = (vec_from.0 * vec_from.0 + vec_from.1 * vec_from.1).sqrt();
         assert!((dist - 10.0).abs() < 1e-3);
     }
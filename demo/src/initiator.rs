@@ -20,7 +20,7 @@ use ferari::world::State;
 pub fn get_visible_objects(cur_state: &State, camera: &Camera) -> Vec<Unit> {
     let mut units = Vec::new();
     units.push(cur_state.player.clone());
-    units.extend(cur_state.mobs.clone());
+    units.extend(cur_state.mobs().cloned());
 
     units.into_iter().filter(|mob| camera.is_visible(mob.x, mob.y)).collect()
 }
@@ -45,19 +45,17 @@ mod visible_objects_tests {
 
     impl DummyState {
         fn to_real_state(&self) -> State {
-            State {
-                player: Unit {
-                    x: self.player.x,
-                    y: self.player.y,
-                    x_speed: self.player.x_speed,
-                    y_speed: self.player.y_speed,
-                },
-                mobs: self
-                    .mobs
-                    .iter()
-                    .map(|m| Unit { x: m.x, y: m.y, x_speed: m.x_speed, y_speed: m.y_speed })
-                    .collect(),
+            let mut state = State::default();
+            state.player = Unit {
+                x: self.player.x,
+                y: self.player.y,
+                x_speed: self.player.x_speed,
+                y_speed: self.player.y_speed,
+            };
+            for m in &self.mobs {
+                state.spawn_unit(Unit { x: m.x, y: m.y, x_speed: m.x_speed, y_speed: m.y_speed });
             }
+            state
         }
     }
 
@@ -12,6 +12,7 @@ use crate::initiator::get_visible_objects;
 use ferari::assets;
 use ferari::draw;
 use ferari::input;
+use ferari::reload;
 use ferari::render;
 use ferari::time;
 use ferari::world;
@@ -28,6 +29,30 @@ const LOGIC_HEIGHT: usize = 200;
 const TILE_SIZE: usize = 16;
 /// Upscaling factor for display.
 const UPSCALE: usize = 5;
+/// Colour of the world outside the map.
+const BACKGROUND: u32 = 195213255;
+
+/// Builds renderable entities for units, the player first.
+///
+/// Animators are resolved into sprite ids once, so no per-unit lookups are done.
+fn entities(
+    units: &[world::Unit],
+    player_animator: &assets::Animator,
+    mob_animator: &assets::Animator,
+    atlas: &assets::Atlas,
+) -> Vec<RenderableEntity> {
+    let player_sprite = player_animator.current_sprite(atlas);
+    let mob_sprite = mob_animator.current_sprite(atlas);
+
+    units
+        .iter()
+        .enumerate()
+        .filter_map(|(i, unit)| {
+            let sprite = if i == 0 { player_sprite } else { mob_sprite };
+            sprite.map(|sprite| RenderableEntity::from_id(unit.x, unit.y, sprite))
+        })
+        .collect()
+}
 
 fn main() {
     // Need to find root directory
@@ -40,13 +65,26 @@ fn main() {
     let tiles_path = assets_path.join("tiles/atlas.json");
     let entities_path = assets_path.join("entities/atlas.json");
 
-    let tiles_atlas = assets::Atlas::load(tiles_path.to_str().unwrap()).unwrap();
+    let tiles_atlas = Arc::new(assets::Atlas::load(tiles_path.to_str().unwrap()).unwrap());
     let entities_atlas = assets::Atlas::load(entities_path.to_str().unwrap()).unwrap();
 
     // parse game descr
     let game_path = project_root.join("examples/input.json");
 
-    let game = assets::GameMap::load(game_path).unwrap();
+    let game = Arc::new(assets::GameMap::load(&game_path).unwrap());
+    let report = game.validate(&tiles_atlas, &entities_atlas);
+    if !report.is_ok() {
+        eprintln!("{}: invalid map\n{report}", game_path.display());
+        std::process::exit(1);
+    }
+
+    // mob behaviours come from the built-in registry
+    let registry = Arc::new(world::BehaviourRegistry::new());
+    let report = registry.validate(&game);
+    if !report.is_ok() {
+        eprintln!("{}: invalid behaviours\n{report}", game_path.display());
+        std::process::exit(1);
+    }
 
     // init draw
     let input_state = Arc::new(input::InputState::new());
@@ -72,15 +110,13 @@ fn main() {
         });
     }
 
-    // init world_buf
+    // init render, the world is baked in chunks around the camera
     let world_width = game.size[0] as usize * TILE_SIZE * 2;
     let world_height = game.size[1] as usize * TILE_SIZE * 2;
 
-    let world_buf: Vec<u32> = vec![195213255; world_width * world_height];
-    // init render
-    let shadow_map: Vec<u8> = vec![0; world_width * world_height];
+    let chunk_options = render::ChunkOptions { background: BACKGROUND, ..Default::default() };
     let mut render =
-        render::Render::new(world_buf, world_height, world_width, entities_atlas, shadow_map);
+        render::Render::new_chunked(world_height, world_width, entities_atlas, chunk_options);
 
     // init camera
     let mut camera = world::Camera::new(
@@ -93,31 +129,38 @@ fn main() {
     // init time
     let mut time = time::Time::new();
 
+    // init animations
+    let mut player_animator = assets::Animator::new("knight_0");
+    let mut mob_animator = assets::Animator::new("imp_20");
+
     // init state of game
-    let mut state = world::State::new(&game);
+    let mut state = world::State::with_registry(&game, registry);
+    let mut spawners = world::SpawnerSystem::new(&game);
+    spawners.update(&game, &mut state, 0.0);
 
     // prerender
-    render.init(&game, &tiles_atlas);
+    render.init(Arc::clone(&game), Arc::clone(&tiles_atlas));
+
+    // `--watch` reloads the map and atlases when their files change
+    let mut hot_reload = std::env::args().any(|arg| arg == "--watch").then(|| {
+        reload::HotReload::new(
+            &game_path,
+            &tiles_path,
+            &entities_path,
+            Arc::clone(&game),
+            Arc::clone(&tiles_atlas),
+            BACKGROUND,
+        )
+    });
 
     let all_units = {
         let mut units = vec![state.player.clone()];
-        units.extend(state.mobs.clone());
+        units.extend(state.mobs().cloned());
         units
     };
 
-    let visible_entities: Vec<RenderableEntity> = all_units
-        .into_iter()
-        .enumerate()
-        .map(|(i, unit)| {
-            let name_model = if i == 0 { "knight_0" } else { "imp_20" };
-            let period = 0.4;
-            let cycles = (time.total / period).floor() as u32;
-            let animation_num = if cycles.is_multiple_of(2) { "_0" } else { "_1" };
-            let full_name = name_model.to_string() + animation_num;
-
-            RenderableEntity::new(unit.x, unit.y, full_name)
-        })
-        .collect();
+    let visible_entities =
+        entities(&all_units, &player_animator, &mob_animator, &render.entity_atlas);
 
     render.render_frame(&visible_entities, &camera, &mut back_buffer);
     state.player.x = camera.center_x;
@@ -125,6 +168,8 @@ fn main() {
     // game loop
     while running.load(Ordering::Acquire) {
         time.update();
+        player_animator.update(time.delta);
+        mob_animator.update(time.delta);
 
         // test gradient (TODO: move to tests?)
         // let r = ((time.total).sin() * 127.0 + 128.0) as u32;
@@ -142,6 +187,23 @@ fn main() {
             running.store(false, Ordering::Release);
         }
 
+        if let Some(hot_reload) = hot_reload.as_mut() {
+            let report = hot_reload.poll(&mut render, &mut state);
+            for path in &report.reloaded {
+                println!("Reloaded {}", path.display());
+            }
+            for err in &report.errors {
+                eprintln!("Reload failed, keeping the last good version: {err}");
+            }
+            if report.respawned {
+                spawners = world::SpawnerSystem::new(&hot_reload.map);
+            }
+        }
+
+        // spawners and behaviours run on the last good map
+        let map = hot_reload.as_ref().map_or(&game, |hot_reload| &hot_reload.map);
+        spawners.update(map, &mut state, time.delta);
+        state.tick(map, time.delta);
         make_step(&mut state, &input);
 
         camera.center_x = state.player.x;
@@ -154,19 +216,8 @@ fn main() {
         }
 
         // frame render
-        let visible_entities: Vec<RenderableEntity> = units_for_render
-            .into_iter()
-            .enumerate()
-            .map(|(i, unit)| {
-                let name_model = if i == 0 { "knight_0" } else { "imp_20" };
-                let period = 0.4;
-                let cycles = (time.total / period).floor() as u32;
-                let animation_num = if cycles.is_multiple_of(2) { "_0" } else { "_1" };
-                let full_name = name_model.to_string() + animation_num;
-
-                RenderableEntity::new(unit.x, unit.y, full_name)
-            })
-            .collect();
+        let visible_entities =
+            entities(&units_for_render, &player_animator, &mob_animator, &render.entity_atlas);
 
         render.render_frame(&visible_entities, &camera, &mut back_buffer);
 
@@ -0,0 +1,414 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::{Atlas, Frame, SpriteId};
+
+// ============================
+// JSON-level structs
+// ============================
+
+/// Frame of an animation clip from JSON.
+///
+/// Either a plain frame name or a frame name with its own duration.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub(super) enum JsonAnimationFrame {
+    /// Frame shown for the clip's default duration
+    Name(String),
+    /// Frame shown for the given duration
+    Timed {
+        /// Name of the frame in the atlas
+        frame: String,
+        /// Duration of the frame in seconds
+        duration: f32,
+    },
+}
+
+/// Animation clip definition from JSON atlas data.
+#[derive(Deserialize, Debug)]
+pub(super) struct JsonAnimation {
+    /// Ordered frames of the clip
+    pub frames: Vec<JsonAnimationFrame>,
+    /// Default duration of a frame in seconds
+    #[serde(default = "default_frame_duration")]
+    pub duration: f32,
+    /// Playback mode of the clip
+    #[serde(default)]
+    pub mode: PlaybackMode,
+}
+
+fn default_frame_duration() -> f32 {
+    0.1
+}
+
+// ============================
+// Game-level structs
+// ============================
+
+/// How a clip continues after its last frame.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackMode {
+    /// Start over from the first frame
+    #[default]
+    Loop,
+    /// Play backwards to the first frame, then forwards again
+    PingPong,
+    /// Stop on the last frame
+    Once,
+}
+
+/// Single frame of an animation clip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationFrame {
+    /// Name of the frame in the atlas
+    pub frame: String,
+    /// Duration of the frame in seconds
+    pub duration: f32,
+}
+
+/// Named animation clip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    /// Name identifier of the clip
+    pub name: String,
+    /// Ordered frames of the clip
+    pub frames: Vec<AnimationFrame>,
+    /// Playback mode of the clip
+    pub mode: PlaybackMode,
+}
+
+/// Playback state of a single animation clip.
+///
+/// The animator only stores the clip name and the playback position,
+/// frames are resolved against an [`Atlas`] when needed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animator {
+    /// Name of the played clip
+    clip: String,
+    /// Playback position in seconds
+    position: f32,
+    /// Playback speed multiplier
+    pub speed: f32,
+}
+
+// ============================
+// Implementation
+// ============================
+
+impl Animation {
+    /// Builds a clip from its JSON definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the clip
+    /// * `json` - Parsed JSON definition
+    /// * `frames` - Frames of the atlas the clip belongs to
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - Ok(Animation) if the clip is consistent, problem description otherwise.
+    pub(super) fn from_json(
+        name: &str,
+        json: JsonAnimation,
+        frames: &HashMap<String, Frame>,
+    ) -> Result<Self, String> {
+        if json.frames.is_empty() {
+            return Err(format!("animation `{name}` has no frames"));
+        }
+
+        let mut clip_frames = Vec::with_capacity(json.frames.len());
+        for json_frame in json.frames {
+            let (frame, duration) = match json_frame {
+                JsonAnimationFrame::Name(frame) => (frame, json.duration),
+                JsonAnimationFrame::Timed { frame, duration } => (frame, duration),
+            };
+
+            if !frames.contains_key(&frame) {
+                return Err(format!("animation `{name}` refers to unknown frame `{frame}`"));
+            }
+            if duration.is_nan() || duration <= 0.0 {
+                return Err(format!("animation `{name}` has non-positive duration for `{frame}`"));
+            }
+
+            clip_frames.push(AnimationFrame { frame, duration });
+        }
+
+        Ok(Animation { name: name.to_string(), frames: clip_frames, mode: json.mode })
+    }
+
+    /// Returns the duration of one pass over the clip frames.
+    ///
+    /// # Returns
+    ///
+    /// * `f32` - Sum of frame durations in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+
+    /// Returns the index of the frame shown at the given playback position.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Playback position in seconds
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - Index into `frames`.
+    pub fn frame_index_at(&self, position: f32) -> usize {
+        let last = self.frames.len() - 1;
+        let position = position.max(0.0);
+
+        match self.mode {
+            PlaybackMode::Once => {
+                if position >= self.duration() {
+                    last
+                } else {
+                    self.index_in_pass(position)
+                }
+            }
+            PlaybackMode::Loop => self.index_in_pass(position % self.duration()),
+            PlaybackMode::PingPong => {
+                if last == 0 {
+                    return 0;
+                }
+                // The way back skips both end frames, so they are not shown twice
+                let inner: f32 = self.frames[1..last].iter().map(|f| f.duration).sum();
+                let cycle = self.duration() + inner;
+                let t = position % cycle;
+                if t < self.duration() {
+                    return self.index_in_pass(t);
+                }
+
+                let mut t = t - self.duration();
+                for index in (1..last).rev() {
+                    if t < self.frames[index].duration {
+                        return index;
+                    }
+                    t -= self.frames[index].duration;
+                }
+                1
+            }
+        }
+    }
+
+    /// Returns the frame name shown at the given playback position.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - Playback position in seconds
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - Name of the atlas frame.
+    pub fn frame_at(&self, position: f32) -> &str {
+        &self.frames[self.frame_index_at(position)].frame
+    }
+
+    /// Finds the frame index for a position inside one forward pass.
+    fn index_in_pass(&self, mut position: f32) -> usize {
+        for (index, frame) in self.frames.iter().enumerate() {
+            if position < frame.duration {
+                return index;
+            }
+            position -= frame.duration;
+        }
+        self.frames.len() - 1
+    }
+}
+
+impl Animator {
+    /// Creates an animator at the start of the given clip.
+    ///
+    /// # Arguments
+    ///
+    /// * `clip` - Name of the clip in the atlas
+    ///
+    /// # Returns
+    ///
+    /// A new `Animator` instance with zero position and normal speed.
+    pub fn new(clip: &str) -> Self {
+        Self { clip: clip.to_string(), position: 0.0, speed: 1.0 }
+    }
+
+    /// Switches to another clip.
+    ///
+    /// Playback restarts only if the clip actually changes, so calling this
+    /// every tick with the same name keeps the animation running.
+    ///
+    /// # Arguments
+    ///
+    /// * `clip` - Name of the clip in the atlas
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip {
+            self.clip = clip.to_string();
+            self.position = 0.0;
+        }
+    }
+
+    /// Advances playback.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta` - Time since the last update in seconds, usually `Time::delta`
+    pub fn update(&mut self, delta: f32) {
+        self.position += delta * self.speed;
+    }
+
+    /// Returns the name of the played clip.
+    pub fn clip(&self) -> &str {
+        &self.clip
+    }
+
+    /// Returns the playback position in seconds.
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Checks if a [`PlaybackMode::Once`] clip has reached its end.
+    ///
+    /// # Arguments
+    ///
+    /// * `atlas` - Atlas containing the clip
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the clip is finished, false for looping or unknown clips.
+    pub fn is_finished(&self, atlas: &Atlas) -> bool {
+        atlas
+            .get_animation(&self.clip)
+            .is_some_and(|clip| clip.mode == PlaybackMode::Once && self.position >= clip.duration())
+    }
+
+    /// Resolves the frame shown at the current playback position.
+    ///
+    /// # Arguments
+    ///
+    /// * `atlas` - Atlas containing the clip
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Frame>` - Some(&Frame) if the clip exists, None otherwise.
+    pub fn current_frame<'a>(&self, atlas: &'a Atlas) -> Option<&'a Frame> {
+        let clip = atlas.get_animation(&self.clip)?;
+        atlas.get_frame(clip.frame_at(self.position))
+    }
+
+    /// Resolves the sprite id of the frame shown at the current playback position.
+    ///
+    /// Animators shared by many entities can resolve the id once per tick
+    /// and hand it to every [`RenderableEntity`](crate::render::RenderableEntity).
+    ///
+    /// # Arguments
+    ///
+    /// * `atlas` - Atlas containing the clip
+    ///
+    /// # Returns
+    ///
+    /// * `Option<SpriteId>` - Some(SpriteId) if the clip exists, None otherwise.
+    pub fn current_sprite(&self, atlas: &Atlas) -> Option<SpriteId> {
+        let clip = atlas.get_animation(&self.clip)?;
+        atlas.sprite_id(clip.frame_at(self.position))
+    }
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn make_clip(mode: PlaybackMode, durations: &[f32]) -> Animation {
+        Animation {
+            name: "clip".to_string(),
+            frames: durations
+                .iter()
+                .enumerate()
+                .map(|(i, &duration)| AnimationFrame { frame: format!("f{i}"), duration })
+                .collect(),
+            mode,
+        }
+    }
+
+    // Test loop playback with per-frame durations
+    #[test]
+    fn test_loop_mode() {
+        let clip = make_clip(PlaybackMode::Loop, &[0.1, 0.3, 0.2]);
+
+        assert_eq!(clip.duration(), 0.6);
+        assert_eq!(clip.frame_index_at(0.0), 0);
+        assert_eq!(clip.frame_index_at(0.05), 0);
+        assert_eq!(clip.frame_index_at(0.15), 1);
+        assert_eq!(clip.frame_index_at(0.45), 2);
+        assert_eq!(clip.frame_index_at(0.65), 0);
+        assert_eq!(clip.frame_index_at(0.85), 1);
+        assert_eq!(clip.frame_at(0.85), "f1");
+    }
+
+    // Test ping-pong playback without repeating end frames
+    #[test]
+    fn test_ping_pong_mode() {
+        let clip = make_clip(PlaybackMode::PingPong, &[1.0, 1.0, 1.0]);
+
+        let indices: Vec<usize> = (0..9).map(|t| clip.frame_index_at(t as f32 + 0.5)).collect();
+        assert_eq!(indices, vec![0, 1, 2, 1, 0, 1, 2, 1, 0]);
+
+        let single = make_clip(PlaybackMode::PingPong, &[0.5]);
+        assert_eq!(single.frame_index_at(3.0), 0);
+    }
+
+    // Test that once playback holds the last frame
+    #[test]
+    fn test_once_mode() {
+        let clip = make_clip(PlaybackMode::Once, &[0.5, 0.5]);
+
+        assert_eq!(clip.frame_index_at(0.2), 0);
+        assert_eq!(clip.frame_index_at(0.7), 1);
+        assert_eq!(clip.frame_index_at(10.0), 1);
+    }
+
+    // Test animator driven by deltas over an atlas clip
+    #[test]
+    fn test_animator_with_atlas() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let atlas = Atlas::load(project_root.join("assets/entities/atlas.json")).unwrap();
+
+        let mut animator = Animator::new("knight_0");
+        assert_eq!(animator.current_frame(&atlas).unwrap().name, "knight_0_0");
+
+        animator.update(0.3);
+        assert_eq!(animator.current_frame(&atlas).unwrap().name, "knight_0_0");
+        animator.update(0.2);
+        assert_eq!(animator.current_frame(&atlas).unwrap().name, "knight_0_1");
+        assert_eq!(animator.current_sprite(&atlas), atlas.sprite_id("knight_0_1"));
+        animator.update(0.4);
+        assert_eq!(animator.current_frame(&atlas).unwrap().name, "knight_0_0");
+        assert!(!animator.is_finished(&atlas));
+
+        animator.play("knight_0");
+        assert!((animator.position() - 0.9).abs() < 1e-6);
+        animator.play("imp_20");
+        assert_eq!(animator.clip(), "imp_20");
+        assert_eq!(animator.position(), 0.0);
+        assert_eq!(animator.current_frame(&atlas).unwrap().name, "imp_20_0");
+
+        animator.play("missing");
+        assert!(animator.current_frame(&atlas).is_none());
+        assert!(animator.current_sprite(&atlas).is_none());
+    }
+
+    // Test that clips referring to unknown frames are rejected
+    #[test]
+    fn test_from_json_unknown_frame() {
+        let json: JsonAnimation =
+            serde_json::from_str(r#"{"frames": ["a", {"frame": "b", "duration": 0.5}]}"#).unwrap();
+        let mut frames = HashMap::new();
+        frames.insert("a".to_string(), Frame::new("a", 0, 0, 1, 1));
+
+        let err = Animation::from_json("walk", json, &frames).unwrap_err();
+        assert!(err.contains("`b`"));
+    }
+}
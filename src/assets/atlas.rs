@@ -1,10 +1,15 @@
-use image::{open, RgbaImage};
+use image::{imageops, open, ImageError, RgbaImage};
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::error::Error;
-use std::fs::File;
-use std::io::BufReader;
+use std::fs;
+use std::io::{ErrorKind, Read};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::animation::{Animation, JsonAnimation};
+use super::error::MEMORY_PATH;
+use super::variant::JsonVariant;
+use super::{AssetError, AtlasFormat, FramePixels, Variant};
 
 // ============================
 // JSON-level structs
@@ -21,6 +26,15 @@ struct JsonFrame {
     pub w: u32,
     /// Height of the frame in pixels
     pub h: u32,
+    /// Anchor point of the sprite, relative to the untrimmed sprite
+    #[serde(default)]
+    pub pivot: Option<[i32; 2]>,
+    /// Position of the frame inside the untrimmed sprite
+    #[serde(default)]
+    pub offset: Option<[i32; 2]>,
+    /// Size of the untrimmed sprite
+    #[serde(default)]
+    pub source_size: Option<[u32; 2]>,
 }
 
 /// Meta information about the atlas from JSON.
@@ -39,6 +53,12 @@ struct Meta {
 struct AtlasJson {
     /// Mapping of frame names to their definitions
     pub frames: HashMap<String, JsonFrame>,
+    /// Mapping of animation clip names to their definitions
+    #[serde(default)]
+    pub animations: HashMap<String, JsonAnimation>,
+    /// Mapping of variant frame names to recolouring definitions
+    #[serde(default)]
+    pub variants: HashMap<String, JsonVariant>,
     /// Meta information about the atlas
     pub meta: Meta,
 }
@@ -61,6 +81,29 @@ pub struct Frame {
     pub w: u32,
     /// Height of the frame in pixels
     pub h: u32,
+    /// Anchor point of the sprite, relative to the untrimmed sprite.
+    /// None means the renderer picks a default for the kind of sprite.
+    pub pivot: Option<(i32, i32)>,
+    /// Position of the frame inside the untrimmed sprite, (0, 0) if not trimmed
+    pub offset: (i32, i32),
+    /// Size of the untrimmed sprite, None if not trimmed
+    pub source_size: Option<(u32, u32)>,
+}
+
+/// Interned handle of an atlas frame.
+///
+/// Ids are resolved once with [`Atlas::sprite_id`] and are only valid for
+/// the atlas that issued them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SpriteId(pub u32);
+
+/// Frame with its decoded pixels, stored under a [`SpriteId`].
+#[derive(Debug, Clone)]
+struct Sprite {
+    /// Frame definition
+    frame: Frame,
+    /// Decoded pixels of the frame
+    pixels: Arc<FramePixels>,
 }
 
 /// Complete atlas containing the image and frame definitions.
@@ -69,41 +112,444 @@ pub struct Frame {
 pub struct Atlas {
     /// The loaded RGBA image data of the atlas
     pub image: RgbaImage,
-    /// Mapping of frame names to frame definitions
-    pub frames: HashMap<String, Frame>,
+    /// Mapping of frame names to frame definitions, changed only together with `sprites`
+    pub(super) frames: HashMap<String, Frame>,
+    /// Mapping of animation clip names to clip definitions
+    pub animations: HashMap<String, Animation>,
     /// Size of tiles in the atlas
     pub tile_size: u32,
     /// Version of the atlas
     pub version: u32,
+    /// Frames with decoded pixels, indexed by sprite id
+    sprites: Vec<Sprite>,
+    /// Sprite ids by frame name
+    sprite_ids: HashMap<String, SpriteId>,
 }
 
 // ============================
 // Implementation
 // ============================
 
+impl Frame {
+    /// Creates an untrimmed frame without a pivot.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name identifier of the frame
+    /// * `x` - X coordinate of the frame in the atlas image
+    /// * `y` - Y coordinate of the frame in the atlas image
+    /// * `w` - Width of the frame in pixels
+    /// * `h` - Height of the frame in pixels
+    ///
+    /// # Returns
+    ///
+    /// A new `Frame` instance with zero offset and no pivot.
+    pub fn new(name: &str, x: u32, y: u32, w: u32, h: u32) -> Self {
+        Self { name: name.to_string(), x, y, w, h, pivot: None, offset: (0, 0), source_size: None }
+    }
+
+    /// Returns the size of the sprite before trimming.
+    ///
+    /// # Returns
+    ///
+    /// * `(u32, u32)` - Tuple of (width, height), the frame size if not trimmed.
+    pub fn untrimmed_size(&self) -> (u32, u32) {
+        self.source_size.unwrap_or((self.w, self.h))
+    }
+
+    /// Computes where the top-left pixel of the frame is drawn.
+    ///
+    /// The frame is placed so that its pivot lands on the anchor point,
+    /// shifted by the trim offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `anchor_x` - X coordinate of the anchor point
+    /// * `anchor_y` - Y coordinate of the anchor point
+    /// * `default_pivot` - Pivot used when the frame does not define one
+    ///
+    /// # Returns
+    ///
+    /// * `(i32, i32)` - Tuple of (x, y) coordinates of the top-left pixel.
+    pub fn origin(&self, anchor_x: i32, anchor_y: i32, default_pivot: (i32, i32)) -> (i32, i32) {
+        let (pivot_x, pivot_y) = self.pivot.unwrap_or(default_pivot);
+        (anchor_x - pivot_x + self.offset.0, anchor_y - pivot_y + self.offset.1)
+    }
+}
+
+/// Finds the smallest rectangle holding all non-transparent pixels of an image area.
+///
+/// # Arguments
+///
+/// * `image` - Image to scan
+/// * `x`, `y`, `w`, `h` - Scanned area, clipped to the image
+///
+/// # Returns
+///
+/// * `Option<(u32, u32, u32, u32)>` - (x, y, w, h) in image coordinates, None if fully transparent.
+pub(super) fn opaque_bounds(
+    image: &RgbaImage,
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    let x_end = x.saturating_add(w).min(image.width());
+    let y_end = y.saturating_add(h).min(image.height());
+
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for py in y..y_end {
+        for px in x..x_end {
+            if image.get_pixel(px, py)[3] == 0 {
+                continue;
+            }
+            let (min_x, min_y, max_x, max_y) = bounds.unwrap_or((px, py, px, py));
+            bounds = Some((min_x.min(px), min_y.min(py), max_x.max(px), max_y.max(py)));
+        }
+    }
+
+    bounds.map(|(min_x, min_y, max_x, max_y)| (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+/// Opens the image of an atlas.
+///
+/// # Arguments
+///
+/// * `json_path` - Path to the atlas JSON file
+/// * `image` - Image path from the atlas meta, relative to the JSON file
+///
+/// # Returns
+///
+/// * `Result<RgbaImage, AssetError>` - Ok(RgbaImage) if successful, Err otherwise.
+pub(super) fn load_image(json_path: &Path, image: &str) -> Result<RgbaImage, AssetError> {
+    let image_path = resolve_image(json_path, image);
+
+    match open(&image_path) {
+        Ok(image) => Ok(image.to_rgba8()),
+        Err(ImageError::IoError(e)) if e.kind() == ErrorKind::NotFound => {
+            Err(AssetError::MissingImage { path: json_path.to_path_buf(), image: image_path })
+        }
+        Err(source) => Err(AssetError::Image { path: image_path, source }),
+    }
+}
+
+/// Copies sprites below the image and points their frames at the copies.
+///
+/// Sprites are laid out in rows no wider than the original image, or the
+/// widest sprite if it does not fit.
+pub(super) fn append_sprites(
+    image: RgbaImage,
+    sprites: &[(String, RgbaImage)],
+    frames: &mut HashMap<String, Frame>,
+) -> RgbaImage {
+    let row_width = sprites.iter().map(|(_, s)| s.width()).max().unwrap_or(0).max(image.width());
+
+    let mut places = Vec::with_capacity(sprites.len());
+    let (mut x, mut y, mut row_height) = (0, image.height(), 0);
+    for (_, sprite) in sprites {
+        if x + sprite.width() > row_width {
+            x = 0;
+            y += row_height;
+            row_height = 0;
+        }
+        places.push((x, y));
+        x += sprite.width();
+        row_height = row_height.max(sprite.height());
+    }
+
+    let mut result = RgbaImage::new(row_width, y + row_height);
+    imageops::replace(&mut result, &image, 0, 0);
+    for ((name, sprite), (x, y)) in sprites.iter().zip(places) {
+        imageops::replace(&mut result, sprite, x as i64, y as i64);
+        if let Some(frame) = frames.get_mut(name) {
+            frame.x = x;
+            frame.y = y;
+        }
+    }
+    result
+}
+
+/// Decodes an in-memory atlas image.
+fn decode_image(bytes: &[u8]) -> Result<RgbaImage, AssetError> {
+    image::load_from_memory(bytes)
+        .map(|image| image.to_rgba8())
+        .map_err(|source| AssetError::Image { path: PathBuf::from(MEMORY_PATH), source })
+}
+
+/// Resolves the image of an atlas against its JSON file.
+fn resolve_image(json_path: &Path, image: &str) -> PathBuf {
+    json_path.parent().map(|dir| dir.join(image)).unwrap_or_else(|| PathBuf::from(image))
+}
+
 impl Atlas {
+    /// Creates an atlas and decodes the pixels of its frames.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - Atlas image
+    /// * `frames` - Mapping of frame names to frame definitions
+    /// * `animations` - Mapping of animation clip names to clip definitions
+    /// * `tile_size` - Size of tiles in the atlas
+    /// * `version` - Version of the atlas
+    ///
+    /// # Returns
+    ///
+    /// A new `Atlas` instance.
+    pub fn new(
+        image: RgbaImage,
+        frames: HashMap<String, Frame>,
+        animations: HashMap<String, Animation>,
+        tile_size: u32,
+        version: u32,
+    ) -> Self {
+        let mut atlas = Self {
+            image,
+            frames,
+            animations,
+            tile_size,
+            version,
+            sprites: Vec::new(),
+            sprite_ids: HashMap::new(),
+        };
+        atlas.decode_frames();
+        atlas
+    }
+
+    /// Decodes the pixels of every frame again and reassigns sprite ids.
+    ///
+    /// Call it after changing `image`. Ids are given in frame name order,
+    /// so they stay the same while the set of names does.
+    pub fn decode_frames(&mut self) {
+        let mut names: Vec<&String> = self.frames.keys().collect();
+        names.sort();
+
+        self.sprites = names
+            .iter()
+            .map(|name| {
+                let frame = self.frames[*name].clone();
+                let pixels = Arc::new(FramePixels::decode(&self.image, &frame));
+                Sprite { frame, pixels }
+            })
+            .collect();
+        self.sprite_ids = names
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| (name.clone(), SpriteId(index as u32)))
+            .collect();
+    }
+
+    /// Adds a frame or replaces the frame with the same name.
+    ///
+    /// Pixels are decoded again, so sprites always match the frames.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Frame to store under its name
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Frame>` - Some(Frame) that was replaced, None if the name is new.
+    pub fn insert_frame(&mut self, frame: Frame) -> Option<Frame> {
+        let replaced = self.frames.insert(frame.name.clone(), frame);
+        self.decode_frames();
+        replaced
+    }
+
+    /// Removes a frame.
+    ///
+    /// Pixels are decoded again, sprite ids of frames named after it shift.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the frame to remove
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Frame>` - Some(Frame) that was removed, None if there was no such frame.
+    pub fn remove_frame(&mut self, name: &str) -> Option<Frame> {
+        let removed = self.frames.remove(name)?;
+        self.decode_frames();
+        Some(removed)
+    }
+
+    /// Resolves a frame name into a sprite id.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the frame
+    ///
+    /// # Returns
+    ///
+    /// * `Option<SpriteId>` - Some(SpriteId) if frame exists, None otherwise.
+    pub fn sprite_id(&self, name: &str) -> Option<SpriteId> {
+        self.sprite_ids.get(name).copied()
+    }
+
+    /// Retrieves a frame by its sprite id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Sprite id issued by this atlas
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Frame>` - Some(&Frame) if id is valid, None otherwise.
+    pub fn sprite(&self, id: SpriteId) -> Option<&Frame> {
+        self.sprites.get(id.0 as usize).map(|sprite| &sprite.frame)
+    }
+
+    /// Retrieves decoded pixels by sprite id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Sprite id issued by this atlas
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Arc<FramePixels>>` - Some pixels if id is valid, None otherwise.
+    pub fn sprite_pixels(&self, id: SpriteId) -> Option<&Arc<FramePixels>> {
+        self.sprites.get(id.0 as usize).map(|sprite| &sprite.pixels)
+    }
+
+    /// Returns the decoded pixels of a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Frame of this atlas
+    ///
+    /// # Returns
+    ///
+    /// * `Arc<FramePixels>` - Pixels decoded at load, or freshly decoded ones
+    ///   if the frame is unknown or was changed since.
+    pub fn frame_pixels(&self, frame: &Frame) -> Arc<FramePixels> {
+        match self.sprite_id(&frame.name).and_then(|id| self.sprite_pixels(id)) {
+            Some(pixels) if pixels.matches(frame) => Arc::clone(pixels),
+            _ => Arc::new(FramePixels::decode(&self.image, frame)),
+        }
+    }
+
     /// Loads a texture atlas from a JSON file.
     ///
+    /// The layout of the file is detected automatically, see [`AtlasFormat`].
+    ///
+    /// # Arguments
+    ///
+    /// * `json_path` - Path to the atlas JSON file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, AssetError>` - Ok(Atlas) if successful, Err otherwise.
+    pub fn load<P: AsRef<Path>>(json_path: P) -> Result<Self, AssetError> {
+        Self::load_as(json_path, AtlasFormat::Auto)
+    }
+
+    /// Loads a texture atlas from a JSON file in the given layout.
+    ///
     /// # Arguments
     ///
     /// * `json_path` - Path to the atlas JSON file
+    /// * `format` - Layout of the file, [`AtlasFormat::Auto`] to detect it
     ///
     /// # Returns
     ///
-    /// * `Result<Self, Box<dyn Error>>` - Ok(Atlas) if successful, Err otherwise.
-    pub fn load<P: AsRef<Path>>(json_path: P) -> Result<Self, Box<dyn Error>> {
-        let file = File::open(&json_path)?;
+    /// * `Result<Self, AssetError>` - Ok(Atlas) if successful, Err otherwise.
+    pub fn load_as<P: AsRef<Path>>(json_path: P, format: AtlasFormat) -> Result<Self, AssetError> {
+        let json_path = json_path.as_ref();
+        let text = fs::read_to_string(json_path).map_err(|e| AssetError::io(json_path, e))?;
 
-        let reader = BufReader::new(file);
-        let atlas_json: AtlasJson = serde_json::from_reader(reader)?;
+        Self::from_text(json_path, &text, format, |image| load_image(json_path, image))
+    }
 
-        let image_path = json_path
-            .as_ref()
-            .parent()
-            .map(|dir| dir.join(&atlas_json.meta.image))
-            .unwrap_or_else(|| PathBuf::from(&atlas_json.meta.image));
+    /// Loads a texture atlas from in-memory JSON and image data.
+    ///
+    /// The layout of the JSON is detected automatically and `meta.image` is
+    /// ignored. Errors report the path `<memory>`.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - Contents of the atlas JSON file
+    /// * `image` - Encoded atlas image, in any format supported by `image`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, AssetError>` - Ok(Atlas) if successful, Err otherwise.
+    pub fn from_bytes(json: &[u8], image: &[u8]) -> Result<Self, AssetError> {
+        let text = std::str::from_utf8(json)
+            .map_err(|_| AssetError::invalid(MEMORY_PATH, "atlas JSON is not valid UTF-8"))?;
 
-        let image = open(image_path)?.to_rgba8();
+        Self::from_text(Path::new(MEMORY_PATH), text, AtlasFormat::Auto, |_| decode_image(image))
+    }
+
+    /// Loads a texture atlas from readers of JSON and image data.
+    ///
+    /// Same as [`Atlas::from_bytes`], both readers are read to the end.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - Reader of the atlas JSON file
+    /// * `image` - Reader of the encoded atlas image
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, AssetError>` - Ok(Atlas) if successful, Err otherwise.
+    pub fn from_reader<J: Read, I: Read>(mut json: J, mut image: I) -> Result<Self, AssetError> {
+        let mut json_bytes = Vec::new();
+        json.read_to_end(&mut json_bytes).map_err(|e| AssetError::io(MEMORY_PATH, e))?;
+        let mut image_bytes = Vec::new();
+        image.read_to_end(&mut image_bytes).map_err(|e| AssetError::io(MEMORY_PATH, e))?;
+
+        Self::from_bytes(&json_bytes, &image_bytes)
+    }
+
+    /// Builds an atlas from JSON text in the given layout.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Path reported in errors
+    /// * `text` - Contents of the atlas JSON file
+    /// * `format` - Layout of the JSON, [`AtlasFormat::Auto`] to detect it
+    /// * `image` - Provides the atlas image for the `meta.image` value
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, AssetError>` - Ok(Atlas) if successful, Err otherwise.
+    fn from_text<F>(
+        source: &Path,
+        text: &str,
+        format: AtlasFormat,
+        image: F,
+    ) -> Result<Self, AssetError>
+    where
+        F: FnOnce(&str) -> Result<RgbaImage, AssetError>,
+    {
+        let format = match format {
+            AtlasFormat::Auto => {
+                AtlasFormat::detect(text).map_err(|e| AssetError::json(source, e))?
+            }
+            format => format,
+        };
+
+        match format {
+            AtlasFormat::Aseprite | AtlasFormat::TexturePacker => {
+                Self::from_sheet_json(source, text, image)
+            }
+            _ => Self::from_ferari_json(source, text, image),
+        }
+    }
+
+    /// Builds an atlas from the text of a Ferari atlas JSON file.
+    fn from_ferari_json<F>(json_path: &Path, text: &str, image: F) -> Result<Self, AssetError>
+    where
+        F: FnOnce(&str) -> Result<RgbaImage, AssetError>,
+    {
+        let atlas_json: AtlasJson =
+            serde_json::from_str(text).map_err(|e| AssetError::json(json_path, e))?;
+
+        if atlas_json.meta.tile_size == 0 {
+            return Err(AssetError::invalid(json_path, "meta.tile_size must be positive"));
+        }
+
+        let image = image(&atlas_json.meta.image)?;
 
         let mut frames = HashMap::new();
 
@@ -114,16 +560,84 @@ impl Atlas {
                 y: json_frame.y,
                 w: json_frame.w,
                 h: json_frame.h,
+                pivot: json_frame.pivot.map(|[x, y]| (x, y)),
+                offset: json_frame.offset.map(|[x, y]| (x, y)).unwrap_or((0, 0)),
+                source_size: json_frame.source_size.map(|[w, h]| (w, h)),
             };
             frames.insert(name, frame);
         }
 
-        Ok(Atlas {
+        let mut atlas = Atlas::new(
             image,
             frames,
-            tile_size: atlas_json.meta.tile_size,
-            version: atlas_json.meta.version,
-        })
+            HashMap::new(),
+            atlas_json.meta.tile_size,
+            atlas_json.meta.version,
+        );
+
+        // Variants are added in name order to keep the image layout stable
+        let mut variants = atlas_json
+            .variants
+            .into_iter()
+            .map(|(name, json_variant)| Variant::from_json(&name, json_variant))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|message| AssetError::invalid(json_path, message))?;
+        variants.sort_by(|a, b| a.name.cmp(&b.name));
+        atlas.add_variants(&variants).map_err(|message| AssetError::invalid(json_path, message))?;
+
+        for (name, json_animation) in atlas_json.animations {
+            let animation = Animation::from_json(&name, json_animation, &atlas.frames)
+                .map_err(|message| AssetError::invalid(json_path, message))?;
+            atlas.animations.insert(name, animation);
+        }
+
+        Ok(atlas)
+    }
+
+    /// Finds the image file an atlas JSON file refers to.
+    ///
+    /// Works for every [`AtlasFormat`], all of them name the image in `meta.image`.
+    ///
+    /// # Arguments
+    ///
+    /// * `json_path` - Path to the atlas JSON file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf, AssetError>` - Ok(PathBuf) of the image, Err if the JSON has none.
+    pub fn image_path<P: AsRef<Path>>(json_path: P) -> Result<PathBuf, AssetError> {
+        let json_path = json_path.as_ref();
+        let text = fs::read_to_string(json_path).map_err(|e| AssetError::io(json_path, e))?;
+        let value: serde_json::Value =
+            serde_json::from_str(&text).map_err(|e| AssetError::json(json_path, e))?;
+
+        match value.pointer("/meta/image").and_then(|image| image.as_str()) {
+            Some(image) => Ok(resolve_image(json_path, image)),
+            None => Err(AssetError::invalid(json_path, "meta.image is missing")),
+        }
+    }
+
+    /// Loads a texture atlas in strict mode.
+    ///
+    /// Same as [`Atlas::load`], but the atlas is also checked with
+    /// [`Atlas::validate`] and rejected if any problem is found.
+    ///
+    /// # Arguments
+    ///
+    /// * `json_path` - Path to the atlas JSON file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, AssetError>` - Ok(Atlas) if valid, `AssetError::Invalid` with the report otherwise.
+    pub fn load_strict<P: AsRef<Path>>(json_path: P) -> Result<Self, AssetError> {
+        let atlas = Self::load(&json_path)?;
+
+        let report = atlas.validate();
+        if !report.is_ok() {
+            return Err(AssetError::invalid(json_path, report.to_string()));
+        }
+
+        Ok(atlas)
     }
 
     /// Retrieves a frame by its name.
@@ -139,6 +653,19 @@ impl Atlas {
         self.frames.get(name)
     }
 
+    /// Retrieves an animation clip by its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the clip to retrieve
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Animation>` - Some(&Animation) if clip exists, None otherwise.
+    pub fn get_animation(&self, name: &str) -> Option<&Animation> {
+        self.animations.get(name)
+    }
+
     /// Checks if the atlas contains a frame with the given name.
     ///
     /// # Arguments
@@ -181,6 +708,7 @@ impl Atlas {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::TempDir;
 
     // Test atlas JSON parsing on example
     #[test]
@@ -214,6 +742,7 @@ mod tests {
         assert_eq!(dirt_big_frame.y, 1);
         assert_eq!(dirt_big_frame.w, 16);
         assert_eq!(dirt_big_frame.h, 16);
+        assert_eq!(dirt_big_frame.pivot, None);
 
         let grass_big_frame = atlas.get_frame("grass_tile_big_0_1").unwrap();
         assert_eq!(grass_big_frame.name, "grass_tile_big_0_1");
@@ -235,19 +764,21 @@ mod tests {
         assert_eq!(cactus_frame.y, 52);
         assert_eq!(cactus_frame.w, 10);
         assert_eq!(cactus_frame.h, 16);
+        assert_eq!(cactus_frame.pivot, Some((4, 14)));
+        assert_eq!(cactus_frame.offset, (0, 0));
 
         let fence_rising_frame = atlas.get_frame("fence_rising_11_10").unwrap();
         assert_eq!(fence_rising_frame.name, "fence_rising_11_10");
         assert_eq!(fence_rising_frame.x, 162);
         assert_eq!(fence_rising_frame.y, 153);
-        assert_eq!(fence_rising_frame.w, 16);
+        assert_eq!(fence_rising_frame.w, 14);
         assert_eq!(fence_rising_frame.h, 16);
 
         let fence_falling_frame = atlas.get_frame("fence_falling_10_10").unwrap();
         assert_eq!(fence_falling_frame.name, "fence_falling_10_10");
         assert_eq!(fence_falling_frame.x, 162);
         assert_eq!(fence_falling_frame.y, 136);
-        assert_eq!(fence_falling_frame.w, 16);
+        assert_eq!(fence_falling_frame.w, 14);
         assert_eq!(fence_falling_frame.h, 16);
 
         let mut frame_names: Vec<String> = atlas.iter_frames().map(|f| f.name.clone()).collect();
@@ -271,4 +802,156 @@ mod tests {
 
         assert!(!atlas.image.is_empty());
     }
+
+    // Test that a missing atlas file is reported as an I/O error
+    #[test]
+    fn test_load_missing_file() {
+        let err = Atlas::load("does/not/exist/atlas.json").unwrap_err();
+
+        assert!(matches!(err, AssetError::Io { .. }));
+        assert_eq!(err.path(), Path::new("does/not/exist/atlas.json"));
+    }
+
+    // Test that malformed JSON reports the position of the error
+    #[test]
+    fn test_load_malformed_json() {
+        let dir = TempDir::new("atlas_malformed");
+        let json_path = dir.join("atlas.json");
+        std::fs::write(&json_path, "{\n  \"frames\": {\n    \"a\": {\"x\": 0,}\n").unwrap();
+
+        match Atlas::load(&json_path).unwrap_err() {
+            AssetError::Json { path, line, column, .. } => {
+                assert_eq!(path, json_path);
+                assert_eq!(line, 3);
+                assert!(column > 0);
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    // Test that an image referenced by meta.image that is absent is reported
+    #[test]
+    fn test_load_missing_image() {
+        let dir = TempDir::new("atlas_missing_image");
+        let json_path = dir.join("atlas.json");
+        std::fs::write(
+            &json_path,
+            r#"{"frames": {}, "meta": {"image": "nope.png", "tile_size": 16, "version": 1}}"#,
+        )
+        .unwrap();
+
+        match Atlas::load(&json_path).unwrap_err() {
+            AssetError::MissingImage { path, image } => {
+                assert_eq!(path, json_path);
+                assert_eq!(image, dir.join("nope.png"));
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    // Test that a zero tile size is rejected as invalid content
+    #[test]
+    fn test_load_zero_tile_size() {
+        let dir = TempDir::new("atlas_zero_tile");
+        let json_path = dir.join("atlas.json");
+        std::fs::write(
+            &json_path,
+            r#"{"frames": {}, "meta": {"image": "atlas.png", "tile_size": 0, "version": 1}}"#,
+        )
+        .unwrap();
+
+        let err = Atlas::load(&json_path).unwrap_err();
+        assert!(matches!(err, AssetError::Invalid { .. }));
+        assert_eq!(err.path(), json_path);
+    }
+
+    // Test that strict loading rejects atlases with broken frames
+    #[test]
+    fn test_load_strict() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        assert!(Atlas::load_strict(project_root.join("assets/tiles/atlas.json")).is_ok());
+
+        let dir = TempDir::new("atlas_strict");
+        let json_path = dir.join("atlas.json");
+        std::fs::write(
+            &json_path,
+            format!(
+                r#"{{"frames": {{"huge": {{"x": 0, "y": 0, "w": 4096, "h": 16}}}},
+                    "meta": {{"image": "{}", "tile_size": 16, "version": 1}}}}"#,
+                project_root.join("assets/tiles/atlas.png").display()
+            ),
+        )
+        .unwrap();
+
+        assert!(Atlas::load(&json_path).is_ok());
+        match Atlas::load_strict(&json_path).unwrap_err() {
+            AssetError::Invalid { path, message } => {
+                assert_eq!(path, json_path);
+                assert!(message.contains("huge"));
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    // Test that sprite ids resolve to the named frames and pixels
+    #[test]
+    fn test_sprite_ids() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let atlas = Atlas::load(project_root.join("assets/entities/atlas.json")).unwrap();
+
+        let id = atlas.sprite_id("imp_20_1").unwrap();
+        let frame = atlas.sprite(id).unwrap();
+        assert_eq!(frame.name, "imp_20_1");
+        assert!(atlas.sprite_pixels(id).unwrap().matches(frame));
+        assert_ne!(atlas.sprite_id("imp_20_0"), Some(id));
+
+        assert!(atlas.sprite_id("missing").is_none());
+        assert!(atlas.sprite(SpriteId(atlas.frame_count() as u32)).is_none());
+    }
+
+    // Test that frame changes reach the sprites drawn by id
+    #[test]
+    fn test_insert_and_remove_frame() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let mut atlas = Atlas::load(project_root.join("assets/entities/atlas.json")).unwrap();
+
+        let mut frame = atlas.get_frame("imp_20_1").unwrap().clone();
+        frame.pivot = Some((1, 2));
+        frame.w -= 1;
+        assert!(atlas.insert_frame(frame.clone()).is_some());
+        let id = atlas.sprite_id("imp_20_1").unwrap();
+        assert_eq!(atlas.sprite(id).unwrap().pivot, Some((1, 2)));
+        assert!(atlas.sprite_pixels(id).unwrap().matches(&frame));
+
+        assert!(atlas.insert_frame(Frame::new("extra", 0, 0, 1, 1)).is_none());
+        assert!(atlas.sprite(atlas.sprite_id("extra").unwrap()).is_some());
+
+        assert_eq!(atlas.remove_frame("extra").unwrap().name, "extra");
+        assert!(atlas.sprite_id("extra").is_none());
+        assert!(atlas.remove_frame("extra").is_none());
+        assert_eq!(atlas.sprite(atlas.sprite_id("imp_20_1").unwrap()).unwrap().w, frame.w);
+    }
+
+    // Test loading atlases from memory, as with `include_bytes!`
+    #[test]
+    fn test_atlas_from_bytes() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let json = fs::read(project_root.join("assets/entities/atlas.json")).unwrap();
+        let png = fs::read(project_root.join("assets/entities/atlas.png")).unwrap();
+
+        let atlas = Atlas::from_bytes(&json, &png).unwrap();
+        let from_file = Atlas::load(project_root.join("assets/entities/atlas.json")).unwrap();
+        assert_eq!(atlas.frame_count(), from_file.frame_count());
+        assert_eq!(atlas.image, from_file.image);
+        assert!(atlas.get_animation("knight_0").is_some());
+
+        let from_reader = Atlas::from_reader(json.as_slice(), png.as_slice()).unwrap();
+        assert_eq!(from_reader.frame_count(), atlas.frame_count());
+
+        match Atlas::from_bytes(&json, b"not an image").unwrap_err() {
+            AssetError::Image { path, .. } => assert_eq!(path, Path::new("<memory>")),
+            err => panic!("unexpected error: {err}"),
+        }
+        assert!(matches!(Atlas::from_bytes(b"{", &png), Err(AssetError::Json { .. })));
+    }
 }
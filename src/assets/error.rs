@@ -0,0 +1,128 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Path reported by errors of assets loaded from memory or readers.
+pub(crate) const MEMORY_PATH: &str = "<memory>";
+
+/// Error produced while loading atlases and game maps.
+///
+/// Every variant carries the path of the file that caused the failure,
+/// so the error can be reported to the user or handled in code. Assets
+/// loaded from memory report the path `<memory>`.
+#[derive(Debug)]
+pub enum AssetError {
+    /// The file could not be opened or read
+    Io {
+        /// Path of the file that failed
+        path: PathBuf,
+        /// Underlying I/O error
+        source: io::Error,
+    },
+    /// The file is not valid JSON or does not match the expected schema
+    Json {
+        /// Path of the file that failed
+        path: PathBuf,
+        /// Line of the error (1-based)
+        line: usize,
+        /// Column of the error (1-based)
+        column: usize,
+        /// Underlying serde error
+        source: serde_json::Error,
+    },
+    /// The image referenced by `meta.image` does not exist
+    MissingImage {
+        /// Path of the atlas JSON file
+        path: PathBuf,
+        /// Resolved path of the missing image
+        image: PathBuf,
+    },
+    /// The referenced image exists but could not be decoded
+    Image {
+        /// Path of the image file
+        path: PathBuf,
+        /// Underlying image error
+        source: image::ImageError,
+    },
+    /// The file was parsed, but its content is inconsistent
+    Invalid {
+        /// Path of the file that failed
+        path: PathBuf,
+        /// Description of the problem
+        message: String,
+    },
+}
+
+impl AssetError {
+    /// Builds an error from a failed I/O operation on `path`.
+    pub(crate) fn io<P: AsRef<Path>>(path: P, source: io::Error) -> Self {
+        AssetError::Io { path: path.as_ref().to_path_buf(), source }
+    }
+
+    /// Builds an error from a failed JSON parse of `path`.
+    ///
+    /// I/O failures reported by serde are turned into [`AssetError::Io`].
+    pub(crate) fn json<P: AsRef<Path>>(path: P, source: serde_json::Error) -> Self {
+        let path = path.as_ref().to_path_buf();
+        if source.is_io() {
+            let source = io::Error::from(source);
+            return AssetError::Io { path, source };
+        }
+
+        AssetError::Json { path, line: source.line(), column: source.column(), source }
+    }
+
+    /// Builds an error for semantically invalid content of `path`.
+    pub(crate) fn invalid<P: AsRef<Path>, S: Into<String>>(path: P, message: S) -> Self {
+        AssetError::Invalid { path: path.as_ref().to_path_buf(), message: message.into() }
+    }
+
+    /// Returns the path of the file that caused the error.
+    ///
+    /// # Returns
+    ///
+    /// * `&Path` - For [`AssetError::MissingImage`] this is the atlas JSON path.
+    pub fn path(&self) -> &Path {
+        match self {
+            AssetError::Io { path, .. }
+            | AssetError::Json { path, .. }
+            | AssetError::MissingImage { path, .. }
+            | AssetError::Image { path, .. }
+            | AssetError::Invalid { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            AssetError::Json { path, source, .. } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            AssetError::MissingImage { path, image } => {
+                write!(f, "{}: image {} not found", path.display(), image.display())
+            }
+            AssetError::Image { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            AssetError::Invalid { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl Error for AssetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AssetError::Io { source, .. } => Some(source),
+            AssetError::Json { source, .. } => Some(source),
+            AssetError::Image { source, .. } => Some(source),
+            AssetError::MissingImage { .. } | AssetError::Invalid { .. } => None,
+        }
+    }
+}
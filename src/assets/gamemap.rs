@@ -1,9 +1,15 @@
-use serde::Deserialize;
-use std::collections::HashMap;
-use std::error::Error;
-use std::fs::File;
-use std::io::BufReader;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
 use std::path::Path;
+use std::str::FromStr;
+
+use super::error::MEMORY_PATH;
+use super::prefab::{JsonInstance, JsonPrefabSource};
+use super::region::JsonRegion;
+use super::spawner::JsonSpawner;
+use super::{AssetError, Prefab, PrefabInstance, PrefabSource, Properties, Region, Spawner};
 
 // TODO: delete mobs from json!
 
@@ -12,23 +18,27 @@ use std::path::Path;
 // ============================
 
 /// Behaviour data from JSON.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BehaviourJson {
     /// Type of behaviour
     #[serde(rename = "type")]
     pub behaviour_type: String,
 
     /// Direction for the behaviour
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub direction: Option<String>,
 
     /// Speed value for the behaviour
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub speed: Option<f32>,
+
+    /// Other keys, parameters of the behaviour
+    #[serde(flatten)]
+    pub params: Properties,
 }
 
 /// Mob data from JSON.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JsonMob {
     /// Starting X coordinate of the mob
     pub x_start: u32,
@@ -42,12 +52,16 @@ pub struct JsonMob {
     pub is_player: bool,
 
     /// Behaviour configuration for the mob
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub behaviour: Option<BehaviourJson>,
+
+    /// Custom game data of the mob
+    #[serde(default, skip_serializing_if = "Properties::is_empty")]
+    pub properties: Properties,
 }
 
 /// Object data from JSON.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JsonObject {
     /// X coordinate of the object
     pub x: u32,
@@ -57,16 +71,24 @@ pub struct JsonObject {
     pub asset: String,
 
     /// Indicates if the object can be collided with
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub collidable: bool,
 
     /// Indicates if the object casts a shadow
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub shadow: bool,
+
+    /// Elevation of the object in height units
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub z: u32,
+
+    /// Custom game data of the object
+    #[serde(default, skip_serializing_if = "Properties::is_empty")]
+    pub properties: Properties,
 }
 
 /// Tile data from JSON.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JsonTile {
     /// X coordinate of the tile
     pub x: u32,
@@ -74,10 +96,43 @@ pub struct JsonTile {
     pub y: u32,
     /// Asset identifier for the tile's appearance
     pub asset: String,
+
+    /// Elevation of the tile in height units
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub z: u32,
+
+    /// Asset drawn for each height unit below a raised tile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<String>,
+
+    /// Custom game data of the tile
+    #[serde(default, skip_serializing_if = "Properties::is_empty")]
+    pub properties: Properties,
+}
+
+/// Layer of a tile grid from JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JsonLayer {
+    /// Name of the layer
+    pub name: String,
+    /// Row-major tileset indices, one per cell, 0 for an empty cell
+    pub data: Vec<u32>,
+
+    /// Elevation of the whole layer in height units
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub z: u32,
+
+    /// Row-major elevations added to `z`, one per cell
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub heights: Vec<u32>,
+
+    /// Asset drawn for each height unit below raised cells
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<String>,
 }
 
 /// Meta information about the game map from JSON.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Meta {
     /// Name of the map
     pub name: String,
@@ -89,19 +144,62 @@ pub struct Meta {
     /// Map dimensions [width, height]
     #[serde(default)]
     pub size: [u32; 2],
+
+    /// Custom game data of the map
+    #[serde(default, skip_serializing_if = "Properties::is_empty")]
+    pub properties: Properties,
 }
 
 /// Complete map structure from JSON.
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct JsonMap {
-    /// Mapping of mobs' names to their definitions
-    pub mobs: HashMap<String, JsonMob>,
-    /// Mapping of objects' names to their definitions
-    pub objects: HashMap<String, JsonObject>,
-    /// Mapping of tiles' names to their definitions
-    pub tiles: HashMap<String, JsonTile>,
     /// Map meta information
     pub meta: Meta,
+    /// Tile assets referenced by layers, index 1 is the first entry
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tileset: Vec<String>,
+    /// Tile grid layers, drawn in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<JsonLayer>,
+    /// Mapping of tiles' names to their definitions
+    #[serde(default, serialize_with = "sorted")]
+    pub tiles: HashMap<String, JsonTile>,
+    /// Mapping of objects' names to their definitions
+    #[serde(serialize_with = "sorted")]
+    pub objects: HashMap<String, JsonObject>,
+    /// Mapping of mobs' names to their definitions
+    #[serde(serialize_with = "sorted")]
+    pub mobs: HashMap<String, JsonMob>,
+    /// Mapping of regions' names to their definitions
+    #[serde(default, serialize_with = "sorted", skip_serializing_if = "HashMap::is_empty")]
+    pub regions: HashMap<String, JsonRegion>,
+    /// Mapping of spawners' names to their definitions
+    #[serde(default, serialize_with = "sorted", skip_serializing_if = "HashMap::is_empty")]
+    pub spawners: HashMap<String, JsonSpawner>,
+    /// Mapping of prefabs' names to their definitions or files
+    #[serde(default, serialize_with = "sorted", skip_serializing_if = "HashMap::is_empty")]
+    pub prefabs: HashMap<String, JsonPrefabSource>,
+    /// Mapping of prefab instances' names to their placements
+    #[serde(default, serialize_with = "sorted", skip_serializing_if = "HashMap::is_empty")]
+    pub instances: HashMap<String, JsonInstance>,
+}
+
+/// Skips `false` flags when writing JSON.
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Skips zero elevations and rotations when writing JSON.
+pub(super) fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+/// Writes a map with its keys in sorted order, so saved files diff cleanly.
+pub(super) fn sorted<S: Serializer, V: Serialize>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
 }
 
 // ============================
@@ -115,13 +213,21 @@ pub enum BehaviourType {
     Controlled,
     /// Autonomous walking behaviour
     Walker,
-    /// Unknown behaviour type
-    Unknown,
+    /// Walking between waypoints
+    Patrol,
+    /// Walking to random points around the start position
+    Wander,
+    /// Walking towards the player
+    Chase,
+    /// Walking away from the player
+    Flee,
+    /// Behaviour registered by the game under this name
+    Custom(String),
 }
 
 /// Processed behaviour data for game logic.
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Behaviour {
     /// Type of behaviour
     pub behaviour_type: BehaviourType,
@@ -129,11 +235,13 @@ pub struct Behaviour {
     pub direction: Option<String>,
     /// Speed value for the behaviour
     pub speed: Option<f32>,
+    /// Parameters of the behaviour, like patrol waypoints
+    pub params: Properties,
 }
 
 /// Mob in the game world.
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Mob {
     /// Unique identifier for the mob
     pub name: String,
@@ -147,11 +255,13 @@ pub struct Mob {
     pub is_player: bool,
     /// Behaviour configuration for the mob
     pub behaviour: Option<Behaviour>,
+    /// Custom game data of the mob
+    pub properties: Properties,
 }
 
 /// Static object in the game world.
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Object {
     /// Unique identifier for the object
     pub name: String,
@@ -165,11 +275,15 @@ pub struct Object {
     pub collidable: bool,
     /// Indicates if the object casts a shadow
     pub shadow: bool,
+    /// Elevation of the object in height units
+    pub z: u32,
+    /// Custom game data of the object
+    pub properties: Properties,
 }
 
 /// Tile in the game world.
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Tile {
     /// Unique identifier for the tile
     pub name: String,
@@ -179,11 +293,32 @@ pub struct Tile {
     pub y: u32,
     /// Asset identifier for the tile's appearance
     pub asset: String,
+    /// Elevation of the tile in height units, half a tile size each
+    pub z: u32,
+    /// Asset drawn for each height unit below the tile, the tile asset if None
+    pub side: Option<String>,
+    /// Custom game data of the tile
+    pub properties: Properties,
+}
+
+/// Layer of a tile grid covering the whole map.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TileLayer {
+    /// Name of the layer
+    pub name: String,
+    /// Row-major tileset indices, `size[0] * size[1]` values, 0 for an empty cell
+    pub cells: Vec<u32>,
+    /// Elevation of the whole layer in height units
+    pub z: u32,
+    /// Row-major elevations added to `z`, empty or one per cell
+    pub heights: Vec<u32>,
+    /// Asset drawn for each height unit below raised cells, the cell asset if None
+    pub side: Option<String>,
 }
 
 /// Game map, as parsed and ready to use.
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct GameMap {
     /// Name of the map
     pub name: String,
@@ -197,6 +332,20 @@ pub struct GameMap {
     pub objects: HashMap<String, Object>,
     /// Mapping of tiles' names to their definitions
     pub tiles: HashMap<String, Tile>,
+    /// Tile assets referenced by layers, index 1 is the first entry
+    pub tileset: Vec<String>,
+    /// Tile grid layers, drawn in order above `tiles`
+    pub layers: Vec<TileLayer>,
+    /// Mapping of regions' names to their definitions
+    pub regions: HashMap<String, Region>,
+    /// Mapping of spawners' names to their definitions
+    pub spawners: HashMap<String, Spawner>,
+    /// Mapping of prefabs' names to their definitions
+    pub prefabs: HashMap<String, PrefabSource>,
+    /// Mapping of prefab instances' names to their placements, already expanded into the map
+    pub instances: HashMap<String, PrefabInstance>,
+    /// Custom game data of the map
+    pub properties: Properties,
 }
 
 // ============================
@@ -212,63 +361,245 @@ impl GameMap {
     ///
     /// # Returns
     ///
-    /// * `Result<Self, Box<dyn Error>>` - Parsed GameMap on success, error on failure.
-    pub fn load<P: AsRef<Path>>(json_path: P) -> Result<Self, Box<dyn Error>> {
-        let file = File::open(json_path)?;
+    /// * `Result<Self, AssetError>` - Parsed GameMap on success, error on failure.
+    pub fn load<P: AsRef<Path>>(json_path: P) -> Result<Self, AssetError> {
+        let json_path = json_path.as_ref();
+        let file = File::open(json_path).map_err(|e| AssetError::io(json_path, e))?;
         let reader = BufReader::new(file);
-        let map_json: JsonMap = serde_json::from_reader(reader)?;
-
-        let mut mobs = HashMap::new();
-        for (name, mob_data) in map_json.mobs {
-            let behaviour = mob_data.behaviour.as_ref().map(|b| Behaviour {
-                behaviour_type: match b.behaviour_type.as_str() {
-                    "controlled" => BehaviourType::Controlled,
-                    "walker" => BehaviourType::Walker,
-                    _ => BehaviourType::Unknown,
-                },
-                direction: b.direction.clone(),
-                speed: b.speed,
-            });
+        let map_json: JsonMap =
+            serde_json::from_reader(reader).map_err(|e| AssetError::json(json_path, e))?;
+
+        Self::from_json(json_path, map_json)
+    }
 
-            let mob = Mob {
-                name: name.clone(),
-                x_start: mob_data.x_start,
-                y_start: mob_data.y_start,
-                asset: mob_data.asset,
-                is_player: mob_data.is_player,
-                behaviour,
-            };
-            mobs.insert(name, mob);
+    /// Parses a game map from a reader of JSON data.
+    ///
+    /// Errors report the path `<memory>`. Prefabs must be defined inline,
+    /// there is no directory to read prefab files from.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Reader of the map JSON, buffered internally
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, AssetError>` - Parsed GameMap on success, error on failure.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, AssetError> {
+        let map_json: JsonMap = serde_json::from_reader(BufReader::new(reader))
+            .map_err(|e| AssetError::json(MEMORY_PATH, e))?;
+
+        Self::from_json(Path::new(MEMORY_PATH), map_json)
+    }
+
+    /// Converts parsed JSON data into a game map.
+    ///
+    /// Fails if a layer does not cover the map or refers past the end of the tileset.
+    fn from_json(json_path: &Path, map_json: JsonMap) -> Result<Self, AssetError> {
+        let mobs: HashMap<String, Mob> = map_json
+            .mobs
+            .into_iter()
+            .map(|(name, json)| (name.clone(), Mob::from_json(name, json)))
+            .collect();
+        let objects: HashMap<String, Object> = map_json
+            .objects
+            .into_iter()
+            .map(|(name, json)| (name.clone(), Object::from_json(name, json)))
+            .collect();
+        let tiles: HashMap<String, Tile> = map_json
+            .tiles
+            .into_iter()
+            .map(|(name, json)| (name.clone(), Tile::from_json(name, json)))
+            .collect();
+
+        let [width, height] = map_json.meta.size;
+        let cell_count = width as usize * height as usize;
+        let mut layers = Vec::with_capacity(map_json.layers.len());
+        for layer in map_json.layers {
+            if layer.data.len() != cell_count {
+                return Err(AssetError::invalid(
+                    json_path,
+                    format!(
+                        "layer `{}` has {} cells, the {width}x{height} map needs {cell_count}",
+                        layer.name,
+                        layer.data.len()
+                    ),
+                ));
+            }
+            if let Some(&index) =
+                layer.data.iter().find(|&&index| index as usize > map_json.tileset.len())
+            {
+                return Err(AssetError::invalid(
+                    json_path,
+                    format!(
+                        "layer `{}` refers to tileset entry {index}, the tileset has {}",
+                        layer.name,
+                        map_json.tileset.len()
+                    ),
+                ));
+            }
+            if !layer.heights.is_empty() && layer.heights.len() != cell_count {
+                return Err(AssetError::invalid(
+                    json_path,
+                    format!(
+                        "layer `{}` has {} heights, the {width}x{height} map needs {cell_count}",
+                        layer.name,
+                        layer.heights.len()
+                    ),
+                ));
+            }
+            layers.push(TileLayer {
+                name: layer.name,
+                cells: layer.data,
+                z: layer.z,
+                heights: layer.heights,
+                side: layer.side,
+            });
         }
 
-        let mut objects = HashMap::new();
-        for (name, obj_data) in map_json.objects {
-            let object = Object {
-                name: name.clone(),
-                x: obj_data.x,
-                y: obj_data.y,
-                asset: obj_data.asset,
-                collidable: obj_data.collidable,
-                shadow: obj_data.shadow,
-            };
-            objects.insert(name, object);
+        let mut regions = HashMap::with_capacity(map_json.regions.len());
+        for (name, region_data) in map_json.regions {
+            let region = Region::from_json(&name, region_data)
+                .map_err(|message| AssetError::invalid(json_path, message))?;
+            regions.insert(name, region);
         }
 
-        let mut tiles = HashMap::new();
-        for (name, tile_data) in map_json.tiles {
-            let tile =
-                Tile { name: name.clone(), x: tile_data.x, y: tile_data.y, asset: tile_data.asset };
-            tiles.insert(name, tile);
+        let mut spawners = HashMap::with_capacity(map_json.spawners.len());
+        for (name, spawner_data) in map_json.spawners {
+            let spawner = Spawner::from_json(&name, spawner_data)
+                .map_err(|message| AssetError::invalid(json_path, message))?;
+            spawners.insert(name, spawner);
         }
 
-        Ok(GameMap {
+        let mut map = GameMap {
             name: map_json.meta.name,
             tile_size: map_json.meta.tile_size,
             size: map_json.meta.size,
             mobs,
             objects,
             tiles,
-        })
+            tileset: map_json.tileset,
+            layers,
+            regions,
+            spawners,
+            prefabs: Prefab::load_all(json_path, map_json.prefabs)?,
+            instances: HashMap::with_capacity(map_json.instances.len()),
+            properties: map_json.meta.properties,
+        };
+
+        let mut instances: Vec<_> = map_json.instances.into_iter().collect();
+        instances.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, instance_data) in instances {
+            let instance = PrefabInstance::from_json(&name, instance_data)
+                .map_err(|message| AssetError::invalid(json_path, message))?;
+            let source = map.prefabs.get(&instance.prefab).ok_or_else(|| {
+                AssetError::invalid(
+                    json_path,
+                    format!("instance `{name}` places unknown prefab `{}`", instance.prefab),
+                )
+            })?;
+            let prefab = source.prefab.clone();
+            map.instantiate(&prefab, &instance)
+                .map_err(|message| AssetError::invalid(json_path, message))?;
+            map.instances.insert(name, instance);
+        }
+
+        Ok(map)
+    }
+
+    /// Writes the game map to a JSON file.
+    ///
+    /// The file holds the text of [`GameMap::to_json`], so changes to the
+    /// entries of prefab instances are not saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `json_path` - Path to the JSON file, replaced if it exists
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), AssetError>` - Ok on success, error if the file cannot be written.
+    pub fn save<P: AsRef<Path>>(&self, json_path: P) -> Result<(), AssetError> {
+        let json_path = json_path.as_ref();
+        fs::write(json_path, self.to_json()).map_err(|e| AssetError::io(json_path, e))
+    }
+
+    /// Converts the game map into pretty-printed JSON.
+    ///
+    /// Names are written in sorted order and default values are left out,
+    /// so the same map always gives the same text. Parsing the text gives
+    /// back an equal map. Prefab instances are written as placements, the
+    /// entries they expanded into are left out. Changes made to those entries
+    /// are lost, parsing expands the instances from their prefabs again. Edit
+    /// the prefab or the instance overrides instead.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - JSON text of the map, ending with a newline.
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(&self.to_json_map())
+            .expect("map JSON has only string keys");
+        json.push('\n');
+        json
+    }
+
+    /// Converts the game map into JSON-level data.
+    fn to_json_map(&self) -> JsonMap {
+        let expanded: HashSet<String> = self
+            .instances
+            .values()
+            .filter_map(|instance| {
+                let source = self.prefabs.get(&instance.prefab)?;
+                Some(instance.entry_names(&source.prefab))
+            })
+            .flatten()
+            .collect();
+        let saved = |name: &&String| !expanded.contains(name.as_str());
+
+        let mobs = self.mobs.iter().filter(|(name, _)| saved(name));
+        let objects = self.objects.iter().filter(|(name, _)| saved(name));
+        let tiles = self.tiles.iter().filter(|(name, _)| saved(name));
+
+        let layers = self.layers.iter().map(|layer| JsonLayer {
+            name: layer.name.clone(),
+            data: layer.cells.clone(),
+            z: layer.z,
+            heights: layer.heights.clone(),
+            side: layer.side.clone(),
+        });
+
+        JsonMap {
+            meta: Meta {
+                name: self.name.clone(),
+                tile_size: self.tile_size,
+                size: self.size,
+                properties: self.properties.clone(),
+            },
+            tileset: self.tileset.clone(),
+            layers: layers.collect(),
+            tiles: tiles.map(|(name, tile)| (name.clone(), tile.to_json())).collect(),
+            objects: objects.map(|(name, object)| (name.clone(), object.to_json())).collect(),
+            mobs: mobs.map(|(name, mob)| (name.clone(), mob.to_json())).collect(),
+            regions: self
+                .regions
+                .iter()
+                .map(|(name, region)| (name.clone(), region.to_json()))
+                .collect(),
+            spawners: self
+                .spawners
+                .iter()
+                .map(|(name, spawner)| (name.clone(), spawner.to_json()))
+                .collect(),
+            prefabs: self
+                .prefabs
+                .iter()
+                .map(|(name, source)| (name.clone(), source.to_json()))
+                .collect(),
+            instances: self
+                .instances
+                .iter()
+                .map(|(name, instance)| (name.clone(), instance.to_json()))
+                .collect(),
+        }
     }
 
     /// Retrieves a mob by name.
@@ -343,6 +674,76 @@ impl GameMap {
         self.tiles.len()
     }
 
+    /// Retrieves a tile grid layer by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the layer to retrieve
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&TileLayer>` - Reference to the layer if found, None otherwise.
+    pub fn get_layer(&self, name: &str) -> Option<&TileLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Retrieves the tile asset of a layer cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer` - Layer of this map
+    /// * `x` - X coordinate of the cell
+    /// * `y` - Y coordinate of the cell
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - Asset identifier, None for empty cells and cells outside the map.
+    pub fn layer_asset(&self, layer: &TileLayer, x: u32, y: u32) -> Option<&str> {
+        let [width, height] = self.size;
+        if x >= width || y >= height {
+            return None;
+        }
+        let index = *layer.cells.get((y * width + x) as usize)?;
+        let asset = self.tileset.get(index.checked_sub(1)? as usize)?;
+        Some(asset)
+    }
+
+    /// Retrieves the elevation of a layer cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer` - Layer of this map
+    /// * `x` - X coordinate of the cell
+    /// * `y` - Y coordinate of the cell
+    ///
+    /// # Returns
+    ///
+    /// * `u32` - Layer elevation plus the cell height, in height units.
+    pub fn layer_height(&self, layer: &TileLayer, x: u32, y: u32) -> u32 {
+        let index = y as usize * self.size[0] as usize + x as usize;
+        layer.z + layer.heights.get(index).copied().unwrap_or(0)
+    }
+
+    /// Returns an iterator over the non-empty cells of a layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer` - Layer of this map
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = (u32, u32, &str)>` - Iterator over (x, y, asset) in row-major order.
+    pub fn iter_layer_cells<'a>(
+        &'a self,
+        layer: &'a TileLayer,
+    ) -> impl Iterator<Item = (u32, u32, &'a str)> + 'a {
+        let width = self.size[0].max(1);
+        layer.cells.iter().enumerate().filter_map(move |(i, &index)| {
+            let asset = self.tileset.get(index.checked_sub(1)? as usize)?;
+            Some((i as u32 % width, i as u32 / width, asset.as_str()))
+        })
+    }
+
     /// Returns an iterator over all mobs in the map.
     ///
     /// # Returns
@@ -373,7 +774,117 @@ impl GameMap {
     }
 }
 
+impl FromStr for GameMap {
+    type Err = AssetError;
+
+    /// Parses a game map from JSON text.
+    ///
+    /// Errors report the path `<memory>`. Prefabs must be defined inline,
+    /// there is no directory to read prefab files from.
+    ///
+    /// # Arguments
+    ///
+    /// * `json` - Contents of the map JSON file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, AssetError>` - Parsed GameMap on success, error on failure.
+    fn from_str(json: &str) -> Result<Self, AssetError> {
+        let map_json: JsonMap =
+            serde_json::from_str(json).map_err(|e| AssetError::json(MEMORY_PATH, e))?;
+
+        Self::from_json(Path::new(MEMORY_PATH), map_json)
+    }
+}
+
+impl BehaviourType {
+    /// Parses a behaviour type from its name in map files.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the behaviour type
+    ///
+    /// # Returns
+    ///
+    /// * `BehaviourType` - Matching built-in type, `Custom` for other names.
+    pub(super) fn from_name(name: &str) -> Self {
+        match name {
+            "controlled" => BehaviourType::Controlled,
+            "walker" => BehaviourType::Walker,
+            "patrol" => BehaviourType::Patrol,
+            "wander" => BehaviourType::Wander,
+            "chase" => BehaviourType::Chase,
+            "flee" => BehaviourType::Flee,
+            _ => BehaviourType::Custom(name.to_string()),
+        }
+    }
+
+    /// Returns the name of the behaviour type in map files.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - Name parsed back by `from_name`.
+    pub fn name(&self) -> &str {
+        match self {
+            BehaviourType::Controlled => "controlled",
+            BehaviourType::Walker => "walker",
+            BehaviourType::Patrol => "patrol",
+            BehaviourType::Wander => "wander",
+            BehaviourType::Chase => "chase",
+            BehaviourType::Flee => "flee",
+            BehaviourType::Custom(name) => name,
+        }
+    }
+}
+
+impl Behaviour {
+    /// Builds a behaviour from its JSON definition.
+    pub(super) fn from_json(json: &BehaviourJson) -> Self {
+        Behaviour {
+            behaviour_type: BehaviourType::from_name(&json.behaviour_type),
+            direction: json.direction.clone(),
+            speed: json.speed,
+            params: json.params.clone(),
+        }
+    }
+
+    /// Converts the behaviour back into its JSON definition.
+    pub(super) fn to_json(&self) -> BehaviourJson {
+        BehaviourJson {
+            behaviour_type: self.behaviour_type.name().to_string(),
+            direction: self.direction.clone(),
+            speed: self.speed,
+            params: self.params.clone(),
+        }
+    }
+}
+
 impl Mob {
+    /// Builds a mob from its JSON definition.
+    pub(super) fn from_json(name: String, json: JsonMob) -> Self {
+        Mob {
+            name,
+            x_start: json.x_start,
+            y_start: json.y_start,
+            asset: json.asset,
+            is_player: json.is_player,
+            behaviour: json.behaviour.as_ref().map(Behaviour::from_json),
+            properties: json.properties,
+        }
+    }
+
+    /// Converts the mob back into its JSON definition.
+    pub(super) fn to_json(&self) -> JsonMob {
+        JsonMob {
+            x_start: self.x_start,
+            y_start: self.y_start,
+            asset: self.asset.clone(),
+            is_player: self.is_player,
+            behaviour: self.behaviour.as_ref().map(Behaviour::to_json),
+            properties: self.properties.clone(),
+        }
+    }
+
     /// Returns the start position of the mob.
     ///
     /// # Returns
@@ -386,6 +897,33 @@ impl Mob {
 }
 
 impl Object {
+    /// Builds an object from its JSON definition.
+    pub(super) fn from_json(name: String, json: JsonObject) -> Self {
+        Object {
+            name,
+            x: json.x,
+            y: json.y,
+            asset: json.asset,
+            collidable: json.collidable,
+            shadow: json.shadow,
+            z: json.z,
+            properties: json.properties,
+        }
+    }
+
+    /// Converts the object back into its JSON definition.
+    pub(super) fn to_json(&self) -> JsonObject {
+        JsonObject {
+            x: self.x,
+            y: self.y,
+            asset: self.asset.clone(),
+            collidable: self.collidable,
+            shadow: self.shadow,
+            z: self.z,
+            properties: self.properties.clone(),
+        }
+    }
+
     /// Returns the position of the object.
     ///
     /// # Returns
@@ -398,6 +936,31 @@ impl Object {
 }
 
 impl Tile {
+    /// Builds a tile from its JSON definition.
+    pub(super) fn from_json(name: String, json: JsonTile) -> Self {
+        Tile {
+            name,
+            x: json.x,
+            y: json.y,
+            asset: json.asset,
+            z: json.z,
+            side: json.side,
+            properties: json.properties,
+        }
+    }
+
+    /// Converts the tile back into its JSON definition.
+    pub(super) fn to_json(&self) -> JsonTile {
+        JsonTile {
+            x: self.x,
+            y: self.y,
+            asset: self.asset.clone(),
+            z: self.z,
+            side: self.side.clone(),
+            properties: self.properties.clone(),
+        }
+    }
+
     /// Returns the position of the tile.
     ///
     /// # Returns
@@ -416,6 +979,8 @@ impl Tile {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::assets::Property;
+    use crate::test_util::TempDir;
     use std::path::PathBuf;
 
     // Test game map parsing on example
@@ -440,7 +1005,7 @@ mod tests {
         assert_eq!(player.x_start, 0);
         assert_eq!(player.y_start, 0);
         assert_eq!(player.asset, "knight_0_0");
-        assert_eq!(player.is_player, true);
+        assert!(player.is_player);
         assert!(player.behaviour.is_some());
 
         let player_behaviour = player.behaviour.as_ref().unwrap();
@@ -454,7 +1019,7 @@ mod tests {
         assert_eq!(mob_1.x_start, 440);
         assert_eq!(mob_1.y_start, 470);
         assert_eq!(mob_1.asset, "imp_20_0");
-        assert_eq!(mob_1.is_player, false);
+        assert!(!mob_1.is_player);
         assert!(mob_1.behaviour.is_some());
 
         let mob_1_behaviour = mob_1.behaviour.as_ref().unwrap();
@@ -468,7 +1033,7 @@ mod tests {
         assert_eq!(mob_2.x_start, 400);
         assert_eq!(mob_2.y_start, 460);
         assert_eq!(mob_2.asset, "ghost_30_0");
-        assert_eq!(mob_2.is_player, false);
+        assert!(!mob_2.is_player);
         assert!(mob_2.behaviour.is_some());
 
         let mob_2_behaviour = mob_2.behaviour.as_ref().unwrap();
@@ -482,7 +1047,7 @@ mod tests {
         assert_eq!(mob_4.x_start, 420);
         assert_eq!(mob_4.y_start, 470);
         assert_eq!(mob_4.asset, "imp_20_0");
-        assert_eq!(mob_4.is_player, false);
+        assert!(!mob_4.is_player);
         assert!(mob_4.behaviour.is_some());
 
         let mob_5 = game_map.get_mob("mob_5").unwrap();
@@ -490,7 +1055,7 @@ mod tests {
         assert_eq!(mob_5.x_start, 493);
         assert_eq!(mob_5.y_start, 470);
         assert_eq!(mob_5.asset, "imp_20_0");
-        assert_eq!(mob_5.is_player, false);
+        assert!(!mob_5.is_player);
         assert!(mob_5.behaviour.is_some());
 
         let mob_6 = game_map.get_mob("mob_6").unwrap();
@@ -498,7 +1063,7 @@ mod tests {
         assert_eq!(mob_6.x_start, 540);
         assert_eq!(mob_6.y_start, 470);
         assert_eq!(mob_6.asset, "imp_20_0");
-        assert_eq!(mob_6.is_player, false);
+        assert!(!mob_6.is_player);
         assert!(mob_6.behaviour.is_some());
 
         let obj_1 = game_map.get_object("obj_1").unwrap();
@@ -506,8 +1071,8 @@ mod tests {
         assert_eq!(obj_1.x, 2);
         assert_eq!(obj_1.y, 1);
         assert_eq!(obj_1.asset, "cactus_long_3_9");
-        assert_eq!(obj_1.collidable, false);
-        assert_eq!(obj_1.shadow, false);
+        assert!(!obj_1.collidable);
+        assert!(!obj_1.shadow);
         assert_eq!(obj_1.position(), (2, 1));
 
         let obj_2 = game_map.get_object("obj_2").unwrap();
@@ -515,8 +1080,8 @@ mod tests {
         assert_eq!(obj_2.x, 4);
         assert_eq!(obj_2.y, 14);
         assert_eq!(obj_2.asset, "fence_rising_11_10");
-        assert_eq!(obj_2.collidable, false);
-        assert_eq!(obj_2.shadow, false);
+        assert!(!obj_2.collidable);
+        assert!(!obj_2.shadow);
         assert_eq!(obj_2.position(), (4, 14));
 
         let obj_3 = game_map.get_object("obj_3").unwrap();
@@ -524,8 +1089,8 @@ mod tests {
         assert_eq!(obj_3.x, 8);
         assert_eq!(obj_3.y, 15);
         assert_eq!(obj_3.asset, "fence_falling_10_10");
-        assert_eq!(obj_3.collidable, false);
-        assert_eq!(obj_3.shadow, false);
+        assert!(!obj_3.collidable);
+        assert!(!obj_3.shadow);
         assert_eq!(obj_3.position(), (8, 15));
 
         let tile_1 = game_map.get_tile("tile_1").unwrap();
@@ -562,4 +1127,239 @@ mod tests {
         assert!(tile_names.contains(&"tile_1".to_string()));
         assert!(tile_names.contains(&"tile_625".to_string()));
     }
+
+    // Test that map errors carry the offending path
+    #[test]
+    fn test_load_game_map_errors() {
+        let err = GameMap::load("does/not/exist/map.json").unwrap_err();
+        assert!(matches!(err, AssetError::Io { .. }));
+        assert_eq!(err.path(), Path::new("does/not/exist/map.json"));
+
+        let dir = TempDir::new("map_errors");
+        let map_path = dir.join("map.json");
+        std::fs::write(&map_path, r#"{"meta": {"name": "broken"}, "tiles": {}}"#).unwrap();
+
+        match GameMap::load(&map_path).unwrap_err() {
+            AssetError::Json { path, line, .. } => {
+                assert_eq!(path, map_path);
+                assert_eq!(line, 1);
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    // Test parsing maps from strings and readers
+    #[test]
+    fn test_game_map_from_memory() {
+        let json = r#"{
+            "meta": {"name": "inline", "tile_size": 16, "size": [2, 2]},
+            "tiles": {"t": {"x": 1, "y": 0, "asset": "grass"}},
+            "objects": {},
+            "mobs": {"p": {"x_start": 0, "y_start": 0, "asset": "hero", "is_player": true}}
+        }"#;
+
+        let map = GameMap::from_str(json).unwrap();
+        assert_eq!(map.name, "inline");
+        assert_eq!(map.get_tile("t").unwrap().asset, "grass");
+
+        let from_reader = GameMap::from_reader(json.as_bytes()).unwrap();
+        assert_eq!(from_reader, map);
+
+        let err = "{".parse::<GameMap>().unwrap_err();
+        assert!(matches!(err, AssetError::Json { .. }));
+        assert_eq!(err.path(), Path::new("<memory>"));
+    }
+
+    // Test that custom properties keep their types and survive saving
+    #[test]
+    fn test_map_properties() {
+        let json = r#"{
+            "meta": {"name": "props", "tile_size": 16, "size": [2, 2],
+                     "properties": {"music": "cave.ogg", "gravity": 9.5}},
+            "tiles": {"t": {"x": 1, "y": 0, "asset": "grass", "properties": {"slow": true}}},
+            "objects": {"chest": {"x": 0, "y": 1, "asset": "chest",
+                                  "properties": {"loot": ["gold", 5], "weight": 2.0}}},
+            "mobs": {"p": {"x_start": 0, "y_start": 0, "asset": "hero", "is_player": true,
+                           "properties": {"health": 10}}}
+        }"#;
+
+        let map = GameMap::from_str(json).unwrap();
+        assert_eq!(map.properties.get_str("music"), Some("cave.ogg"));
+        assert_eq!(map.properties.get_f64("gravity"), Some(9.5));
+        assert_eq!(map.get_tile("t").unwrap().properties.get_bool("slow"), Some(true));
+        let chest = &map.get_object("chest").unwrap().properties;
+        let loot = [Property::String("gold".to_string()), Property::Int(5)];
+        assert_eq!(chest.get_array("loot"), Some(&loot[..]));
+        assert_eq!(chest.get("weight"), Some(&Property::Float(2.0)));
+        assert_eq!(map.get_mob("p").unwrap().properties.get_i64("health"), Some(10));
+
+        let saved = map.to_json();
+        assert!(saved.contains("\"weight\": 2.0"), "{saved}");
+        assert_eq!(GameMap::from_str(&saved).unwrap(), map);
+
+        let plain = GameMap::from_str(&saved.replace("\"health\": 10", "\"health\": 10.5"));
+        assert_eq!(plain.unwrap().get_mob("p").unwrap().properties.get_i64("health"), None);
+        assert!(GameMap::from_str(&saved.replace("\"slow\": true", "\"slow\": null")).is_err());
+    }
+
+    // Test loading the layered example and reading its cells
+    #[test]
+    fn test_load_layered_map() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let game_map = GameMap::load(project_root.join("examples/layered.json")).unwrap();
+        let legacy = GameMap::load(project_root.join("examples/input.json")).unwrap();
+
+        assert_eq!(game_map.tile_count(), 0);
+        assert_eq!(game_map.layers.len(), 3);
+        assert_eq!(game_map.mobs, legacy.mobs);
+        assert_eq!(game_map.objects, legacy.objects);
+
+        let ground = game_map.get_layer("ground").unwrap();
+        assert_eq!(game_map.iter_layer_cells(ground).count(), 625);
+        assert_eq!(game_map.layer_asset(ground, 0, 0), Some("grass_tile_big_0_1"));
+        assert_eq!(game_map.layer_asset(ground, 5, 12), Some("dirt_tile_big_0_0"));
+        assert_eq!(game_map.layer_asset(ground, 25, 0), None);
+
+        let decoration = game_map.get_layer("decoration").unwrap();
+        assert_eq!(game_map.layer_asset(decoration, 0, 0), None);
+        assert_eq!(game_map.layer_asset(decoration, 3, 5), Some("rock_tile_small_1_2"));
+        let cells: Vec<_> = game_map.iter_layer_cells(decoration).collect();
+        assert_eq!(cells.len(), 5);
+        assert_eq!(cells[0], (10, 3, "rock_tile_small_1_2"));
+        assert!(game_map.get_layer("overlay").is_none());
+
+        let plateau = game_map.get_layer("plateau").unwrap();
+        assert_eq!(plateau.side.as_deref(), Some("rock_tile_big_0_2"));
+        assert_eq!(game_map.layer_asset(plateau, 18, 4), Some("rock_tile_big_0_2"));
+        assert_eq!(game_map.layer_height(plateau, 18, 4), 2);
+        assert_eq!(game_map.layer_height(plateau, 19, 7), 1);
+        assert_eq!(game_map.layer_height(ground, 18, 4), 0);
+
+        let oasis = game_map.get_region("oasis").unwrap();
+        assert!(oasis.has_tag("water"));
+        assert!(oasis.shape.contains(18.5, 20.5) && !oasis.shape.contains(15.5, 20.5));
+        assert!(legacy.regions.is_empty());
+    }
+
+    // Test elevation fields of tiles and objects
+    #[test]
+    fn test_elevation() {
+        let json = r#"{
+            "meta": {"name": "hills", "tile_size": 16, "size": [2, 1]},
+            "tiles": {
+                "low": {"x": 0, "y": 0, "asset": "grass"},
+                "high": {"x": 1, "y": 0, "asset": "grass", "z": 2, "side": "cliff"}
+            },
+            "objects": {"tree": {"x": 1, "y": 0, "asset": "tree", "z": 2}},
+            "mobs": {},
+            "tileset": ["grass"],
+            "layers": [{"name": "g", "data": [1, 1], "heights": [1]}]
+        }"#;
+
+        let err = GameMap::from_str(json).unwrap_err();
+        assert!(err.to_string().contains("1 heights"), "{err}");
+
+        let game_map = GameMap::from_str(&json.replace(r#""heights": [1]"#, r#""z": 3"#)).unwrap();
+        let high = game_map.get_tile("high").unwrap();
+        assert_eq!((high.z, high.side.as_deref()), (2, Some("cliff")));
+        assert_eq!(game_map.get_tile("low").unwrap().z, 0);
+        assert_eq!(game_map.get_object("tree").unwrap().z, 2);
+        assert_eq!(game_map.layer_height(&game_map.layers[0], 1, 0), 3);
+
+        let json = game_map.to_json();
+        assert_eq!(GameMap::from_str(&json).unwrap(), game_map);
+        assert_eq!(json.matches("\"z\"").count(), 3);
+    }
+
+    // Test that layers must cover the map and stay within the tileset
+    #[test]
+    fn test_layer_errors() {
+        let map = |data: &str| {
+            format!(
+                r#"{{"meta": {{"name": "m", "size": [2, 2]}}, "objects": {{}}, "mobs": {{}},
+                    "tiles": {{"t": {{"x": 0, "y": 0, "asset": "grass"}}}},
+                    "tileset": ["grass"], "layers": [{{"name": "ground", "data": {data}}}]}}"#
+            )
+        };
+
+        let game_map = GameMap::from_str(&map("[0, 1, 1, 0]")).unwrap();
+        assert_eq!(game_map.tile_count(), 1);
+        assert_eq!(game_map.iter_layer_cells(&game_map.layers[0]).count(), 2);
+
+        let err = GameMap::from_str(&map("[1, 1, 1]")).unwrap_err();
+        assert!(matches!(err, AssetError::Invalid { .. }));
+        assert!(err.to_string().contains("needs 4"), "{err}");
+
+        let err = GameMap::from_str(&map("[1, 1, 1, 2]")).unwrap_err();
+        assert!(err.to_string().contains("entry 2"), "{err}");
+    }
+
+    // Test that saved maps load back unchanged and are written deterministically
+    #[test]
+    fn test_save_round_trip() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let dir = TempDir::new("map_save");
+
+        for example in ["examples/input.json", "examples/layered.json"] {
+            let game_map = GameMap::load(project_root.join(example)).unwrap();
+            let saved_path = dir.join("saved.json");
+            game_map.save(&saved_path).unwrap();
+
+            let reloaded = GameMap::load(&saved_path).unwrap();
+            assert_eq!(reloaded, game_map, "{example}");
+            assert_eq!(reloaded.to_json(), game_map.to_json());
+        }
+
+        let game_map = GameMap::load(project_root.join("examples/input.json")).unwrap();
+        let json = game_map.to_json();
+        let position = |key: &str| json.find(&format!("\"{key}\"")).unwrap();
+        assert!(position("meta") < position("tiles"));
+        assert!(position("mob_1") < position("mob_2") && position("mob_6") < position("player"));
+        assert!(json.contains("\"type\": \"walker\"") && !json.contains("\"layers\""));
+
+        // Behaviour types unknown to the engine keep their names
+        let json = r#"{
+            "meta": {"name": "custom", "tile_size": 16, "size": [1, 1]},
+            "objects": {},
+            "mobs": {"m": {"x_start": 0, "y_start": 0, "asset": "imp",
+                           "behaviour": {"type": "dance", "speed": 1.0}}}
+        }"#;
+        let custom = GameMap::from_str(json).unwrap();
+        let behaviour = custom.get_mob("m").unwrap().behaviour.as_ref().unwrap();
+        assert_eq!(behaviour.behaviour_type, BehaviourType::Custom("dance".into()));
+        assert!(custom.to_json().contains("\"type\": \"dance\""));
+        assert_eq!(GameMap::from_str(&custom.to_json()).unwrap(), custom);
+
+        // Entries of inline prefabs and instance overrides are sorted as well
+        let json = r#"{
+            "meta": {"name": "grove", "tile_size": 16, "size": [8, 8]},
+            "objects": {},
+            "mobs": {},
+            "prefabs": {"grove": {
+                "tiles": {"moss_b": {"x": 1, "y": 0, "asset": "moss"},
+                          "moss_a": {"x": 0, "y": 0, "asset": "moss"}},
+                "objects": {"rock_c": {"x": 2, "y": 1, "asset": "rock"},
+                            "rock_a": {"x": 0, "y": 1, "asset": "rock"},
+                            "rock_b": {"x": 1, "y": 1, "asset": "rock"}}
+            }},
+            "instances": {"g": {"prefab": "grove", "x": 1, "y": 1, "overrides": {
+                "rock_c": {"size": 3}, "rock_a": {"size": 1}, "rock_b": {"size": 2}
+            }}}
+        }"#;
+        let saved: Vec<String> =
+            (0..8).map(|_| GameMap::from_str(json).unwrap().to_json()).collect();
+        assert!(saved.iter().all(|json| *json == saved[0]));
+        let (prefabs, overrides) = saved[0].split_at(saved[0].find("\"overrides\"").unwrap());
+        for text in [prefabs, overrides] {
+            let position = |key: &str| text.find(&format!("\"{key}\"")).unwrap();
+            assert!(
+                position("rock_a") < position("rock_b") && position("rock_b") < position("rock_c")
+            );
+        }
+        assert!(prefabs.find("\"moss_a\"") < prefabs.find("\"moss_b\""));
+        assert_eq!(GameMap::from_str(&saved[0]).unwrap(), GameMap::from_str(json).unwrap());
+
+        let err = game_map.save(dir.join("missing/map.json")).unwrap_err();
+        assert!(matches!(err, AssetError::Io { .. }));
+    }
 }
@@ -0,0 +1,332 @@
+use image::RgbaImage;
+use std::collections::HashMap;
+
+use super::atlas::opaque_bounds;
+use super::{Animation, AnimationFrame, Atlas, Frame, PlaybackMode};
+
+/// Default duration of a frame in clips created from aliases, in seconds.
+const ALIAS_FRAME_DURATION: f32 = 0.1;
+
+// ============================
+// Game-level structs
+// ============================
+
+/// Name given to a range of grid cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridAlias {
+    /// Name of the alias
+    pub name: String,
+    /// First cell of the range as (row, column)
+    pub first: (u32, u32),
+    /// Last cell of the range as (row, column), inclusive
+    pub last: (u32, u32),
+}
+
+/// Naming rules for frames generated by [`Atlas::from_grid`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GridNaming {
+    /// Frame name pattern with `{row}`, `{col}` and `{index}` placeholders
+    pub pattern: String,
+    /// Extra names for ranges of cells
+    pub aliases: Vec<GridAlias>,
+}
+
+impl Default for GridNaming {
+    /// Creates naming rules producing `row_col` names without aliases.
+    ///
+    /// # Returns
+    ///
+    /// A new `GridNaming` instance with `{row}_{col}` pattern.
+    fn default() -> Self {
+        Self { pattern: "{row}_{col}".to_string(), aliases: Vec::new() }
+    }
+}
+
+// ============================
+// Implementation
+// ============================
+
+impl GridAlias {
+    /// Creates an alias for a range of cells.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the alias
+    /// * `first` - First cell of the range as (row, column)
+    /// * `last` - Last cell of the range as (row, column), inclusive
+    ///
+    /// # Returns
+    ///
+    /// A new `GridAlias` instance.
+    pub fn new(name: &str, first: (u32, u32), last: (u32, u32)) -> Self {
+        Self { name: name.to_string(), first, last }
+    }
+}
+
+impl GridNaming {
+    /// Creates naming rules with the given pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Frame name pattern, like `"cell_{row}_{col}"`
+    ///
+    /// # Returns
+    ///
+    /// A new `GridNaming` instance without aliases.
+    pub fn new(pattern: &str) -> Self {
+        Self { pattern: pattern.to_string(), aliases: Vec::new() }
+    }
+
+    /// Adds an alias for a range of cells.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the alias
+    /// * `first` - First cell of the range as (row, column)
+    /// * `last` - Last cell of the range as (row, column), inclusive
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Naming rules with the added alias.
+    pub fn with_alias(mut self, name: &str, first: (u32, u32), last: (u32, u32)) -> Self {
+        self.aliases.push(GridAlias::new(name, first, last));
+        self
+    }
+
+    /// Builds the frame name of a cell.
+    fn frame_name(&self, row: u32, col: u32, index: u32) -> String {
+        self.pattern
+            .replace("{row}", &row.to_string())
+            .replace("{col}", &col.to_string())
+            .replace("{index}", &index.to_string())
+    }
+}
+
+impl Atlas {
+    /// Creates an atlas from a sprite sheet made of equally sized cells.
+    ///
+    /// Every cell with at least one non-transparent pixel becomes a frame.
+    /// Cells are counted row by row, `{index}` counts all cells, empty included.
+    ///
+    /// Each alias covering one cell adds a frame under the alias name.
+    /// Aliases covering several cells add frames `name_0`, `name_1`, ... for
+    /// the non-empty cells of the range, in row-major order, and a looping
+    /// animation clip `name` over them.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - Sprite sheet image
+    /// * `cell_w` - Width of a cell in pixels, also used as tile size
+    /// * `cell_h` - Height of a cell in pixels
+    /// * `margin` - Empty pixels around the whole sheet
+    /// * `spacing` - Empty pixels between neighbouring cells
+    /// * `naming` - Frame naming rules
+    ///
+    /// # Returns
+    ///
+    /// * `Atlas` - Atlas with generated frames. Call [`Atlas::trim_frames`]
+    ///   to shrink them to their opaque bounds.
+    pub fn from_grid(
+        image: RgbaImage,
+        cell_w: u32,
+        cell_h: u32,
+        margin: u32,
+        spacing: u32,
+        naming: &GridNaming,
+    ) -> Self {
+        let (image_w, image_h) = image.dimensions();
+        let count = |size: u32, cell: u32| {
+            if cell == 0 || size < 2 * margin + cell {
+                0
+            } else {
+                (size - 2 * margin + spacing) / (cell + spacing)
+            }
+        };
+        let cols = count(image_w, cell_w);
+        let rows = count(image_h, cell_h);
+
+        let mut frames = HashMap::new();
+        let mut cells: HashMap<(u32, u32), Frame> = HashMap::new();
+        for row in 0..rows {
+            for col in 0..cols {
+                let x = margin + col * (cell_w + spacing);
+                let y = margin + row * (cell_h + spacing);
+                if opaque_bounds(&image, x, y, cell_w, cell_h).is_none() {
+                    continue;
+                }
+
+                let name = naming.frame_name(row, col, row * cols + col);
+                let frame = Frame::new(&name, x, y, cell_w, cell_h);
+                cells.insert((row, col), frame.clone());
+                frames.insert(name, frame);
+            }
+        }
+
+        let mut animations = HashMap::new();
+        for alias in &naming.aliases {
+            let first = alias.first.0 * cols + alias.first.1;
+            let last = alias.last.0 * cols + alias.last.1;
+            let range: Vec<&Frame> = (first..=last)
+                .filter_map(|index| cells.get(&(index / cols.max(1), index % cols.max(1))))
+                .collect();
+
+            if first == last {
+                if let Some(frame) = range.first() {
+                    frames.insert(
+                        alias.name.clone(),
+                        Frame { name: alias.name.clone(), ..(*frame).clone() },
+                    );
+                }
+                continue;
+            }
+
+            let mut clip_frames = Vec::with_capacity(range.len());
+            for (i, frame) in range.into_iter().enumerate() {
+                let name = format!("{}_{}", alias.name, i);
+                frames.insert(name.clone(), Frame { name: name.clone(), ..frame.clone() });
+                clip_frames.push(AnimationFrame { frame: name, duration: ALIAS_FRAME_DURATION });
+            }
+            if !clip_frames.is_empty() {
+                let clip = Animation {
+                    name: alias.name.clone(),
+                    frames: clip_frames,
+                    mode: PlaybackMode::Loop,
+                };
+                animations.insert(alias.name.clone(), clip);
+            }
+        }
+
+        Atlas::new(image, frames, animations, cell_w, 1)
+    }
+
+    /// Shrinks every frame to the bounds of its non-transparent pixels.
+    ///
+    /// Trim offset and untrimmed size are recorded, so trimmed frames are
+    /// drawn at the same place as before. Fully transparent frames are kept.
+    pub fn trim_frames(&mut self) {
+        for frame in self.frames.values_mut() {
+            let Some((x, y, w, h)) = opaque_bounds(&self.image, frame.x, frame.y, frame.w, frame.h)
+            else {
+                continue;
+            };
+
+            frame.source_size = Some(frame.untrimmed_size());
+            frame.offset.0 += (x - frame.x) as i32;
+            frame.offset.1 += (y - frame.y) as i32;
+            frame.x = x;
+            frame.y = y;
+            frame.w = w;
+            frame.h = h;
+        }
+        self.decode_frames();
+    }
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+    use std::path::PathBuf;
+
+    // 3x2 sheet of 8x8 cells with 1px margin and 2px spacing, cell (1, 1) empty
+    fn sheet() -> RgbaImage {
+        let mut image = RgbaImage::new(1 + 3 * 8 + 2 * 2 + 1, 1 + 2 * 8 + 2 + 1);
+        for (row, col) in [(0, 0), (0, 1), (0, 2), (1, 0), (1, 2)] {
+            let x = 1 + col * 10;
+            let y = 1 + row * 10;
+            // Opaque 4x6 block in the bottom-centre of the cell
+            for dy in 2..8 {
+                for dx in 2..6 {
+                    image.put_pixel(x + dx, y + dy, Rgba([row as u8, col as u8, 0, 255]));
+                }
+            }
+        }
+        image
+    }
+
+    // Test frame generation with margin, spacing and empty cells
+    #[test]
+    fn test_from_grid() {
+        let atlas = Atlas::from_grid(sheet(), 8, 8, 1, 2, &GridNaming::new("cell_{row}_{col}"));
+
+        assert_eq!(atlas.frame_count(), 5);
+        assert_eq!(atlas.tile_size, 8);
+        assert!(!atlas.contains_frame("cell_1_1"));
+
+        let frame = atlas.get_frame("cell_1_2").unwrap();
+        assert_eq!((frame.x, frame.y, frame.w, frame.h), (21, 11, 8, 8));
+        assert!(atlas.validate().is_ok(), "{}", atlas.validate());
+
+        let indexed = Atlas::from_grid(sheet(), 8, 8, 1, 2, &GridNaming::new("s{index}"));
+        let mut names: Vec<&str> = indexed.frames.keys().map(|n| n.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["s0", "s1", "s2", "s3", "s5"]);
+    }
+
+    // Test aliases for single cells and ranges
+    #[test]
+    fn test_from_grid_aliases() {
+        let naming = GridNaming::default().with_alias("hero", (0, 1), (0, 1)).with_alias(
+            "walk",
+            (0, 2),
+            (1, 2),
+        );
+        let atlas = Atlas::from_grid(sheet(), 8, 8, 1, 2, &naming);
+
+        let hero = atlas.get_frame("hero").unwrap();
+        assert_eq!((hero.x, hero.y), (11, 1));
+
+        // Range (0, 2)..=(1, 2) covers (0, 2), (1, 0) and (1, 2), (1, 1) is empty
+        let walk = atlas.get_animation("walk").unwrap();
+        let clip_frames: Vec<&str> = walk.frames.iter().map(|f| f.frame.as_str()).collect();
+        assert_eq!(clip_frames, vec!["walk_0", "walk_1", "walk_2"]);
+        assert_eq!(atlas.get_frame("walk_1").unwrap().x, 1);
+        assert_eq!(atlas.get_frame("walk_2").unwrap().x, 21);
+        assert!(atlas.contains_frame("0_2"));
+
+        // Aliases share the rectangles of their cells without overlapping them
+        assert!(atlas.validate().is_ok(), "{}", atlas.validate());
+    }
+
+    // Test that trimming keeps frames aligned
+    #[test]
+    fn test_trim_frames() {
+        let mut atlas = Atlas::from_grid(sheet(), 8, 8, 1, 2, &GridNaming::default());
+        let before = atlas.get_frame("1_0").unwrap().clone();
+        let default_pivot = (4, 8);
+        let origin_before = before.origin(50, 50, default_pivot);
+
+        atlas.trim_frames();
+
+        let after = atlas.get_frame("1_0").unwrap();
+        assert_eq!((after.x, after.y, after.w, after.h), (before.x + 2, before.y + 2, 4, 6));
+        assert_eq!(after.offset, (2, 2));
+        assert_eq!(after.untrimmed_size(), (8, 8));
+        assert_eq!(after.origin(50, 50, default_pivot), (origin_before.0 + 2, origin_before.1 + 2));
+        assert!(atlas.validate().is_ok());
+    }
+
+    // Test slicing the bundled entities sheet
+    #[test]
+    fn test_from_grid_entities_sheet() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let image = image::open(
+            project_root.join("assets/entities/IsometricTRPGAssetPack_OutlinedEntities.png"),
+        )
+        .unwrap()
+        .to_rgba8();
+
+        let mut atlas = Atlas::from_grid(image, 16, 16, 0, 0, &GridNaming::default());
+        atlas.trim_frames();
+
+        assert!(atlas.frame_count() > 0);
+        for frame in atlas.iter_frames() {
+            assert!(frame.w <= 16 && frame.h <= 16);
+        }
+        assert!(atlas.validate().is_ok(), "{}", atlas.validate());
+    }
+}
@@ -0,0 +1,543 @@
+use image::{imageops, RgbaImage};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use super::atlas::append_sprites;
+use super::{Animation, AnimationFrame, AssetError, Atlas, Frame, PlaybackMode};
+
+/// Duration of frames and clips without explicit timing, in seconds.
+const DEFAULT_FRAME_DURATION: f32 = 0.1;
+
+// ============================
+// JSON-level structs
+// ============================
+
+/// Rectangle in Aseprite and TexturePacker JSON.
+#[derive(Deserialize, Debug, Clone, Copy)]
+struct SheetRect {
+    x: i32,
+    y: i32,
+    w: u32,
+    h: u32,
+}
+
+/// Size in Aseprite and TexturePacker JSON.
+#[derive(Deserialize, Debug, Clone, Copy)]
+struct SheetSize {
+    w: u32,
+    h: u32,
+}
+
+/// Point relative to the sprite size, 0.0 to 1.0 on both axes.
+#[derive(Deserialize, Debug, Clone, Copy)]
+struct SheetPoint {
+    x: f32,
+    y: f32,
+}
+
+/// Frame of an Aseprite or TexturePacker sheet.
+#[derive(Deserialize, Debug)]
+struct SheetFrame {
+    /// Frame name, only present in the array layout
+    #[serde(default)]
+    filename: Option<String>,
+    /// Area of the frame in the sheet image, unrotated size if `rotated`
+    frame: SheetRect,
+    /// Whether the frame is stored rotated 90 degrees clockwise
+    #[serde(default)]
+    rotated: bool,
+    /// Area of the trimmed frame inside the untrimmed sprite
+    #[serde(rename = "spriteSourceSize", default)]
+    sprite_source_size: Option<SheetRect>,
+    /// Size of the untrimmed sprite
+    #[serde(rename = "sourceSize", default)]
+    source_size: Option<SheetSize>,
+    /// Duration of the frame in milliseconds
+    #[serde(default)]
+    duration: Option<f32>,
+    /// Anchor point of the sprite
+    #[serde(default)]
+    pivot: Option<SheetPoint>,
+}
+
+/// Frames of a sheet in file order, from either the array or the hash layout.
+#[derive(Debug)]
+struct SheetFrames(Vec<(String, SheetFrame)>);
+
+/// Frame tag of an Aseprite sheet.
+#[derive(Deserialize, Debug)]
+struct SheetTag {
+    /// Name of the tag, used as clip name
+    name: String,
+    /// Index of the first frame
+    from: usize,
+    /// Index of the last frame, inclusive
+    to: usize,
+    /// `forward`, `reverse`, `pingpong` or `pingpong_reverse`
+    #[serde(default)]
+    direction: Option<String>,
+    /// Number of passes, stored as a string by Aseprite
+    #[serde(default)]
+    repeat: Option<serde_json::Value>,
+}
+
+/// Meta information of a sheet.
+#[derive(Deserialize, Debug)]
+struct SheetMeta {
+    /// Path to the sheet image file
+    image: String,
+    /// Aseprite frame tags
+    #[serde(rename = "frameTags", default)]
+    frame_tags: Vec<SheetTag>,
+}
+
+/// Root structure of an Aseprite or TexturePacker sheet.
+#[derive(Deserialize, Debug)]
+struct SheetJson {
+    frames: SheetFrames,
+    /// Named frame lists, as exported by some TexturePacker targets
+    #[serde(default)]
+    animations: HashMap<String, Vec<String>>,
+    meta: SheetMeta,
+}
+
+impl<'de> Deserialize<'de> for SheetFrames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FramesVisitor;
+
+        impl<'de> Visitor<'de> for FramesVisitor {
+            type Value = SheetFrames;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array or a map of frames")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<SheetFrames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(mut frame) = seq.next_element::<SheetFrame>()? {
+                    let name = frame.filename.take().ok_or(de::Error::missing_field("filename"))?;
+                    frames.push((name, frame));
+                }
+                Ok(SheetFrames(frames))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<SheetFrames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(entry) = map.next_entry::<String, SheetFrame>()? {
+                    frames.push(entry);
+                }
+                Ok(SheetFrames(frames))
+            }
+        }
+
+        deserializer.deserialize_any(FramesVisitor)
+    }
+}
+
+// ============================
+// Game-level structs
+// ============================
+
+/// Layout of an atlas JSON file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AtlasFormat {
+    /// Detect the layout from the file contents
+    #[default]
+    Auto,
+    /// Ferari layout with `frames` and `meta{image, tile_size, version}`
+    Ferari,
+    /// Aseprite JSON export, array or hash layout
+    Aseprite,
+    /// TexturePacker generic JSON export, array or hash layout
+    TexturePacker,
+}
+
+// ============================
+// Implementation
+// ============================
+
+impl AtlasFormat {
+    /// Detects the layout of an atlas JSON document.
+    ///
+    /// `meta.app` decides between Aseprite and TexturePacker. Without it,
+    /// frames given as an array or with a nested `frame` rectangle are taken
+    /// as TexturePacker, anything else as Ferari.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - Contents of the JSON file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, serde_json::Error>` - Detected format, never `Auto`, Err if not valid JSON.
+    pub fn detect(text: &str) -> Result<Self, serde_json::Error> {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+
+        let app = value.pointer("/meta/app").and_then(|app| app.as_str()).unwrap_or_default();
+        let app = app.to_ascii_lowercase();
+        if app.contains("aseprite") {
+            return Ok(AtlasFormat::Aseprite);
+        }
+        if app.contains("texturepacker") || app.contains("codeandweb") {
+            return Ok(AtlasFormat::TexturePacker);
+        }
+
+        let sheet_like = match value.get("frames") {
+            Some(serde_json::Value::Array(_)) => true,
+            Some(serde_json::Value::Object(frames)) => {
+                frames.values().next().is_some_and(|frame| frame.get("frame").is_some())
+            }
+            _ => false,
+        };
+
+        Ok(if sheet_like { AtlasFormat::TexturePacker } else { AtlasFormat::Ferari })
+    }
+}
+
+impl SheetTag {
+    /// Lists frame indices of one playback of the tag and the clip mode.
+    fn sequence(&self) -> Result<(Vec<usize>, PlaybackMode), String> {
+        let direction = self.direction.as_deref().unwrap_or("forward");
+        let forward: Vec<usize> = (self.from..=self.to).collect();
+        let reverse: Vec<usize> = forward.iter().rev().copied().collect();
+
+        let repeat = match &self.repeat {
+            None => None,
+            Some(serde_json::Value::Number(n)) => n.as_u64(),
+            Some(serde_json::Value::String(s)) => s.parse().ok(),
+            Some(_) => None,
+        }
+        .filter(|&n| n > 0);
+
+        let (first, second) = match direction {
+            "forward" => (&forward, &forward),
+            "reverse" => (&reverse, &reverse),
+            "pingpong" => (&forward, &reverse),
+            "pingpong_reverse" => (&reverse, &forward),
+            other => return Err(format!("tag `{}` has unknown direction `{other}`", self.name)),
+        };
+        let ping_pong = direction.starts_with("pingpong");
+
+        let Some(repeat) = repeat else {
+            let mode = if ping_pong { PlaybackMode::PingPong } else { PlaybackMode::Loop };
+            return Ok((first.clone(), mode));
+        };
+
+        // Finite playback is unrolled, ping-pong passes share their end frames
+        let mut sequence = first.clone();
+        for pass in 1..repeat {
+            let frames = if pass % 2 == 1 { second } else { first };
+            let skip = usize::from(ping_pong && frames.len() > 1);
+            sequence.extend(frames.iter().skip(skip));
+        }
+        Ok((sequence, PlaybackMode::Once))
+    }
+}
+
+impl Atlas {
+    /// Builds an atlas from the text of an Aseprite or TexturePacker JSON file.
+    ///
+    /// Rotated frames are copied upright below the original image, trimmed
+    /// frames keep their offset and untrimmed size. Aseprite frame tags and
+    /// TexturePacker `animations` become animation clips.
+    ///
+    /// Tile size of the atlas is the most common untrimmed frame width.
+    ///
+    /// # Arguments
+    ///
+    /// * `json_path` - Path to the JSON file, used for errors
+    /// * `text` - Contents of the JSON file
+    /// * `image` - Provides the sheet image for the `meta.image` value
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, AssetError>` - Ok(Atlas) if successful, Err otherwise.
+    pub(super) fn from_sheet_json<F>(
+        json_path: &Path,
+        text: &str,
+        image: F,
+    ) -> Result<Self, AssetError>
+    where
+        F: FnOnce(&str) -> Result<RgbaImage, AssetError>,
+    {
+        let sheet: SheetJson =
+            serde_json::from_str(text).map_err(|e| AssetError::json(json_path, e))?;
+        let mut image = image(&sheet.meta.image)?;
+
+        let mut rotated: Vec<(String, RgbaImage)> = Vec::new();
+        let mut frames = HashMap::new();
+        let mut order = Vec::with_capacity(sheet.frames.0.len());
+
+        for (name, sheet_frame) in &sheet.frames.0 {
+            let rect = sheet_frame.frame;
+            if rect.x < 0 || rect.y < 0 {
+                return Err(AssetError::invalid(
+                    json_path,
+                    format!("frame `{name}` has negative position"),
+                ));
+            }
+
+            if sheet_frame.rotated {
+                // Stored area is h x w, turning it back counter-clockwise restores the sprite
+                let stored =
+                    imageops::crop_imm(&image, rect.x as u32, rect.y as u32, rect.h, rect.w);
+                rotated.push((name.clone(), imageops::rotate270(&stored.to_image())));
+            }
+
+            let source_size = sheet_frame.source_size.map(|size| (size.w, size.h));
+            let offset = sheet_frame.sprite_source_size.map(|s| (s.x, s.y)).unwrap_or((0, 0));
+            let (source_w, source_h) = source_size.unwrap_or((rect.w, rect.h));
+            let pivot = sheet_frame.pivot.map(|p| {
+                ((p.x * source_w as f32).round() as i32, (p.y * source_h as f32).round() as i32)
+            });
+
+            let frame = Frame {
+                name: name.clone(),
+                x: rect.x as u32,
+                y: rect.y as u32,
+                w: rect.w,
+                h: rect.h,
+                pivot,
+                offset,
+                source_size: source_size
+                    .filter(|&size| size != (rect.w, rect.h) || offset != (0, 0)),
+            };
+            frames.insert(name.clone(), frame);
+            order.push(name.clone());
+        }
+
+        if !rotated.is_empty() {
+            image = append_sprites(image, &rotated, &mut frames);
+        }
+
+        let mut animations = HashMap::new();
+        for tag in &sheet.meta.frame_tags {
+            let (sequence, mode) =
+                tag.sequence().map_err(|message| AssetError::invalid(json_path, message))?;
+
+            let mut clip_frames = Vec::with_capacity(sequence.len());
+            for index in sequence {
+                let (name, sheet_frame) = sheet.frames.0.get(index).ok_or_else(|| {
+                    AssetError::invalid(
+                        json_path,
+                        format!("tag `{}` refers to missing frame {index}", tag.name),
+                    )
+                })?;
+                let duration = sheet_frame
+                    .duration
+                    .filter(|&ms| ms > 0.0)
+                    .map(|ms| ms / 1000.0)
+                    .unwrap_or(DEFAULT_FRAME_DURATION);
+                clip_frames.push(AnimationFrame { frame: name.clone(), duration });
+            }
+
+            if !clip_frames.is_empty() {
+                let clip = Animation { name: tag.name.clone(), frames: clip_frames, mode };
+                animations.insert(tag.name.clone(), clip);
+            }
+        }
+
+        for (name, frame_names) in &sheet.animations {
+            if frame_names.is_empty() {
+                return Err(AssetError::invalid(
+                    json_path,
+                    format!("animation `{name}` has no frames"),
+                ));
+            }
+            let mut clip_frames = Vec::with_capacity(frame_names.len());
+            for frame in frame_names {
+                if !frames.contains_key(frame) {
+                    return Err(AssetError::invalid(
+                        json_path,
+                        format!("animation `{name}` refers to unknown frame `{frame}`"),
+                    ));
+                }
+                clip_frames.push(AnimationFrame {
+                    frame: frame.clone(),
+                    duration: DEFAULT_FRAME_DURATION,
+                });
+            }
+            let clip =
+                Animation { name: name.clone(), frames: clip_frames, mode: PlaybackMode::Loop };
+            animations.insert(name.clone(), clip);
+        }
+
+        let tile_size = common_width(order.iter().map(|name| frames[name].untrimmed_size().0));
+
+        Ok(Atlas::new(image, frames, animations, tile_size, 1))
+    }
+}
+
+/// Finds the most common value, the smallest one on ties.
+fn common_width(widths: impl Iterator<Item = u32>) -> u32 {
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for width in widths {
+        *counts.entry(width).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+        .map(|(width, _)| width)
+        .unwrap_or(1)
+        .max(1)
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use image::Rgba;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // 8x4 image: red 4x2 block at (0, 0), the same block rotated clockwise at (4, 0)
+    fn rotated_sheet() -> RgbaImage {
+        let mut image = RgbaImage::new(8, 4);
+        for y in 0..2 {
+            for x in 0..4 {
+                let pixel = Rgba([x as u8 * 10, y as u8 * 10, 0, 255]);
+                image.put_pixel(x, y, pixel);
+                // Clockwise rotation maps (x, y) of a 4x2 sprite to (1 - y, x)
+                image.put_pixel(4 + (1 - y), x, pixel);
+            }
+        }
+        image
+    }
+
+    // Test loading a TexturePacker hash with rotated, trimmed and pivoted frames
+    #[test]
+    fn test_texture_packer_hash() {
+        let dir = TempDir::new("import_tp");
+        rotated_sheet().save(dir.join("sheet.png")).unwrap();
+        let json = r#"{
+            "frames": {
+                "plain.png": {
+                    "frame": {"x": 0, "y": 0, "w": 4, "h": 2},
+                    "rotated": false, "trimmed": true,
+                    "spriteSourceSize": {"x": 2, "y": 3, "w": 4, "h": 2},
+                    "sourceSize": {"w": 8, "h": 8},
+                    "pivot": {"x": 0.5, "y": 1.0}
+                },
+                "turned.png": {
+                    "frame": {"x": 4, "y": 0, "w": 4, "h": 2},
+                    "rotated": true, "trimmed": false,
+                    "spriteSourceSize": {"x": 0, "y": 0, "w": 4, "h": 2},
+                    "sourceSize": {"w": 4, "h": 2}
+                }
+            },
+            "meta": {"app": "https://www.codeandweb.com/texturepacker", "image": "sheet.png"}
+        }"#;
+        let path = dir.join("sheet.json");
+        fs::write(&path, json).unwrap();
+
+        assert_eq!(AtlasFormat::detect(json).unwrap(), AtlasFormat::TexturePacker);
+        let atlas = Atlas::load(&path).unwrap();
+
+        let plain = atlas.get_frame("plain.png").unwrap();
+        assert_eq!(plain.offset, (2, 3));
+        assert_eq!(plain.untrimmed_size(), (8, 8));
+        assert_eq!(plain.pivot, Some((4, 8)));
+
+        let turned = atlas.get_frame("turned.png").unwrap();
+        assert_eq!((turned.w, turned.h), (4, 2));
+        assert_eq!(turned.source_size, None);
+        assert!(turned.y >= 4);
+        for y in 0..2 {
+            for x in 0..4 {
+                assert_eq!(
+                    atlas.image.get_pixel(turned.x + x, turned.y + y),
+                    atlas.image.get_pixel(plain.x + x, plain.y + y)
+                );
+            }
+        }
+        assert!(atlas.validate().is_ok(), "{}", atlas.validate());
+    }
+
+    // Test loading an Aseprite array with frame tags and durations
+    #[test]
+    fn test_aseprite_array() {
+        let dir = TempDir::new("import_aseprite");
+        rotated_sheet().save(dir.join("walk.png")).unwrap();
+        let frame = |name: &str, x: u32, duration: u32| {
+            format!(
+                r#"{{"filename": "{name}", "frame": {{"x": {x}, "y": 0, "w": 2, "h": 2}},
+                    "rotated": false, "trimmed": false,
+                    "spriteSourceSize": {{"x": 0, "y": 0, "w": 2, "h": 2}},
+                    "sourceSize": {{"w": 2, "h": 2}}, "duration": {duration}}}"#
+            )
+        };
+        let json = format!(
+            r#"{{
+                "frames": [{}, {}, {}],
+                "meta": {{
+                    "app": "https://www.aseprite.org/",
+                    "image": "walk.png",
+                    "frameTags": [
+                        {{"name": "walk", "from": 0, "to": 2, "direction": "pingpong"}},
+                        {{"name": "back", "from": 0, "to": 1, "direction": "reverse", "repeat": "2"}}
+                    ]
+                }}
+            }}"#,
+            frame("walk 0.aseprite", 0, 100),
+            frame("walk 1.aseprite", 2, 250),
+            frame("walk 2.aseprite", 4, 100)
+        );
+        let path = dir.join("walk.json");
+        fs::write(&path, &json).unwrap();
+
+        let atlas = Atlas::load_as(&path, AtlasFormat::Aseprite).unwrap();
+        assert_eq!(atlas.frame_count(), 3);
+        assert_eq!(atlas.tile_size, 2);
+
+        let walk = atlas.get_animation("walk").unwrap();
+        assert_eq!(walk.mode, PlaybackMode::PingPong);
+        assert_eq!(walk.frames.len(), 3);
+        assert_eq!(walk.frames[1].frame, "walk 1.aseprite");
+        assert!((walk.frames[1].duration - 0.25).abs() < 1e-6);
+
+        let back = atlas.get_animation("back").unwrap();
+        let names: Vec<&str> = back.frames.iter().map(|f| f.frame.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["walk 1.aseprite", "walk 0.aseprite", "walk 1.aseprite", "walk 0.aseprite"]
+        );
+        assert_eq!(back.mode, PlaybackMode::Once);
+    }
+
+    // Test format detection and that Ferari atlases still load unchanged
+    #[test]
+    fn test_detect_format() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let text = fs::read_to_string(project_root.join("assets/tiles/atlas.json")).unwrap();
+        assert_eq!(AtlasFormat::detect(&text).unwrap(), AtlasFormat::Ferari);
+
+        let hash = r#"{"frames": {"a": {"frame": {"x": 0, "y": 0, "w": 1, "h": 1}}}, "meta": {}}"#;
+        assert_eq!(AtlasFormat::detect(hash).unwrap(), AtlasFormat::TexturePacker);
+        let aseprite = r#"{"frames": [], "meta": {"app": "http://www.aseprite.org/"}}"#;
+        assert_eq!(AtlasFormat::detect(aseprite).unwrap(), AtlasFormat::Aseprite);
+        assert!(AtlasFormat::detect("{").is_err());
+    }
+
+    // Test unrolling of finite ping-pong tags
+    #[test]
+    fn test_tag_sequence() {
+        let tag = SheetTag {
+            name: "t".to_string(),
+            from: 0,
+            to: 2,
+            direction: Some("pingpong".to_string()),
+            repeat: Some(serde_json::json!(3)),
+        };
+        assert_eq!(tag.sequence().unwrap(), (vec![0, 1, 2, 1, 0, 1, 2], PlaybackMode::Once));
+
+        let bad = SheetTag { direction: Some("sideways".to_string()), repeat: None, ..tag };
+        assert!(bad.sequence().is_err());
+    }
+}
@@ -1,8 +1,30 @@
+mod animation;
 mod atlas;
+mod error;
 mod gamemap;
+mod grid;
+mod import;
+pub mod pack;
+mod pixels;
+mod prefab;
+pub mod procgen;
+mod properties;
+mod region;
+mod spawner;
+mod tiled;
+mod validation;
+mod variant;
 
-pub use atlas::{Atlas, Frame};
-pub use gamemap::{GameMap, Object, Tile};
-
-#[cfg(test)]
-pub use gamemap::{Behaviour, BehaviourType, Mob};
+pub use animation::{Animation, AnimationFrame, Animator, PlaybackMode};
+pub use atlas::{Atlas, Frame, SpriteId};
+pub use error::AssetError;
+pub use gamemap::{Behaviour, BehaviourType, GameMap, Mob, Object, Tile, TileLayer};
+pub use grid::{GridAlias, GridNaming};
+pub use import::AtlasFormat;
+pub use pixels::{FramePixels, PixelSpan};
+pub use prefab::{Prefab, PrefabInstance, PrefabSource};
+pub use properties::{Properties, Property};
+pub use region::{Region, RegionShape, RegionSpace};
+pub use spawner::{MobTemplate, Spawner};
+pub use validation::{AtlasIssue, AtlasReport, MapEntity, MapIssue, MapReport};
+pub use variant::{Tint, Variant};
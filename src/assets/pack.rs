@@ -0,0 +1,405 @@
+use image::{imageops, open, RgbaImage};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use super::atlas::opaque_bounds;
+use super::{AssetError, Atlas, Frame};
+
+// ============================
+// JSON-level structs
+// ============================
+
+/// Frame definition written to the atlas JSON.
+#[derive(Serialize, Debug)]
+struct PackedFrame {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pivot: Option<[i32; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<[i32; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_size: Option<[u32; 2]>,
+}
+
+/// Meta information written to the atlas JSON.
+#[derive(Serialize, Debug)]
+struct PackedMeta {
+    image: String,
+    tile_size: u32,
+    version: u32,
+}
+
+/// Complete atlas JSON, with frames in name order for clean diffs.
+#[derive(Serialize, Debug)]
+struct PackedAtlas {
+    frames: BTreeMap<String, PackedFrame>,
+    meta: PackedMeta,
+}
+
+// ============================
+// Game-level structs
+// ============================
+
+/// Settings of the atlas packer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackOptions {
+    /// Empty pixels between sprites and around the image border
+    pub padding: u32,
+    /// Maximum width of the atlas image in pixels
+    pub max_width: u32,
+    /// Cut transparent borders off the sprites
+    pub trim: bool,
+    /// Pivot written for every sprite, as a fraction of the untrimmed sprite size.
+    /// None leaves the pivot to the renderer defaults.
+    pub pivot: Option<(f32, f32)>,
+    /// Tile size written to `meta.tile_size`
+    pub tile_size: u32,
+}
+
+impl Default for PackOptions {
+    /// Creates options with 1px padding, trimming and no pivots.
+    ///
+    /// # Returns
+    ///
+    /// A new `PackOptions` instance with default values.
+    fn default() -> Self {
+        Self { padding: 1, max_width: 2048, trim: true, pivot: None, tile_size: 16 }
+    }
+}
+
+/// Sprite prepared for packing.
+struct Sprite {
+    name: String,
+    image: RgbaImage,
+    offset: (i32, i32),
+    source_size: (u32, u32),
+}
+
+// ============================
+// Implementation
+// ============================
+
+/// Packs every PNG file of a directory into one atlas.
+///
+/// Frame names are taken from the file names without extension.
+///
+/// # Arguments
+///
+/// * `dir` - Directory with sprite images
+/// * `options` - Packer settings
+///
+/// # Returns
+///
+/// * `Result<Atlas, AssetError>` - Packed atlas on success, error on failure.
+pub fn pack_dir<P: AsRef<Path>>(dir: P, options: &PackOptions) -> Result<Atlas, AssetError> {
+    let dir = dir.as_ref();
+    let entries = fs::read_dir(dir).map_err(|e| AssetError::io(dir, e))?;
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| AssetError::io(dir, e))?.path();
+        let is_png = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        if path.is_file() && is_png {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut sprites = Vec::with_capacity(paths.len());
+    for path in paths {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let image = open(&path).map_err(|source| AssetError::Image { path, source })?.to_rgba8();
+        sprites.push((name, image));
+    }
+
+    pack_sprites(sprites, options).map_err(|message| AssetError::invalid(dir, message))
+}
+
+/// Packs named sprite images into one atlas.
+///
+/// Sprites are trimmed (if enabled) and placed on shelves, tallest first.
+/// Fully transparent sprites are kept as 1x1 frames so their names resolve.
+///
+/// # Arguments
+///
+/// * `sprites` - Pairs of frame name and sprite image
+/// * `options` - Packer settings
+///
+/// # Returns
+///
+/// * `Result<Atlas, String>` - Packed atlas, or a description of why the sprites don't fit.
+pub fn pack_sprites(
+    sprites: Vec<(String, RgbaImage)>,
+    options: &PackOptions,
+) -> Result<Atlas, String> {
+    let mut prepared: Vec<Sprite> = sprites
+        .into_iter()
+        .map(|(name, image)| {
+            let source_size = image.dimensions();
+            let (offset, image) = if options.trim { trim(&image) } else { ((0, 0), image) };
+            Sprite { name, image, offset, source_size }
+        })
+        .collect();
+
+    prepared.sort_by(|a, b| b.image.height().cmp(&a.image.height()).then(a.name.cmp(&b.name)));
+
+    let placements = place(&prepared, options)?;
+
+    let width = placements.iter().zip(&prepared).map(|(p, s)| p.0 + s.image.width()).max();
+    let height = placements.iter().zip(&prepared).map(|(p, s)| p.1 + s.image.height()).max();
+    let width = width.unwrap_or(0) + options.padding;
+    let height = height.unwrap_or(0) + options.padding;
+
+    let mut image = RgbaImage::new(width.max(1), height.max(1));
+    let mut frames = HashMap::new();
+    for (sprite, &(x, y)) in prepared.iter().zip(&placements) {
+        imageops::replace(&mut image, &sprite.image, x as i64, y as i64);
+
+        let (source_w, source_h) = sprite.source_size;
+        let pivot = options.pivot.map(|(px, py)| {
+            ((source_w as f32 * px).round() as i32, (source_h as f32 * py).round() as i32)
+        });
+        let trimmed = sprite.image.dimensions() != sprite.source_size;
+        let frame = Frame {
+            name: sprite.name.clone(),
+            x,
+            y,
+            w: sprite.image.width(),
+            h: sprite.image.height(),
+            pivot,
+            offset: sprite.offset,
+            source_size: trimmed.then_some(sprite.source_size),
+        };
+        frames.insert(sprite.name.clone(), frame);
+    }
+
+    Ok(Atlas::new(image, frames, HashMap::new(), options.tile_size, 1))
+}
+
+/// Writes an atlas as JSON and PNG files.
+///
+/// The image is written next to the JSON file, with the same name and a
+/// `.png` extension. Animations are not written.
+///
+/// # Arguments
+///
+/// * `atlas` - Atlas to write
+/// * `json_path` - Path of the JSON file to create
+///
+/// # Returns
+///
+/// * `Result<(), AssetError>` - Ok(()) on success, error on failure.
+pub fn write_atlas<P: AsRef<Path>>(atlas: &Atlas, json_path: P) -> Result<(), AssetError> {
+    let json_path = json_path.as_ref();
+    let image_path = json_path.with_extension("png");
+    let image_name = image_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+
+    let frames = atlas
+        .frames
+        .iter()
+        .map(|(name, frame)| {
+            let packed = PackedFrame {
+                x: frame.x,
+                y: frame.y,
+                w: frame.w,
+                h: frame.h,
+                pivot: frame.pivot.map(|(x, y)| [x, y]),
+                offset: (frame.offset != (0, 0)).then_some([frame.offset.0, frame.offset.1]),
+                source_size: frame.source_size.map(|(w, h)| [w, h]),
+            };
+            (name.clone(), packed)
+        })
+        .collect();
+
+    let packed = PackedAtlas {
+        frames,
+        meta: PackedMeta { image: image_name, tile_size: atlas.tile_size, version: atlas.version },
+    };
+
+    let json = serde_json::to_string_pretty(&packed).map_err(|e| AssetError::json(json_path, e))?;
+    fs::write(json_path, json + "\n").map_err(|e| AssetError::io(json_path, e))?;
+    atlas.image.save(&image_path).map_err(|source| AssetError::Image { path: image_path, source })
+}
+
+/// Cuts transparent borders off a sprite.
+///
+/// # Returns
+///
+/// * `((i32, i32), RgbaImage)` - Position of the kept area and its pixels.
+fn trim(image: &RgbaImage) -> ((i32, i32), RgbaImage) {
+    match opaque_bounds(image, 0, 0, image.width(), image.height()) {
+        Some((x, y, w, h)) => {
+            ((x as i32, y as i32), imageops::crop_imm(image, x, y, w, h).to_image())
+        }
+        None => ((0, 0), RgbaImage::new(1, 1)),
+    }
+}
+
+/// Places sprites on horizontal shelves.
+///
+/// Each sprite goes to the first shelf with enough room left, or opens a
+/// new shelf below the last one.
+///
+/// # Returns
+///
+/// * `Result<Vec<(u32, u32)>, String>` - Top-left position of each sprite.
+fn place(sprites: &[Sprite], options: &PackOptions) -> Result<Vec<(u32, u32)>, String> {
+    let padding = options.padding;
+
+    // Aim for a roughly square image, but never narrower than the widest sprite
+    let area: u64 = sprites
+        .iter()
+        .map(|s| (s.image.width() + padding) as u64 * (s.image.height() + padding) as u64)
+        .sum();
+    let widest = sprites.iter().map(|s| s.image.width() + 2 * padding).max().unwrap_or(0);
+    let width = ((area as f64).sqrt().ceil() as u32).next_power_of_two().max(widest);
+    if widest > options.max_width {
+        return Err(format!("sprite is wider than max width {}", options.max_width));
+    }
+    let width = width.min(options.max_width);
+
+    // (top, height, next free x) of every shelf
+    let mut shelves: Vec<(u32, u32, u32)> = Vec::new();
+    let mut placements = Vec::with_capacity(sprites.len());
+
+    for sprite in sprites {
+        let (w, h) = sprite.image.dimensions();
+        let shelf =
+            shelves.iter_mut().find(|(_, shelf_h, x)| h <= *shelf_h && x + w + padding <= width);
+
+        let position = match shelf {
+            Some((top, _, x)) => {
+                let position = (*x, *top);
+                *x += w + padding;
+                position
+            }
+            None => {
+                let top =
+                    shelves.last().map_or(padding, |(top, shelf_h, _)| top + shelf_h + padding);
+                shelves.push((top, h, padding + w + padding));
+                (padding, top)
+            }
+        };
+        placements.push(position);
+    }
+
+    Ok(placements)
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use image::Rgba;
+
+    fn sprite(w: u32, h: u32, opaque: (u32, u32, u32, u32), color: [u8; 4]) -> RgbaImage {
+        let (x0, y0, ow, oh) = opaque;
+        let mut image = RgbaImage::new(w, h);
+        for y in y0..y0 + oh {
+            for x in x0..x0 + ow {
+                image.put_pixel(x, y, Rgba(color));
+            }
+        }
+        image
+    }
+
+    // Test that trimming keeps the offset of the opaque area
+    #[test]
+    fn test_trim() {
+        let (offset, trimmed) = trim(&sprite(16, 16, (3, 5, 4, 6), [1, 2, 3, 255]));
+        assert_eq!(offset, (3, 5));
+        assert_eq!(trimmed.dimensions(), (4, 6));
+
+        let (offset, trimmed) = trim(&RgbaImage::new(8, 8));
+        assert_eq!(offset, (0, 0));
+        assert_eq!(trimmed.dimensions(), (1, 1));
+    }
+
+    // Test that packed sprites keep their pixels and do not overlap
+    #[test]
+    fn test_pack_sprites() {
+        let sprites = vec![
+            ("a".to_string(), sprite(16, 16, (4, 2, 8, 14), [255, 0, 0, 255])),
+            ("b".to_string(), sprite(10, 10, (0, 0, 10, 10), [0, 255, 0, 255])),
+            ("c".to_string(), sprite(32, 8, (1, 1, 30, 6), [0, 0, 255, 255])),
+        ];
+        let options = PackOptions { padding: 2, ..Default::default() };
+        let atlas = pack_sprites(sprites, &options).unwrap();
+
+        assert_eq!(atlas.frame_count(), 3);
+        assert!(atlas.validate().is_ok(), "{}", atlas.validate());
+
+        let a = atlas.get_frame("a").unwrap();
+        assert_eq!((a.w, a.h), (8, 14));
+        assert_eq!(a.offset, (4, 2));
+        assert_eq!(a.source_size, Some((16, 16)));
+        assert_eq!(a.pivot, None);
+        assert_eq!(atlas.image.get_pixel(a.x, a.y), &Rgba([255, 0, 0, 255]));
+
+        // Trimmed sprite lands where the untrimmed one would
+        assert_eq!(a.origin(100, 100, (8, 16)), (100 - 8 + 4, 100 - 16 + 2));
+
+        let b = atlas.get_frame("b").unwrap();
+        assert_eq!(b.source_size, None);
+
+        let c = atlas.get_frame("c").unwrap();
+        assert_eq!(atlas.image.get_pixel(c.x + 29, c.y + 5), &Rgba([0, 0, 255, 255]));
+
+        for frame in atlas.iter_frames() {
+            assert!(frame.x >= 2 && frame.y >= 2);
+            assert!(frame.x + frame.w + 2 <= atlas.image.width());
+            assert!(frame.y + frame.h + 2 <= atlas.image.height());
+        }
+    }
+
+    // Test that the written atlas loads back with the same frames
+    #[test]
+    fn test_pack_dir_round_trip() {
+        let dir = TempDir::new("pack");
+        let sprites_dir = dir.join("sprites");
+        fs::create_dir_all(&sprites_dir).unwrap();
+        sprite(12, 20, (2, 4, 8, 16), [9, 9, 9, 255]).save(sprites_dir.join("tree.png")).unwrap();
+        sprite(8, 8, (0, 0, 8, 8), [7, 7, 7, 128]).save(sprites_dir.join("rock.png")).unwrap();
+        fs::write(sprites_dir.join("notes.txt"), "not a sprite").unwrap();
+
+        let options = PackOptions { pivot: Some((0.5, 1.0)), ..Default::default() };
+        let packed = pack_dir(&sprites_dir, &options).unwrap();
+        let json_path = dir.join("atlas.json");
+        write_atlas(&packed, &json_path).unwrap();
+
+        let atlas = Atlas::load(&json_path).unwrap();
+        assert_eq!(atlas.frame_count(), 2);
+        assert_eq!(atlas.tile_size, 16);
+        for frame in atlas.iter_frames() {
+            let original = packed.get_frame(&frame.name).unwrap();
+            assert_eq!(
+                (frame.x, frame.y, frame.w, frame.h),
+                (original.x, original.y, original.w, original.h)
+            );
+            assert_eq!(frame.pivot, original.pivot);
+            assert_eq!(frame.offset, original.offset);
+            assert_eq!(frame.source_size, original.source_size);
+        }
+        let tree = atlas.get_frame("tree").unwrap();
+        assert_eq!(tree.offset, (2, 4));
+        assert_eq!(tree.pivot, Some((6, 20)));
+        assert_eq!(atlas.image, packed.image);
+    }
+
+    // Test that a sprite wider than the limit is rejected
+    #[test]
+    fn test_pack_too_wide() {
+        let sprites = vec![("wide".to_string(), sprite(64, 4, (0, 0, 64, 4), [1, 1, 1, 255]))];
+        let options = PackOptions { max_width: 32, ..Default::default() };
+        assert!(pack_sprites(sprites, &options).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use image::RgbaImage;
+
+use super::Frame;
+
+// ============================
+// Game-level structs
+// ============================
+
+/// Run of visible pixels in one row of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelSpan {
+    /// Column of the first pixel of the run
+    pub start: u32,
+    /// Number of pixels in the run
+    pub len: u32,
+    /// true if every pixel of the run is fully opaque, false if all are translucent
+    pub opaque: bool,
+}
+
+/// Pixels of a frame decoded for blitting.
+///
+/// Pixels are stored row by row as `0xAARRGGBB` values, the layout of the
+/// render buffers. Fully transparent pixels are left out of the spans, so
+/// blit loops only visit runs that change the destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FramePixels {
+    /// Area of the atlas image the pixels were decoded from, as (x, y, w, h)
+    pub source: (u32, u32, u32, u32),
+    /// Width of the frame in pixels
+    pub w: u32,
+    /// Height of the frame in pixels
+    pub h: u32,
+    /// ARGB pixels, `w * h` values in row-major order
+    pub pixels: Vec<u32>,
+    /// Visible runs of all rows, in row order
+    spans: Vec<PixelSpan>,
+    /// Index of the first span of each row, plus the total span count
+    row_starts: Vec<u32>,
+}
+
+// ============================
+// Implementation
+// ============================
+
+impl FramePixels {
+    /// Decodes the area of a frame from the atlas image.
+    ///
+    /// Parts of the frame outside the image are treated as transparent.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - Atlas image
+    /// * `frame` - Frame to decode
+    ///
+    /// # Returns
+    ///
+    /// A new `FramePixels` instance.
+    pub fn decode(image: &RgbaImage, frame: &Frame) -> Self {
+        let (image_w, image_h) = image.dimensions();
+        let (w, h) = (frame.w, frame.h);
+
+        let mut pixels = Vec::with_capacity(w as usize * h as usize);
+        let mut spans = Vec::new();
+        let mut row_starts = Vec::with_capacity(h as usize + 1);
+
+        for dy in 0..h {
+            row_starts.push(spans.len() as u32);
+            let mut current: Option<PixelSpan> = None;
+
+            for dx in 0..w {
+                let (src_x, src_y) = (frame.x + dx, frame.y + dy);
+                let [r, g, b, a] = if src_x < image_w && src_y < image_h {
+                    image.get_pixel(src_x, src_y).0
+                } else {
+                    [0; 4]
+                };
+                pixels.push((a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32);
+
+                let kind = match a {
+                    0 => None,
+                    255 => Some(true),
+                    _ => Some(false),
+                };
+                match (current.as_mut(), kind) {
+                    (Some(span), Some(opaque)) if span.opaque == opaque => span.len += 1,
+                    (_, kind) => {
+                        spans.extend(current.take());
+                        current = kind.map(|opaque| PixelSpan { start: dx, len: 1, opaque });
+                    }
+                }
+            }
+            spans.extend(current);
+        }
+        row_starts.push(spans.len() as u32);
+
+        Self { source: (frame.x, frame.y, w, h), w, h, pixels, spans, row_starts }
+    }
+
+    /// Checks if the pixels were decoded from the area of the given frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Frame to compare with
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the decoded area matches the frame, false otherwise.
+    pub fn matches(&self, frame: &Frame) -> bool {
+        self.source == (frame.x, frame.y, frame.w, frame.h)
+    }
+
+    /// Returns the pixels of one row.
+    ///
+    /// # Arguments
+    ///
+    /// * `y` - Row index, less than `h`
+    ///
+    /// # Returns
+    ///
+    /// * `&[u32]` - `w` ARGB values.
+    pub fn row(&self, y: u32) -> &[u32] {
+        let start = (y * self.w) as usize;
+        &self.pixels[start..start + self.w as usize]
+    }
+
+    /// Returns the visible runs of one row.
+    ///
+    /// # Arguments
+    ///
+    /// * `y` - Row index, less than `h`
+    ///
+    /// # Returns
+    ///
+    /// * `&[PixelSpan]` - Runs ordered by column.
+    pub fn row_spans(&self, y: u32) -> &[PixelSpan] {
+        let start = self.row_starts[y as usize] as usize;
+        let end = self.row_starts[y as usize + 1] as usize;
+        &self.spans[start..end]
+    }
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    // Test ARGB conversion and span splitting by alpha
+    #[test]
+    fn test_decode_spans() {
+        let mut image = RgbaImage::new(6, 2);
+        image.put_pixel(1, 0, Rgba([1, 2, 3, 255]));
+        image.put_pixel(2, 0, Rgba([4, 5, 6, 255]));
+        image.put_pixel(3, 0, Rgba([7, 8, 9, 128]));
+        image.put_pixel(5, 0, Rgba([0, 0, 0, 255]));
+        image.put_pixel(0, 1, Rgba([0, 0, 0, 10]));
+
+        let pixels = FramePixels::decode(&image, &Frame::new("f", 0, 0, 6, 2));
+
+        assert_eq!(pixels.row(0)[1], 0xFF010203);
+        assert_eq!(pixels.row(0)[3], 0x80070809);
+        assert_eq!(
+            pixels.row_spans(0),
+            &[
+                PixelSpan { start: 1, len: 2, opaque: true },
+                PixelSpan { start: 3, len: 1, opaque: false },
+                PixelSpan { start: 5, len: 1, opaque: true },
+            ]
+        );
+        assert_eq!(pixels.row_spans(1), &[PixelSpan { start: 0, len: 1, opaque: false }]);
+    }
+
+    // Test that areas outside the image decode as transparent
+    #[test]
+    fn test_decode_out_of_bounds() {
+        let mut image = RgbaImage::new(2, 2);
+        image.put_pixel(1, 1, Rgba([255, 255, 255, 255]));
+
+        let frame = Frame::new("f", 1, 1, 3, 3);
+        let pixels = FramePixels::decode(&image, &frame);
+
+        assert_eq!(pixels.pixels.len(), 9);
+        assert_eq!(pixels.row_spans(0), &[PixelSpan { start: 0, len: 1, opaque: true }]);
+        assert!(pixels.row_spans(1).is_empty() && pixels.row_spans(2).is_empty());
+        assert!(pixels.matches(&frame));
+        assert!(!pixels.matches(&Frame::new("f", 0, 0, 3, 3)));
+    }
+}
@@ -0,0 +1,565 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::error::MEMORY_PATH;
+use super::gamemap::{sorted, JsonMob, JsonObject, JsonTile};
+use super::{AssetError, GameMap, Mob, Object, Properties, Tile};
+
+// ============================
+// JSON-level structs
+// ============================
+
+/// Group of tiles, objects and mobs from JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JsonPrefab {
+    /// Mapping of tiles' names to their definitions, positions in cells from the origin
+    #[serde(default, serialize_with = "sorted", skip_serializing_if = "HashMap::is_empty")]
+    pub tiles: HashMap<String, JsonTile>,
+    /// Mapping of objects' names to their definitions, positions in cells from the origin
+    #[serde(default, serialize_with = "sorted", skip_serializing_if = "HashMap::is_empty")]
+    pub objects: HashMap<String, JsonObject>,
+    /// Mapping of mobs' names to their definitions, start positions in cells from the origin
+    #[serde(default, serialize_with = "sorted", skip_serializing_if = "HashMap::is_empty")]
+    pub mobs: HashMap<String, JsonMob>,
+}
+
+/// Prefab from JSON, defined inline or as a path to a prefab file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum JsonPrefabSource {
+    /// Path of a prefab file, relative to the map file
+    File(String),
+    /// Prefab defined in the map
+    Inline(JsonPrefab),
+}
+
+/// Placement of a prefab from JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JsonInstance {
+    /// Name of the placed prefab
+    pub prefab: String,
+    /// X coordinate of the prefab origin in cells
+    pub x: u32,
+    /// Y coordinate of the prefab origin in cells
+    pub y: u32,
+    /// Clockwise rotation in degrees, a multiple of 90
+    #[serde(default, skip_serializing_if = "super::gamemap::is_zero")]
+    pub rotation: u32,
+    /// Properties added to every placed entry
+    #[serde(default, skip_serializing_if = "Properties::is_empty")]
+    pub properties: Properties,
+    /// Properties added to single entries, by entry name in the prefab
+    #[serde(default, serialize_with = "sorted", skip_serializing_if = "HashMap::is_empty")]
+    pub overrides: HashMap<String, Properties>,
+}
+
+// ============================
+// Game-level structs
+// ============================
+
+/// Reusable group of tiles, objects and mobs placed relative to an origin cell.
+///
+/// Positions of all entries, mob start positions included, are cells from
+/// the origin. Rotation turns positions around the origin cell, assets stay
+/// as they are.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Prefab {
+    /// Mapping of tiles' names to their definitions
+    pub tiles: HashMap<String, Tile>,
+    /// Mapping of objects' names to their definitions
+    pub objects: HashMap<String, Object>,
+    /// Mapping of mobs' names to their definitions
+    pub mobs: HashMap<String, Mob>,
+}
+
+/// Prefab of a map together with the place it is defined in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefabSource {
+    /// Path of the prefab file relative to the map file, None for prefabs defined in the map
+    pub file: Option<String>,
+    /// Loaded prefab
+    pub prefab: Prefab,
+}
+
+/// Placement of a prefab on a map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefabInstance {
+    /// Name of the instance, prefix of the placed entry names
+    pub name: String,
+    /// Name of the placed prefab
+    pub prefab: String,
+    /// X coordinate of the prefab origin in cells
+    pub x: u32,
+    /// Y coordinate of the prefab origin in cells
+    pub y: u32,
+    /// Clockwise quarter turns, 0 to 3
+    pub rotation: u32,
+    /// Properties added to every placed entry
+    pub properties: Properties,
+    /// Properties added to single entries, by entry name in the prefab
+    pub overrides: HashMap<String, Properties>,
+}
+
+// ============================
+// Implementation
+// ============================
+
+impl Prefab {
+    /// Loads a prefab from a JSON file.
+    ///
+    /// The file holds `tiles`, `objects` and `mobs` sections like a map.
+    ///
+    /// # Arguments
+    ///
+    /// * `json_path` - Path to the prefab file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, AssetError>` - Parsed Prefab on success, error on failure.
+    pub fn load<P: AsRef<Path>>(json_path: P) -> Result<Self, AssetError> {
+        let json_path = json_path.as_ref();
+        let file = File::open(json_path).map_err(|e| AssetError::io(json_path, e))?;
+        let json: JsonPrefab = serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| AssetError::json(json_path, e))?;
+
+        Ok(Self::from_json(json))
+    }
+
+    /// Builds a prefab from its JSON definition.
+    pub(super) fn from_json(json: JsonPrefab) -> Self {
+        Prefab {
+            tiles: json
+                .tiles
+                .into_iter()
+                .map(|(name, t)| (name.clone(), Tile::from_json(name, t)))
+                .collect(),
+            objects: json
+                .objects
+                .into_iter()
+                .map(|(name, o)| (name.clone(), Object::from_json(name, o)))
+                .collect(),
+            mobs: json
+                .mobs
+                .into_iter()
+                .map(|(name, m)| (name.clone(), Mob::from_json(name, m)))
+                .collect(),
+        }
+    }
+
+    /// Converts the prefab back into its JSON definition.
+    pub(super) fn to_json(&self) -> JsonPrefab {
+        JsonPrefab {
+            tiles: self.tiles.iter().map(|(name, t)| (name.clone(), t.to_json())).collect(),
+            objects: self.objects.iter().map(|(name, o)| (name.clone(), o.to_json())).collect(),
+            mobs: self.mobs.iter().map(|(name, m)| (name.clone(), m.to_json())).collect(),
+        }
+    }
+
+    /// Reads the prefabs section of a map.
+    ///
+    /// # Arguments
+    ///
+    /// * `json_path` - Path of the map, prefab files are relative to its directory
+    /// * `sources` - Inline prefabs and prefab file paths by prefab name
+    ///
+    /// # Returns
+    ///
+    /// * `Result<HashMap<String, PrefabSource>, AssetError>` - Prefabs by name, error of the
+    ///   first prefab file that fails to load or names a file in a map loaded from memory.
+    pub(super) fn load_all(
+        json_path: &Path,
+        sources: HashMap<String, JsonPrefabSource>,
+    ) -> Result<HashMap<String, PrefabSource>, AssetError> {
+        let dir = json_path.parent().unwrap_or(Path::new(""));
+        sources
+            .into_iter()
+            .map(|(name, source)| {
+                let source = match source {
+                    JsonPrefabSource::File(file) if json_path == Path::new(MEMORY_PATH) => {
+                        let message = format!(
+                            "prefab `{name}` reads `{file}`, maps from memory need inline prefabs"
+                        );
+                        return Err(AssetError::invalid(json_path, message));
+                    }
+                    JsonPrefabSource::File(file) => {
+                        let prefab = Prefab::load(dir.join(&file))?;
+                        PrefabSource { file: Some(file), prefab }
+                    }
+                    JsonPrefabSource::Inline(json) => {
+                        PrefabSource { file: None, prefab: Prefab::from_json(json) }
+                    }
+                };
+                Ok((name, source))
+            })
+            .collect()
+    }
+
+    /// Checks if the prefab has an entry of any kind with the name.
+    fn contains(&self, name: &str) -> bool {
+        self.tiles.contains_key(name)
+            || self.objects.contains_key(name)
+            || self.mobs.contains_key(name)
+    }
+}
+
+impl PrefabSource {
+    /// Converts the prefab back into its JSON definition, a path for prefab files.
+    pub(super) fn to_json(&self) -> JsonPrefabSource {
+        match &self.file {
+            Some(file) => JsonPrefabSource::File(file.clone()),
+            None => JsonPrefabSource::Inline(self.prefab.to_json()),
+        }
+    }
+}
+
+impl PrefabInstance {
+    /// Builds a prefab placement from its JSON definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the instance
+    /// * `json` - Parsed JSON definition
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - Ok(PrefabInstance) if the rotation is a multiple of 90,
+    ///   problem description otherwise.
+    pub(super) fn from_json(name: &str, json: JsonInstance) -> Result<Self, String> {
+        if !json.rotation.is_multiple_of(90) {
+            return Err(format!(
+                "instance `{name}` has rotation {}, only multiples of 90 are supported",
+                json.rotation
+            ));
+        }
+        Ok(PrefabInstance {
+            name: name.to_string(),
+            prefab: json.prefab,
+            x: json.x,
+            y: json.y,
+            rotation: json.rotation / 90 % 4,
+            properties: json.properties,
+            overrides: json.overrides,
+        })
+    }
+
+    /// Converts the placement back into its JSON definition.
+    pub(super) fn to_json(&self) -> JsonInstance {
+        JsonInstance {
+            prefab: self.prefab.clone(),
+            x: self.x,
+            y: self.y,
+            rotation: self.rotation * 90,
+            properties: self.properties.clone(),
+            overrides: self.overrides.clone(),
+        }
+    }
+
+    /// Returns the map names of the entries the instance places.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefab` - Prefab the instance places
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = String>` - `<instance>/<entry>` names of tiles, objects and mobs.
+    pub(super) fn entry_names<'a>(
+        &'a self,
+        prefab: &'a Prefab,
+    ) -> impl Iterator<Item = String> + 'a {
+        let entries = prefab.tiles.keys().chain(prefab.objects.keys()).chain(prefab.mobs.keys());
+        entries.map(|entry| format!("{}/{entry}", self.name))
+    }
+
+    /// Places a prefab position relative to the origin on the map.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X coordinate in cells from the prefab origin
+    /// * `y` - Y coordinate in cells from the prefab origin
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(u32, u32)>` - Cell on the map, None if it lies left or above the map.
+    fn place(&self, x: u32, y: u32) -> Option<(u32, u32)> {
+        let (x, y) = (x as i64, y as i64);
+        let (dx, dy) = match self.rotation {
+            0 => (x, y),
+            1 => (-y, x),
+            2 => (-x, -y),
+            _ => (y, -x),
+        };
+        let x = u32::try_from(self.x as i64 + dx).ok()?;
+        let y = u32::try_from(self.y as i64 + dy).ok()?;
+        Some((x, y))
+    }
+
+    /// Returns the properties of a placed entry.
+    ///
+    /// Instance properties replace keys of the prefab entry, overrides
+    /// of the entry replace both.
+    fn properties(&self, entry: &str, properties: &Properties) -> Properties {
+        let mut properties = properties.clone();
+        let overrides = self.overrides.get(entry).into_iter().flat_map(Properties::iter);
+        properties.extend(
+            self.properties
+                .iter()
+                .chain(overrides)
+                .map(|(key, value)| (key.to_string(), value.clone())),
+        );
+        properties
+    }
+}
+
+impl GameMap {
+    /// Places the entries of a prefab on the map.
+    ///
+    /// Entries are named `<instance>/<entry>`. Mobs stand in the middle of
+    /// their cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefab` - Prefab to place
+    /// * `instance` - Position, rotation and property overrides of the placement
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Ok if every entry was placed, problem description otherwise.
+    ///   The map is unchanged on failure.
+    pub fn instantiate(
+        &mut self,
+        prefab: &Prefab,
+        instance: &PrefabInstance,
+    ) -> Result<(), String> {
+        let name = &instance.name;
+        if let Some(entry) = instance.overrides.keys().find(|entry| !prefab.contains(entry)) {
+            return Err(format!("instance `{name}` overrides unknown entry `{entry}`"));
+        }
+        let place = |entry: &str, x: u32, y: u32| {
+            instance
+                .place(x, y)
+                .ok_or_else(|| format!("instance `{name}` places `{entry}` outside the map"))
+        };
+        let entry_name = |entry: &str| format!("{name}/{entry}");
+        let taken = |entry: String, exists: bool| {
+            if exists {
+                Err(format!("instance `{name}` places `{entry}`, the name is already used"))
+            } else {
+                Ok(entry)
+            }
+        };
+
+        let mut tiles = Vec::with_capacity(prefab.tiles.len());
+        for (entry, tile) in &prefab.tiles {
+            let (x, y) = place(entry, tile.x, tile.y)?;
+            let full = entry_name(entry);
+            let exists = self.tiles.contains_key(&full);
+            let properties = instance.properties(entry, &tile.properties);
+            tiles.push(Tile { name: taken(full, exists)?, x, y, properties, ..tile.clone() });
+        }
+
+        let mut objects = Vec::with_capacity(prefab.objects.len());
+        for (entry, object) in &prefab.objects {
+            let (x, y) = place(entry, object.x, object.y)?;
+            let full = entry_name(entry);
+            let exists = self.objects.contains_key(&full);
+            let properties = instance.properties(entry, &object.properties);
+            objects.push(Object { name: taken(full, exists)?, x, y, properties, ..object.clone() });
+        }
+
+        let mut mobs = Vec::with_capacity(prefab.mobs.len());
+        for (entry, mob) in &prefab.mobs {
+            let (x, y) = place(entry, mob.x_start, mob.y_start)?;
+            let (x, y) = self.tile_to_world(x as f32 + 0.5, y as f32 + 0.5);
+            let full = entry_name(entry);
+            let exists = self.mobs.contains_key(&full);
+            let properties = instance.properties(entry, &mob.properties);
+            mobs.push(Mob {
+                name: taken(full, exists)?,
+                x_start: x.round().max(0.0) as u32,
+                y_start: y.round().max(0.0) as u32,
+                properties,
+                ..mob.clone()
+            });
+        }
+
+        self.tiles.extend(tiles.into_iter().map(|tile| (tile.name.clone(), tile)));
+        self.objects.extend(objects.into_iter().map(|object| (object.name.clone(), object)));
+        self.mobs.extend(mobs.into_iter().map(|mob| (mob.name.clone(), mob)));
+        Ok(())
+    }
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::Property;
+    use crate::test_util::TempDir;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    /// Builds a 10x10 map placing the `hut` prefab with the given instances.
+    fn map_with(instances: &str) -> Result<GameMap, AssetError> {
+        GameMap::from_str(&format!(
+            r#"{{
+                "meta": {{"name": "prefabs", "tile_size": 16, "size": [10, 10]}},
+                "objects": {{"hut/door": {{"x": 0, "y": 0, "asset": "door"}}}},
+                "mobs": {{}},
+                "prefabs": {{
+                    "hut": {{
+                        "tiles": {{"floor": {{"x": 1, "y": 0, "asset": "planks", "z": 1}}}},
+                        "objects": {{
+                            "door": {{"x": 0, "y": 0, "asset": "door", "collidable": true,
+                                      "properties": {{"locked": true, "key": "red"}}}},
+                            "wall": {{"x": 2, "y": 1, "asset": "wall"}}
+                        }},
+                        "mobs": {{"cat": {{"x_start": 1, "y_start": 1, "asset": "cat"}}}}
+                    }}
+                }},
+                "instances": {{{instances}}}
+            }}"#
+        ))
+    }
+
+    // Test that instances expand into named, rotated and overridden entries
+    #[test]
+    fn test_instances_expand() {
+        let map = map_with(
+            r#""a": {"prefab": "hut", "x": 3, "y": 4},
+               "b": {"prefab": "hut", "x": 5, "y": 5, "rotation": 90,
+                     "properties": {"owner": "b"}, "overrides": {"door": {"locked": false}}},
+               "c": {"prefab": "hut", "x": 5, "y": 5, "rotation": 540}"#,
+        )
+        .unwrap();
+        assert_eq!((map.tile_count(), map.object_count(), map.mob_count()), (3, 7, 3));
+
+        let floor = map.get_tile("a/floor").unwrap();
+        assert_eq!((floor.x, floor.y, floor.z, floor.asset.as_str()), (4, 4, 1, "planks"));
+        let door = map.get_object("a/door").unwrap();
+        assert_eq!((door.x, door.y, door.collidable), (3, 4, true));
+        assert_eq!(door.properties.get_bool("locked"), Some(true));
+
+        // A quarter turn clockwise takes (x, y) to (-y, x) around the origin
+        assert_eq!(map.get_object("b/wall").unwrap().position(), (4, 7));
+        assert_eq!(map.get_tile("b/floor").unwrap().position(), (5, 6));
+        let door = map.get_object("b/door").unwrap();
+        assert_eq!(door.properties.get_bool("locked"), Some(false));
+        assert_eq!(door.properties.get_str("key"), Some("red"));
+        assert_eq!(door.properties.get("owner"), Some(&Property::String("b".into())));
+        assert_eq!(map.get_object("b/wall").unwrap().properties.get_str("owner"), Some("b"));
+
+        // 540 degrees is half a turn
+        assert_eq!(map.get_object("c/wall").unwrap().position(), (3, 4));
+
+        // Mobs stand in the middle of their cell
+        let cat = map.get_mob("b/cat").unwrap();
+        let (x, y) = map.tile_to_world(4.5, 6.5);
+        assert_eq!(cat.start_position(), (x.round() as u32, y.round() as u32));
+        assert!(!cat.is_player);
+
+        // Saved maps keep the prefabs and instances instead of the expanded entries
+        let json = map.to_json();
+        assert_eq!(GameMap::from_str(&json).unwrap(), map);
+        assert!(json.contains("\"instances\"") && json.contains("\"rotation\": 90"));
+        assert!(json.contains("\"hut/door\"") && !json.contains("\"a/door\""));
+
+        // Changes to expanded entries are not saved, overrides are
+        let mut edited = map.clone();
+        edited.objects.get_mut("a/door").unwrap().collidable = false;
+        let overrides = &mut edited.instances.get_mut("b").unwrap().overrides;
+        overrides.get_mut("door").unwrap().insert("key", Property::String("blue".into()));
+        let reloaded = GameMap::from_str(&edited.to_json()).unwrap();
+        assert!(reloaded.get_object("a/door").unwrap().collidable);
+        assert_eq!(reloaded.get_object("b/door").unwrap().properties.get_str("key"), Some("blue"));
+    }
+
+    // Test that broken instances fail to load with a description
+    #[test]
+    fn test_instance_errors() {
+        let message = |instances: &str| match map_with(instances).unwrap_err() {
+            AssetError::Invalid { message, .. } => message,
+            err => panic!("unexpected error: {err}"),
+        };
+
+        assert_eq!(
+            message(r#""a": {"prefab": "castle", "x": 0, "y": 0}"#),
+            "instance `a` places unknown prefab `castle`"
+        );
+        assert_eq!(
+            message(r#""a": {"prefab": "hut", "x": 3, "y": 3, "rotation": 45}"#),
+            "instance `a` has rotation 45, only multiples of 90 are supported"
+        );
+        assert_eq!(
+            message(r#""a": {"prefab": "hut", "x": 1, "y": 3, "rotation": 180}"#),
+            "instance `a` places `wall` outside the map"
+        );
+        assert_eq!(
+            message(r#""a": {"prefab": "hut", "x": 3, "y": 3, "overrides": {"dog": {}}}"#),
+            "instance `a` overrides unknown entry `dog`"
+        );
+        assert_eq!(
+            message(r#""hut": {"prefab": "hut", "x": 3, "y": 3}"#),
+            "instance `hut` places `hut/door`, the name is already used"
+        );
+    }
+
+    // Test that prefab files are read relative to the map
+    #[test]
+    fn test_prefab_files() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let map = GameMap::load(project_root.join("examples/prefabs.json")).unwrap();
+        let pen = Prefab::load(project_root.join("examples/prefabs/pen.json")).unwrap();
+
+        for pen_name in ["north_pen", "south_pen"] {
+            for entry in pen.objects.keys() {
+                assert!(map.get_object(&format!("{pen_name}/{entry}")).is_some());
+            }
+        }
+        assert_eq!(
+            map.get_object("south_pen/gate").unwrap().properties.get_bool("locked"),
+            Some(false)
+        );
+        assert_eq!(
+            map.get_object("north_pen/gate").unwrap().properties.get_bool("locked"),
+            Some(true)
+        );
+        assert_eq!(map.get_object("grove/cactus_b").unwrap().position(), (8, 3));
+
+        // Saving keeps the prefab file paths
+        let dir = TempDir::new("prefabs");
+        std::fs::create_dir(dir.join("prefabs")).unwrap();
+        std::fs::copy(project_root.join("examples/prefabs/pen.json"), dir.join("prefabs/pen.json"))
+            .unwrap();
+        map.save(dir.join("saved.json")).unwrap();
+        assert!(map.to_json().contains("\"pen\": \"prefabs/pen.json\""));
+        assert_eq!(GameMap::load(dir.join("saved.json")).unwrap(), map);
+
+        let map_path = dir.join("map.json");
+        std::fs::write(
+            &map_path,
+            r#"{"meta": {"name": "m", "size": [4, 4]}, "objects": {}, "mobs": {},
+                "prefabs": {"gone": "missing.json"}}"#,
+        )
+        .unwrap();
+        let err = GameMap::load(&map_path).unwrap_err();
+        assert!(matches!(err, AssetError::Io { .. }));
+        assert_eq!(err.path(), dir.join("missing.json"));
+
+        // Maps loaded from memory have no directory to read prefab files from
+        let json = std::fs::read_to_string(project_root.join("examples/prefabs.json")).unwrap();
+        match GameMap::from_str(&json).unwrap_err() {
+            AssetError::Invalid { message, .. } => assert_eq!(
+                message,
+                "prefab `pen` reads `prefabs/pen.json`, maps from memory need inline prefabs"
+            ),
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+}
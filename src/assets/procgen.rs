@@ -0,0 +1,710 @@
+use std::collections::HashMap;
+
+use super::{
+    Behaviour, BehaviourType, GameMap, Mob, MobTemplate, Object, Properties, Region, RegionShape,
+    RegionSpace, TileLayer,
+};
+use crate::rng::Rng;
+
+// ============================
+// Game-level structs
+// ============================
+
+/// Ground type of a generated cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Biome {
+    /// Dry ground between sand and grass
+    Dirt,
+    /// Wet lowland
+    Grass,
+    /// Highland
+    Rock,
+    /// Dry lowland
+    Sand,
+}
+
+/// Rule for scattering objects over a biome.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectRule {
+    /// Asset identifier of the object
+    pub asset: String,
+    /// Biome the object grows on
+    pub biome: Biome,
+    /// Chance of a cell of the biome to get the object, from 0 to 1
+    pub density: f32,
+    /// Whether the object blocks movement
+    pub collidable: bool,
+    /// Whether the object casts a shadow
+    pub shadow: bool,
+}
+
+/// Settings of the terrain generator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerrainOptions {
+    /// Tile size written to the map
+    pub tile_size: u32,
+    /// Size of noise features in cells, larger values give larger biomes
+    pub scale: f32,
+    /// Number of noise layers, each adds detail at half the size of the previous one
+    pub octaves: u32,
+    /// Elevation from 0 to 1 above which cells are rock
+    pub rock_level: f32,
+    /// Moisture from 0 to 1 below which cells are sand
+    pub sand_level: f32,
+    /// Moisture from 0 to 1 below which cells are dirt, above it grass grows
+    pub dirt_level: f32,
+    /// Share of cells drawn with the small tile frame of their biome
+    pub detail: f32,
+    /// Object scattering rules, the first matching rule wins a cell
+    pub objects: Vec<ObjectRule>,
+    /// Asset of the player placed at the map centre, None for a map without player
+    pub player: Option<String>,
+}
+
+impl Default for TerrainOptions {
+    /// Creates options for the bundled tile atlas with cacti in the desert.
+    ///
+    /// # Returns
+    ///
+    /// A new `TerrainOptions` instance with default values.
+    fn default() -> Self {
+        Self {
+            tile_size: 16,
+            scale: 8.0,
+            octaves: 3,
+            rock_level: 0.68,
+            sand_level: 0.42,
+            dirt_level: 0.5,
+            detail: 0.1,
+            objects: vec![ObjectRule {
+                asset: "cactus_long_3_9".to_string(),
+                biome: Biome::Sand,
+                density: 0.08,
+                collidable: true,
+                shadow: true,
+            }],
+            player: Some("knight_0_0".to_string()),
+        }
+    }
+}
+
+/// Settings of the dungeon generator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DungeonOptions {
+    /// Tile size written to the map
+    pub tile_size: u32,
+    /// Upper limit of rooms, fewer are carved when they do not fit
+    pub max_rooms: u32,
+    /// Smallest room side in cells
+    pub room_min: u32,
+    /// Largest room side in cells
+    pub room_max: u32,
+    /// Tile frame of room floors
+    pub floor: String,
+    /// Tile frame of corridor floors
+    pub corridor: String,
+    /// Tile frame under walls
+    pub wall_floor: String,
+    /// Wall object of walls running along the y axis
+    pub wall_rising: String,
+    /// Wall object of walls running along the x axis
+    pub wall_falling: String,
+    /// Number of mobs in every room but the first
+    pub mobs_per_room: u32,
+    /// Mobs to pick from, at random, for the rooms
+    pub monsters: Vec<MobTemplate>,
+    /// Asset of the player placed in the first room
+    pub player: String,
+}
+
+impl Default for DungeonOptions {
+    /// Creates options for the bundled atlases with fence walls, imps and ghosts.
+    ///
+    /// # Returns
+    ///
+    /// A new `DungeonOptions` instance with default values.
+    fn default() -> Self {
+        let walker = |asset: &str, direction: &str, speed: f32| MobTemplate {
+            asset: asset.to_string(),
+            behaviour: Some(Behaviour {
+                behaviour_type: BehaviourType::Walker,
+                direction: Some(direction.to_string()),
+                speed: Some(speed),
+                params: Properties::new(),
+            }),
+            properties: Properties::new(),
+        };
+
+        Self {
+            tile_size: 16,
+            max_rooms: 8,
+            room_min: 4,
+            room_max: 8,
+            floor: "dirt_tile_big_0_0".to_string(),
+            corridor: "dirt_tile_small_1_0".to_string(),
+            wall_floor: "rock_tile_big_0_2".to_string(),
+            wall_rising: "fence_rising_11_10".to_string(),
+            wall_falling: "fence_falling_10_10".to_string(),
+            mobs_per_room: 2,
+            monsters: vec![walker("imp_20_0", "left", 0.5), walker("ghost_30_0", "right", 0.42)],
+            player: "knight_0_0".to_string(),
+        }
+    }
+}
+
+/// Kind of a dungeon cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DungeonCell {
+    /// Uncarved rock
+    Solid,
+    /// Floor of a room
+    Room,
+    /// Floor of a corridor
+    Corridor,
+}
+
+/// Rectangular room of a dungeon, in cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Room {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+/// Seeded 2D value noise.
+struct ValueNoise {
+    /// Seed of the lattice values
+    seed: u64,
+}
+
+// ============================
+// Implementation
+// ============================
+
+impl Biome {
+    /// All biomes in tileset order.
+    pub const ALL: [Biome; 4] = [Biome::Dirt, Biome::Grass, Biome::Rock, Biome::Sand];
+
+    /// Returns the main tile frame of the biome.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - Frame name in the bundled tile atlas.
+    pub fn tile(&self) -> &str {
+        match self {
+            Biome::Dirt => "dirt_tile_big_0_0",
+            Biome::Grass => "grass_tile_big_0_1",
+            Biome::Rock => "rock_tile_big_0_2",
+            Biome::Sand => "sand_tile_big_0_3",
+        }
+    }
+
+    /// Returns the detail tile frame of the biome.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - Frame name in the bundled tile atlas.
+    pub fn detail_tile(&self) -> &str {
+        match self {
+            Biome::Dirt => "dirt_tile_small_1_0",
+            Biome::Grass => "grass_tile_small_1_1",
+            Biome::Rock => "rock_tile_small_1_2",
+            Biome::Sand => "sand_tile_small_1_3",
+        }
+    }
+}
+
+impl TerrainOptions {
+    /// Picks the biome of a cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `elevation` - Elevation noise from 0 to 1
+    /// * `moisture` - Moisture noise from 0 to 1
+    ///
+    /// # Returns
+    ///
+    /// * `Biome` - Rock above `rock_level`, otherwise sand, dirt or grass by moisture.
+    pub fn biome(&self, elevation: f32, moisture: f32) -> Biome {
+        if elevation >= self.rock_level {
+            Biome::Rock
+        } else if moisture < self.sand_level {
+            Biome::Sand
+        } else if moisture < self.dirt_level {
+            Biome::Dirt
+        } else {
+            Biome::Grass
+        }
+    }
+}
+
+impl Room {
+    /// Returns the centre cell of the room.
+    fn centre(&self) -> (u32, u32) {
+        (self.x + self.w / 2, self.y + self.h / 2)
+    }
+
+    /// Checks if two rooms overlap or touch without a wall cell between them.
+    fn overlaps(&self, other: &Room) -> bool {
+        self.x < other.x + other.w + 1
+            && other.x < self.x + self.w + 1
+            && self.y < other.y + other.h + 1
+            && other.y < self.y + self.h + 1
+    }
+}
+
+impl ValueNoise {
+    /// Random value of a lattice point, from 0 to 1.
+    fn lattice(&self, x: i64, y: i64) -> f32 {
+        let key = (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            ^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+        Rng::new(self.seed ^ key).next_f32()
+    }
+
+    /// Smoothly interpolated noise at a point, from 0 to 1.
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+        let (ix, iy) = (x0 as i64, y0 as i64);
+
+        let top = self.lattice(ix, iy) + (self.lattice(ix + 1, iy) - self.lattice(ix, iy)) * tx;
+        let bottom = self.lattice(ix, iy + 1)
+            + (self.lattice(ix + 1, iy + 1) - self.lattice(ix, iy + 1)) * tx;
+        top + (bottom - top) * ty
+    }
+
+    /// Sum of octaves of noise, each twice as fine and half as strong, from 0 to 1.
+    fn fractal(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let (mut total, mut weight, mut amplitude, mut frequency) = (0.0, 0.0, 1.0, 1.0);
+        for octave in 0..octaves.max(1) {
+            // Shift octaves so their lattices do not line up at the origin
+            let shift = octave as f32 * 17.31;
+            total += self.sample(x * frequency + shift, y * frequency + shift) * amplitude;
+            weight += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        total / weight
+    }
+}
+
+/// Generates a terrain map from a seed.
+///
+/// Two noise fields, elevation and moisture, choose the biome of every
+/// cell. Cells become the `ground` layer, drawn with the big tile frame of
+/// their biome or, for a share of cells, the small one. Objects are
+/// scattered by the density rules, the player stands on the centre cell.
+/// The same size, seed and options always give the same map.
+///
+/// # Arguments
+///
+/// * `size` - Map dimensions [width, height]
+/// * `seed` - Seed of the noise and the scattering
+/// * `options` - Generator settings
+///
+/// # Returns
+///
+/// * `GameMap` - Generated map named `terrain_<seed>`.
+pub fn generate_terrain(size: [u32; 2], seed: u64, options: &TerrainOptions) -> GameMap {
+    let [width, height] = size;
+    let elevation = ValueNoise { seed };
+    let moisture = ValueNoise { seed: seed ^ 0x6d6f_6973_7475_7265 };
+    let mut scatter = Rng::new(seed ^ 0x7363_6174_7465_7273);
+    let scale = options.scale.max(f32::EPSILON);
+
+    let tileset: Vec<String> = Biome::ALL
+        .iter()
+        .flat_map(|biome| [biome.tile().to_string(), biome.detail_tile().to_string()])
+        .collect();
+    let tile_index = |biome: Biome, detail: bool| {
+        let position = Biome::ALL.iter().position(|&b| b == biome).unwrap_or(0) as u32;
+        position * 2 + detail as u32 + 1
+    };
+
+    let centre = (width / 2, height / 2);
+    let mut cells = Vec::with_capacity(width as usize * height as usize);
+    let mut objects = HashMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            let (nx, ny) = (x as f32 / scale, y as f32 / scale);
+            let biome = options.biome(
+                elevation.fractal(nx, ny, options.octaves),
+                moisture.fractal(nx, ny, options.octaves),
+            );
+            cells.push(tile_index(biome, scatter.next_f32() < options.detail));
+
+            let roll = scatter.next_f32();
+            if (x, y) == centre && options.player.is_some() {
+                continue;
+            }
+            let mut chance = 0.0;
+            for rule in options.objects.iter().filter(|rule| rule.biome == biome) {
+                chance += rule.density;
+                if roll < chance {
+                    let name = format!("obj_{}", objects.len() + 1);
+                    let object = Object {
+                        name: name.clone(),
+                        x,
+                        y,
+                        asset: rule.asset.clone(),
+                        collidable: rule.collidable,
+                        shadow: rule.shadow,
+                        z: 0,
+                        properties: Properties::new(),
+                    };
+                    objects.insert(name, object);
+                    break;
+                }
+            }
+        }
+    }
+
+    let mut game_map = new_map(format!("terrain_{seed}"), options.tile_size, size, tileset, cells);
+    game_map.objects = objects;
+
+    if let Some(asset) = &options.player {
+        let player = player_mob(&game_map, centre, asset);
+        game_map.mobs.insert(player.name.clone(), player);
+    }
+    game_map
+}
+
+/// Generates a dungeon of rooms joined by corridors from a seed.
+///
+/// Rooms are placed at random without overlapping and each room is joined to
+/// the previous one by an L-shaped corridor, so every floor cell can be
+/// reached. Rock cells next to floor become collidable wall objects, picked
+/// by the direction the wall runs in. The player stands in the centre of the
+/// first room, the other rooms get walker mobs. Every room is also added as
+/// a tile region `room_<n>` tagged `room`, the first one also `start`.
+/// A map too small for a single room has no rooms and no player.
+///
+/// # Arguments
+///
+/// * `size` - Map dimensions [width, height]
+/// * `seed` - Seed of the layout and the mobs
+/// * `options` - Generator settings
+///
+/// # Returns
+///
+/// * `GameMap` - Generated map named `dungeon_<seed>`.
+pub fn generate_dungeon(size: [u32; 2], seed: u64, options: &DungeonOptions) -> GameMap {
+    let [width, height] = size;
+    let mut rng = Rng::new(seed);
+
+    let room_min = options.room_min.max(1);
+    let room_max = options.room_max.max(room_min);
+    let mut rooms: Vec<Room> = Vec::new();
+    for _ in 0..options.max_rooms * 8 {
+        if rooms.len() >= options.max_rooms as usize {
+            break;
+        }
+        let w = rng.range_u32(room_min, room_max + 1);
+        let h = rng.range_u32(room_min, room_max + 1);
+        // Keep a wall cell between the room and the map border
+        if w + 2 > width || h + 2 > height {
+            continue;
+        }
+        let room = Room { x: rng.range_u32(1, width - w), y: rng.range_u32(1, height - h), w, h };
+        if rooms.iter().all(|other| !room.overlaps(other)) {
+            rooms.push(room);
+        }
+    }
+
+    let index = |x: u32, y: u32| y as usize * width as usize + x as usize;
+    let mut grid = vec![DungeonCell::Solid; width as usize * height as usize];
+    for room in &rooms {
+        for y in room.y..room.y + room.h {
+            for x in room.x..room.x + room.w {
+                grid[index(x, y)] = DungeonCell::Room;
+            }
+        }
+    }
+    for pair in rooms.windows(2) {
+        let ((ax, ay), (bx, by)) = (pair[0].centre(), pair[1].centre());
+        let corner = if rng.next_u64() & 1 == 0 { (bx, ay) } else { (ax, by) };
+        for ((x0, y0), (x1, y1)) in [((ax, ay), corner), (corner, (bx, by))] {
+            for y in y0.min(y1)..=y0.max(y1) {
+                for x in x0.min(x1)..=x0.max(x1) {
+                    if grid[index(x, y)] == DungeonCell::Solid {
+                        grid[index(x, y)] = DungeonCell::Corridor;
+                    }
+                }
+            }
+        }
+    }
+
+    let is_floor = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && x < width as i64
+            && y < height as i64
+            && grid[index(x as u32, y as u32)] != DungeonCell::Solid
+    };
+    let tileset = vec![options.floor.clone(), options.corridor.clone(), options.wall_floor.clone()];
+    let mut cells = Vec::with_capacity(grid.len());
+    let mut objects = HashMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            match grid[index(x, y)] {
+                DungeonCell::Room => cells.push(1),
+                DungeonCell::Corridor => cells.push(2),
+                DungeonCell::Solid => {
+                    let (cx, cy) = (x as i64, y as i64);
+                    let near_floor = (-1..=1)
+                        .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                        .any(|(dx, dy)| is_floor(cx + dx, cy + dy));
+                    if !near_floor {
+                        cells.push(0);
+                        continue;
+                    }
+                    cells.push(3);
+
+                    // Floor above or below means the wall runs along the x axis
+                    let asset = if is_floor(cx, cy - 1) || is_floor(cx, cy + 1) {
+                        &options.wall_falling
+                    } else {
+                        &options.wall_rising
+                    };
+                    let name = format!("obj_{}", objects.len() + 1);
+                    let object = Object {
+                        name: name.clone(),
+                        x,
+                        y,
+                        asset: asset.clone(),
+                        collidable: true,
+                        shadow: false,
+                        z: 0,
+                        properties: Properties::new(),
+                    };
+                    objects.insert(name, object);
+                }
+            }
+        }
+    }
+
+    let mut game_map = new_map(format!("dungeon_{seed}"), options.tile_size, size, tileset, cells);
+    game_map.objects = objects;
+
+    for (i, room) in rooms.iter().enumerate() {
+        let name = format!("room_{}", i + 1);
+        let mut tags = vec!["room".to_string()];
+        if i == 0 {
+            tags.push("start".to_string());
+            let player = player_mob(&game_map, room.centre(), &options.player);
+            game_map.mobs.insert(player.name.clone(), player);
+        } else if !options.monsters.is_empty() {
+            for _ in 0..options.mobs_per_room {
+                let template = rng.range_u32(0, options.monsters.len() as u32) as usize;
+                let cell = (
+                    rng.range_u32(room.x, room.x + room.w),
+                    rng.range_u32(room.y, room.y + room.h),
+                );
+                let name = format!("mob_{}", game_map.mobs.len());
+                let mob = mob_at(&game_map, name.clone(), cell, &options.monsters[template]);
+                game_map.mobs.insert(name, mob);
+            }
+        }
+
+        let shape = RegionShape::Rect {
+            x: room.x as f32,
+            y: room.y as f32,
+            w: room.w as f32,
+            h: room.h as f32,
+        };
+        let region = Region {
+            name: name.clone(),
+            space: RegionSpace::Tile,
+            shape,
+            tags,
+            properties: Properties::new(),
+        };
+        game_map.regions.insert(name, region);
+    }
+    game_map
+}
+
+/// Creates a map without mobs, regions and spawners.
+fn new_map(
+    name: String,
+    tile_size: u32,
+    size: [u32; 2],
+    tileset: Vec<String>,
+    cells: Vec<u32>,
+) -> GameMap {
+    GameMap {
+        name,
+        tile_size,
+        size,
+        mobs: HashMap::new(),
+        objects: HashMap::new(),
+        tiles: HashMap::new(),
+        tileset,
+        layers: vec![TileLayer { name: "ground".to_string(), cells, ..TileLayer::default() }],
+        regions: HashMap::new(),
+        spawners: HashMap::new(),
+        prefabs: HashMap::new(),
+        instances: HashMap::new(),
+        properties: Properties::new(),
+    }
+}
+
+/// Creates a mob standing on the centre of a cell.
+fn mob_at(game_map: &GameMap, name: String, cell: (u32, u32), template: &MobTemplate) -> Mob {
+    let (x, y) = game_map.tile_to_world(cell.0 as f32 + 0.5, cell.1 as f32 + 0.5);
+    Mob {
+        name,
+        x_start: x.round().max(0.0) as u32,
+        y_start: y.round().max(0.0) as u32,
+        asset: template.asset.clone(),
+        is_player: false,
+        behaviour: template.behaviour.clone(),
+        properties: template.properties.clone(),
+    }
+}
+
+/// Creates the player mob named `player` on a cell.
+fn player_mob(game_map: &GameMap, cell: (u32, u32), asset: &str) -> Mob {
+    let template = MobTemplate {
+        asset: asset.to_string(),
+        behaviour: Some(Behaviour {
+            behaviour_type: BehaviourType::Controlled,
+            direction: None,
+            speed: None,
+            params: Properties::new(),
+        }),
+        properties: Properties::new(),
+    };
+    Mob { is_player: true, ..mob_at(game_map, "player".to_string(), cell, &template) }
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::Atlas;
+    use std::path::PathBuf;
+
+    // Test that noise is reproducible, bounded and continuous
+    #[test]
+    fn test_value_noise() {
+        let noise = ValueNoise { seed: 5 };
+        for i in 0..200 {
+            let (x, y) = (i as f32 * 0.37 - 20.0, i as f32 * 0.11);
+            let value = noise.fractal(x, y, 3);
+            assert!((0.0..1.0).contains(&value));
+            assert_eq!(value, ValueNoise { seed: 5 }.fractal(x, y, 3));
+            assert!((noise.sample(x, y) - noise.sample(x + 0.001, y)).abs() < 0.01);
+        }
+        assert_eq!(noise.sample(3.0, 4.0), noise.lattice(3, 4));
+        assert_ne!(noise.lattice(3, 4), ValueNoise { seed: 6 }.lattice(3, 4));
+    }
+
+    // Test that a seed always gives the same valid map with every biome
+    #[test]
+    fn test_generate_terrain() {
+        let options = TerrainOptions::default();
+        let map = generate_terrain([48, 40], 2024, &options);
+        assert_eq!(map, generate_terrain([48, 40], 2024, &options));
+        assert_ne!(map.layers, generate_terrain([48, 40], 2025, &options).layers);
+        assert_eq!(map.name, "terrain_2024");
+        assert_eq!(map.size, [48, 40]);
+
+        let ground = map.get_layer("ground").unwrap();
+        assert_eq!(ground.cells.len(), 48 * 40);
+        let assets: Vec<&str> = map.iter_layer_cells(ground).map(|(_, _, asset)| asset).collect();
+        assert_eq!(assets.len(), 48 * 40);
+        for biome in Biome::ALL {
+            assert!(assets.contains(&biome.tile()), "no {biome:?}");
+        }
+        assert!(assets.iter().any(|asset| asset.contains("small")));
+
+        assert!(map.object_count() > 0);
+        for object in map.iter_objects() {
+            let asset = map.layer_asset(ground, object.x, object.y).unwrap();
+            assert!(asset.starts_with("sand_"), "{asset}");
+        }
+
+        let player = map.get_mob("player").unwrap();
+        assert_eq!(map.world_to_tile(player.x_start as f32, player.y_start as f32), (24.5, 20.5));
+
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let tiles_atlas = Atlas::load(project_root.join("assets/tiles/atlas.json")).unwrap();
+        let entities_atlas = Atlas::load(project_root.join("assets/entities/atlas.json")).unwrap();
+        let report = map.validate(&tiles_atlas, &entities_atlas);
+        assert!(report.is_ok(), "{report}");
+
+        let empty = TerrainOptions { objects: Vec::new(), player: None, ..options };
+        let bare = generate_terrain([8, 8], 1, &empty);
+        assert_eq!((bare.object_count(), bare.mob_count()), (0, 0));
+    }
+
+    // Test that dungeons are reproducible, connected, walled and valid after saving
+    #[test]
+    fn test_generate_dungeon() {
+        let options = DungeonOptions::default();
+        let map = generate_dungeon([40, 32], 7, &options);
+        assert_eq!(map, generate_dungeon([40, 32], 7, &options));
+        assert_ne!(map.layers, generate_dungeon([40, 32], 8, &options).layers);
+        assert_eq!(map.name, "dungeon_7");
+
+        let rooms: Vec<&Region> = map.iter_regions().collect();
+        assert!(rooms.len() >= 3, "{} rooms", rooms.len());
+        let start = map.get_region("room_1").unwrap();
+        assert!(start.has_tag("start") && start.has_tag("room"));
+
+        let player = map.get_mob("player").unwrap();
+        assert!(player.is_player);
+        assert!(map.region_contains(start, player.x_start as f32, player.y_start as f32));
+        assert_eq!(map.mob_count(), 1 + (rooms.len() - 1) * options.mobs_per_room as usize);
+        for mob in map.iter_mobs().filter(|mob| !mob.is_player) {
+            let behaviour = mob.behaviour.as_ref().unwrap();
+            assert_eq!(behaviour.behaviour_type, BehaviourType::Walker);
+            let (x, y) = (mob.x_start as f32, mob.y_start as f32);
+            assert!(rooms
+                .iter()
+                .any(|room| room.name != "room_1" && map.region_contains(room, x, y)));
+        }
+
+        // Walls stand on the wall floor and every floor cell is reachable from the player
+        let ground = map.get_layer("ground").unwrap();
+        let walls: std::collections::HashSet<(u32, u32)> =
+            map.iter_objects().map(|object| object.position()).collect();
+        for object in map.iter_objects() {
+            assert!(object.collidable);
+            assert_eq!(map.layer_asset(ground, object.x, object.y), Some("rock_tile_big_0_2"));
+        }
+        let floor: Vec<(u32, u32)> = map
+            .iter_layer_cells(ground)
+            .filter(|(x, y, _)| !walls.contains(&(*x, *y)))
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        let (px, py) = map.world_to_tile(player.x_start as f32, player.y_start as f32);
+        let mut seen = std::collections::HashSet::from([(px as u32, py as u32)]);
+        let mut queue = vec![(px as u32, py as u32)];
+        while let Some((x, y)) = queue.pop() {
+            for next in [(x + 1, y), (x.wrapping_sub(1), y), (x, y + 1), (x, y.wrapping_sub(1))] {
+                if floor.contains(&next) && seen.insert(next) {
+                    queue.push(next);
+                }
+            }
+        }
+        assert_eq!(seen.len(), floor.len());
+
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let tiles_atlas = Atlas::load(project_root.join("assets/tiles/atlas.json")).unwrap();
+        let entities_atlas = Atlas::load(project_root.join("assets/entities/atlas.json")).unwrap();
+        let saved: GameMap = map.to_json().parse().unwrap();
+        assert_eq!(saved, map);
+        let report = saved.validate(&tiles_atlas, &entities_atlas);
+        assert!(report.is_ok(), "{report}");
+
+        let tiny = generate_dungeon([4, 4], 7, &options);
+        assert_eq!((tiny.mob_count(), tiny.object_count()), (0, 0));
+    }
+}
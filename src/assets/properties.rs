@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// ============================
+// Game-level structs
+// ============================
+
+/// Value of a custom property.
+///
+/// Whole JSON numbers become `Int`, numbers with a fraction or exponent become `Float`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Property {
+    /// `true` or `false`
+    Bool(bool),
+    /// Whole number
+    Int(i64),
+    /// Floating point number
+    Float(f64),
+    /// Text
+    String(String),
+    /// List of values
+    Array(Vec<Property>),
+}
+
+/// Custom key/value data of a map element, written as a JSON object.
+///
+/// Keys are kept sorted, so saved maps list them in a stable order.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Properties(BTreeMap<String, Property>);
+
+// ============================
+// Implementation
+// ============================
+
+impl Property {
+    /// Returns the value as a string slice.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - Text of a `String` value, None for other types.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Property::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a boolean.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<bool>` - Value of a `Bool`, None for other types.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Property::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a whole number.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<i64>` - Value of an `Int`, None for other types.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Property::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a floating point number.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<f64>` - Value of a `Float` or an `Int`, None for other types.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Property::Float(value) => Some(*value),
+            Property::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a list.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&[Property]>` - Items of an `Array`, None for other types.
+    pub fn as_array(&self) -> Option<&[Property]> {
+        match self {
+            Property::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl Properties {
+    /// Creates an empty set of properties.
+    ///
+    /// # Returns
+    ///
+    /// A new `Properties` instance without keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Retrieves a property by key.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Property>` - Reference to the value if found, None otherwise.
+    pub fn get(&self, key: &str) -> Option<&Property> {
+        self.0.get(key)
+    }
+
+    /// Retrieves a string property.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&str>` - Text if the property exists and is a string, None otherwise.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    /// Retrieves a boolean property.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// * `Option<bool>` - Value if the property exists and is a boolean, None otherwise.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get(key)?.as_bool()
+    }
+
+    /// Retrieves a whole number property.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// * `Option<i64>` - Value if the property exists and is a whole number, None otherwise.
+    pub fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get(key)?.as_i64()
+    }
+
+    /// Retrieves a number property, whole numbers are converted.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// * `Option<f64>` - Value if the property exists and is a number, None otherwise.
+    pub fn get_f64(&self, key: &str) -> Option<f64> {
+        self.get(key)?.as_f64()
+    }
+
+    /// Retrieves a list property.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&[Property]>` - Items if the property exists and is a list, None otherwise.
+    pub fn get_array(&self, key: &str) -> Option<&[Property]> {
+        self.get(key)?.as_array()
+    }
+
+    /// Sets a property, replacing the previous value.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    /// * `value` - New value
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Property>` - Previous value if the key was set, None otherwise.
+    pub fn insert<K: Into<String>>(&mut self, key: K, value: Property) -> Option<Property> {
+        self.0.insert(key.into(), value)
+    }
+
+    /// Removes a property.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the property
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Property>` - Removed value if the key was set, None otherwise.
+    pub fn remove(&mut self, key: &str) -> Option<Property> {
+        self.0.remove(key)
+    }
+
+    /// Checks if no properties are set.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if there are no keys, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the number of properties.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - Count of keys.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns an iterator over all properties in key order.
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = (&str, &Property)>` - Iterator over keys and values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Property)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value))
+    }
+}
+
+impl FromIterator<(String, Property)> for Properties {
+    fn from_iter<I: IntoIterator<Item = (String, Property)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Extend<(String, Property)> for Properties {
+    fn extend<I: IntoIterator<Item = (String, Property)>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test that JSON values keep their types and getters convert only where sensible
+    #[test]
+    fn test_property_types() {
+        let json = r#"{"music": "desert.ogg", "level": 3, "chance": 0.25, "boss": true,
+                       "loot": ["gold", 10, 1.5]}"#;
+        let properties: Properties = serde_json::from_str(json).unwrap();
+
+        assert_eq!(properties.len(), 5);
+        assert_eq!(properties.get_str("music"), Some("desert.ogg"));
+        assert_eq!(properties.get_i64("level"), Some(3));
+        assert_eq!(properties.get_f64("level"), Some(3.0));
+        assert_eq!(properties.get_f64("chance"), Some(0.25));
+        assert_eq!(properties.get_i64("chance"), None);
+        assert_eq!(properties.get_bool("boss"), Some(true));
+        assert_eq!(properties.get_str("boss"), None);
+        assert_eq!(
+            properties.get_array("loot"),
+            Some(&[Property::String("gold".into()), Property::Int(10), Property::Float(1.5)][..])
+        );
+        assert!(properties.get("missing").is_none());
+
+        let text = serde_json::to_string(&properties).unwrap();
+        assert!(text.starts_with(r#"{"boss":true,"chance":0.25"#), "{text}");
+        assert_eq!(serde_json::from_str::<Properties>(&text).unwrap(), properties);
+
+        let float: Properties = serde_json::from_str(r#"{"x": 2.0}"#).unwrap();
+        let text = serde_json::to_string(&float).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Properties>(&text).unwrap().get("x"),
+            Some(&Property::Float(2.0))
+        );
+
+        assert!(serde_json::from_str::<Properties>(r#"{"nested": {"a": 1}}"#).is_err());
+        assert!(serde_json::from_str::<Properties>(r#"{"empty": null}"#).is_err());
+    }
+}
@@ -0,0 +1,462 @@
+use serde::{Deserialize, Serialize};
+
+use super::{GameMap, Properties};
+use crate::rng::Rng;
+
+// ============================
+// JSON-level structs
+// ============================
+
+/// Area outline from JSON, shared by regions and spawners.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JsonArea {
+    /// `tile` for cell coordinates, `world` for world buffer pixels
+    #[serde(default = "default_space", skip_serializing_if = "is_default_space")]
+    pub space: String,
+    /// Rectangle as [x, y, width, height]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rect: Option<[f32; 4]>,
+    /// Polygon as a list of [x, y] points
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polygon: Option<Vec<[f32; 2]>>,
+}
+
+/// Trigger region from JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JsonRegion {
+    /// Outline of the region
+    #[serde(flatten)]
+    pub area: JsonArea,
+    /// Tags for grouping regions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Custom game data of the region
+    #[serde(default, skip_serializing_if = "Properties::is_empty")]
+    pub properties: Properties,
+}
+
+fn default_space() -> String {
+    "tile".to_string()
+}
+
+fn is_default_space(space: &str) -> bool {
+    space == "tile"
+}
+
+// ============================
+// Game-level structs
+// ============================
+
+/// Coordinate space of a region shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionSpace {
+    /// Cell coordinates, cell (x, y) covers [x, x + 1) x [y, y + 1)
+    Tile,
+    /// World buffer pixels, the space of mob positions
+    World,
+}
+
+/// Outline of a region.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegionShape {
+    /// Axis-aligned rectangle
+    Rect {
+        /// Left edge
+        x: f32,
+        /// Top edge
+        y: f32,
+        /// Width, positive
+        w: f32,
+        /// Height, positive
+        h: f32,
+    },
+    /// Closed polygon of at least three points
+    Polygon(Vec<(f32, f32)>),
+}
+
+/// Named area of a map that reports units entering and leaving it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    /// Name of the region
+    pub name: String,
+    /// Coordinate space of the shape
+    pub space: RegionSpace,
+    /// Outline of the region
+    pub shape: RegionShape,
+    /// Tags for grouping regions
+    pub tags: Vec<String>,
+    /// Custom game data of the region
+    pub properties: Properties,
+}
+
+// ============================
+// Implementation
+// ============================
+
+impl JsonArea {
+    /// Builds the JSON outline of a shape.
+    pub(super) fn new(space: RegionSpace, shape: &RegionShape) -> Self {
+        let (rect, polygon) = match shape {
+            RegionShape::Rect { x, y, w, h } => (Some([*x, *y, *w, *h]), None),
+            RegionShape::Polygon(points) => {
+                (None, Some(points.iter().map(|&(x, y)| [x, y]).collect()))
+            }
+        };
+        JsonArea { space: space.name().to_string(), rect, polygon }
+    }
+
+    /// Checks the outline and converts it into a space and a shape.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - Description of the element with the area, for messages
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(RegionSpace, RegionShape), String>` - Space and shape if the outline is valid,
+    ///   problem description otherwise.
+    pub(super) fn parse(self, owner: &str) -> Result<(RegionSpace, RegionShape), String> {
+        let space = RegionSpace::from_name(&self.space)
+            .ok_or_else(|| format!("{owner} has unknown space `{}`", self.space))?;
+
+        let shape = match (self.rect, self.polygon) {
+            (Some([x, y, w, h]), None) => {
+                if w <= 0.0 || h <= 0.0 {
+                    return Err(format!("{owner} has an empty rectangle"));
+                }
+                RegionShape::Rect { x, y, w, h }
+            }
+            (None, Some(points)) => {
+                if points.len() < 3 {
+                    return Err(format!("{owner} has a polygon of {} points", points.len()));
+                }
+                RegionShape::Polygon(points.into_iter().map(|[x, y]| (x, y)).collect())
+            }
+            _ => return Err(format!("{owner} needs exactly one of `rect` and `polygon`")),
+        };
+        Ok((space, shape))
+    }
+}
+
+impl RegionSpace {
+    /// Parses a coordinate space from its JSON name.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tile" => Some(RegionSpace::Tile),
+            "world" => Some(RegionSpace::World),
+            _ => None,
+        }
+    }
+
+    /// Returns the JSON name of the coordinate space.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - `tile` or `world`.
+    pub fn name(&self) -> &str {
+        match self {
+            RegionSpace::Tile => "tile",
+            RegionSpace::World => "world",
+        }
+    }
+}
+
+impl RegionShape {
+    /// Checks if a point lies inside the shape.
+    ///
+    /// Rectangles include their top and left edges only, so neighbouring
+    /// rectangles never both contain a point.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X coordinate in the space of the shape
+    /// * `y` - Y coordinate in the space of the shape
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the point is inside, false otherwise.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        match self {
+            RegionShape::Rect { x: left, y: top, w, h } => {
+                x >= *left && x < left + w && y >= *top && y < top + h
+            }
+            RegionShape::Polygon(points) => {
+                // Even-odd rule: count edges crossed by a ray to the right of the point
+                let mut inside = false;
+                let mut previous = points[points.len() - 1];
+                for &(px, py) in points {
+                    let (qx, qy) = previous;
+                    if (py > y) != (qy > y) && x < (qx - px) * (y - py) / (qy - py) + px {
+                        inside = !inside;
+                    }
+                    previous = (px, py);
+                }
+                inside
+            }
+        }
+    }
+
+    /// Picks a uniformly distributed random point inside the shape.
+    ///
+    /// Polygons are sampled by rejection from their bounding box, a point is
+    /// given up on after a few misses and the first polygon point is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng` - Source of randomness
+    ///
+    /// # Returns
+    ///
+    /// * `(f32, f32)` - Point in the space of the shape.
+    pub fn sample(&self, rng: &mut Rng) -> (f32, f32) {
+        match self {
+            RegionShape::Rect { x, y, w, h } => {
+                (rng.range_f32(*x, x + w), rng.range_f32(*y, y + h))
+            }
+            RegionShape::Polygon(points) => {
+                let (mut left, mut top) = points[0];
+                let (mut right, mut bottom) = points[0];
+                for &(px, py) in points {
+                    (left, right) = (left.min(px), right.max(px));
+                    (top, bottom) = (top.min(py), bottom.max(py));
+                }
+                for _ in 0..64 {
+                    let (px, py) = (rng.range_f32(left, right), rng.range_f32(top, bottom));
+                    if self.contains(px, py) {
+                        return (px, py);
+                    }
+                }
+                points[0]
+            }
+        }
+    }
+}
+
+impl Region {
+    /// Builds a region from its JSON definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the region
+    /// * `json` - Parsed JSON definition
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - Ok(Region) if the shape is valid, problem description otherwise.
+    pub(super) fn from_json(name: &str, json: JsonRegion) -> Result<Self, String> {
+        let (space, shape) = json.area.parse(&format!("region `{name}`"))?;
+        Ok(Region {
+            name: name.to_string(),
+            space,
+            shape,
+            tags: json.tags,
+            properties: json.properties,
+        })
+    }
+
+    /// Converts the region back into its JSON definition.
+    pub(super) fn to_json(&self) -> JsonRegion {
+        JsonRegion {
+            area: JsonArea::new(self.space, &self.shape),
+            tags: self.tags.clone(),
+            properties: self.properties.clone(),
+        }
+    }
+
+    /// Checks if the region has a tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - Tag to look for
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the tag is set, false otherwise.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+impl GameMap {
+    /// Converts world buffer pixels into cell coordinates.
+    ///
+    /// Inverts the projection of cell centres used by the renderer, for a
+    /// world buffer of `size * tile_size * 2` pixels. The centre of cell
+    /// (x, y) maps to (x + 0.5, y + 0.5).
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X coordinate in world pixels
+    /// * `y` - Y coordinate in world pixels
+    ///
+    /// # Returns
+    ///
+    /// * `(f32, f32)` - Position in cells, not clamped to the map.
+    pub fn world_to_tile(&self, x: f32, y: f32) -> (f32, f32) {
+        let ts = self.tile_size.max(1) as f32;
+        let [width, height] = self.size;
+        let diff = (x - width as f32 * ts - ts / 2.0) / (ts / 2.0);
+        let sum = (y - height as f32 * ts + ts * 1.5) / (ts / 4.0);
+        ((sum + diff) / 2.0, (sum - diff) / 2.0)
+    }
+
+    /// Converts cell coordinates into world buffer pixels.
+    ///
+    /// Inverse of [`GameMap::world_to_tile`].
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X coordinate in cells
+    /// * `y` - Y coordinate in cells
+    ///
+    /// # Returns
+    ///
+    /// * `(f32, f32)` - Position in world pixels.
+    pub fn tile_to_world(&self, x: f32, y: f32) -> (f32, f32) {
+        let ts = self.tile_size.max(1) as f32;
+        let [width, height] = self.size;
+        let world_x = (x - y) * (ts / 2.0) + width as f32 * ts + ts / 2.0;
+        let world_y = (x + y) * (ts / 4.0) + height as f32 * ts - ts * 1.5;
+        (world_x, world_y)
+    }
+
+    /// Checks if a world position lies inside a region.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - Region of this map
+    /// * `x` - X coordinate in world pixels
+    /// * `y` - Y coordinate in world pixels
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the position is inside the region, false otherwise.
+    pub fn region_contains(&self, region: &Region, x: f32, y: f32) -> bool {
+        match region.space {
+            RegionSpace::World => region.shape.contains(x, y),
+            RegionSpace::Tile => {
+                let (x, y) = self.world_to_tile(x, y);
+                region.shape.contains(x, y)
+            }
+        }
+    }
+
+    /// Retrieves a region by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the region to retrieve
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Region>` - Reference to the region if found, None otherwise.
+    pub fn get_region(&self, name: &str) -> Option<&Region> {
+        self.regions.get(name)
+    }
+
+    /// Returns an iterator over all regions in the map.
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = &Region>` - Iterator over region references.
+    pub fn iter_regions(&self) -> impl Iterator<Item = &Region> {
+        self.regions.values()
+    }
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    // Test point checks of rectangles and concave polygons
+    #[test]
+    fn test_shape_contains() {
+        let rect = RegionShape::Rect { x: 1.0, y: 2.0, w: 3.0, h: 1.0 };
+        assert!(rect.contains(1.0, 2.0) && rect.contains(3.9, 2.5));
+        assert!(!rect.contains(4.0, 2.5) && !rect.contains(2.0, 3.0) && !rect.contains(0.9, 2.5));
+
+        // L-shaped polygon
+        let shape = vec![(0.0, 0.0), (4.0, 0.0), (4.0, 1.0), (1.0, 1.0), (1.0, 4.0), (0.0, 4.0)];
+        let polygon = RegionShape::Polygon(shape);
+        assert!(polygon.contains(0.5, 3.5) && polygon.contains(3.5, 0.5));
+        assert!(!polygon.contains(2.0, 2.0) && !polygon.contains(-0.5, 0.5));
+
+        let mut rng = Rng::new(3);
+        for _ in 0..100 {
+            let (x, y) = rect.sample(&mut rng);
+            assert!(rect.contains(x, y));
+            let (x, y) = polygon.sample(&mut rng);
+            assert!(polygon.contains(x, y));
+        }
+    }
+
+    // Test that cell centres round trip through the renderer projection
+    #[test]
+    fn test_world_to_tile() {
+        let map = GameMap::from_str(TEST_MAP).unwrap();
+        let ts = map.tile_size as f32;
+        let [width, height] = map.size;
+
+        for (cx, cy) in [(0.0, 0.0), (2.0, 1.0), (3.0, 3.0)] {
+            let world_x = (cx - cy) * (ts / 2.0) + width as f32 * ts + ts / 2.0;
+            let world_y = (cx + cy) * (ts / 4.0) + height as f32 * ts - ts - ts / 4.0;
+            assert_eq!(map.world_to_tile(world_x, world_y), (cx + 0.5, cy + 0.5));
+            assert_eq!(map.tile_to_world(cx + 0.5, cy + 0.5), (world_x, world_y));
+        }
+    }
+
+    // Test loading regions in both spaces and rejecting broken shapes
+    #[test]
+    fn test_load_regions() {
+        let map = GameMap::from_str(TEST_MAP).unwrap();
+        assert_eq!(map.iter_regions().count(), 2);
+
+        let pond = map.get_region("pond").unwrap();
+        assert_eq!(pond.space, RegionSpace::Tile);
+        assert!(pond.has_tag("water") && !pond.has_tag("spawn"));
+        assert_eq!(pond.properties.get_str("text"), Some("A quiet pond"));
+        // World (72, 44) is the centre of cell (0, 0), (88, 60) of cell (3, 1)
+        assert!(map.region_contains(pond, 72.0, 44.0));
+        assert!(!map.region_contains(pond, 88.0, 60.0));
+
+        let gate = map.get_region("gate").unwrap();
+        assert_eq!(gate.space, RegionSpace::World);
+        assert!(map.region_contains(gate, 88.0, 60.0));
+        assert!(!map.region_contains(gate, 72.0, 44.0));
+
+        let saved = map.to_json();
+        assert_eq!(GameMap::from_str(&saved).unwrap(), map);
+        assert!(saved.find("\"gate\"").unwrap() < saved.find("\"pond\"").unwrap());
+
+        for broken in [
+            r#""rect": [0, 0, 0, 1]"#,
+            r#""polygon": [[0, 0], [1, 1]]"#,
+            r#""rect": [0, 0, 1, 1], "polygon": [[0, 0], [1, 0], [1, 1]]"#,
+            r#""space": "screen", "rect": [0, 0, 1, 1]"#,
+            r#""tags": []"#,
+        ] {
+            let json = format!(
+                r#"{{"meta": {{"name": "m", "tile_size": 16, "size": [4, 4]}},
+                    "objects": {{}}, "mobs": {{}}, "regions": {{"r": {{{broken}}}}}}}"#
+            );
+            let err = GameMap::from_str(&json).unwrap_err();
+            assert!(err.to_string().contains("region `r`"), "{err}");
+        }
+    }
+
+    const TEST_MAP: &str = r#"{
+        "meta": {"name": "regions", "tile_size": 16, "size": [4, 4]},
+        "objects": {},
+        "mobs": {},
+        "regions": {
+            "pond": {"rect": [0, 0, 1, 1], "tags": ["water"],
+                     "properties": {"text": "A quiet pond"}},
+            "gate": {"space": "world", "polygon": [[80, 52], [96, 52], [96, 68], [80, 68]]}
+        }
+    }"#;
+}
@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+
+use super::gamemap::BehaviourJson;
+use super::region::JsonArea;
+use super::{Behaviour, GameMap, Properties, RegionShape, RegionSpace};
+use crate::rng::Rng;
+
+// ============================
+// JSON-level structs
+// ============================
+
+/// Mob template from JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JsonMobTemplate {
+    /// Asset identifier for the mob's appearance
+    pub asset: String,
+    /// Behaviour configuration for spawned mobs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub behaviour: Option<BehaviourJson>,
+    /// Custom game data of spawned mobs
+    #[serde(default, skip_serializing_if = "Properties::is_empty")]
+    pub properties: Properties,
+}
+
+/// Mob spawner from JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JsonSpawner {
+    /// Area mobs appear in
+    pub area: JsonArea,
+    /// Mob to spawn
+    pub template: JsonMobTemplate,
+    /// Total number of mobs to spawn
+    pub count: u32,
+    /// Seconds between two spawns, zero spawns everything at once
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub interval: f32,
+    /// Upper limit of spawned mobs alive at the same time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_alive: Option<u32>,
+    /// Seed of spawn positions
+    #[serde(default)]
+    pub seed: u64,
+}
+
+/// Skips zero intervals when writing JSON.
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+// ============================
+// Game-level structs
+// ============================
+
+/// Definition of mobs created by a spawner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MobTemplate {
+    /// Asset identifier for the mob's appearance
+    pub asset: String,
+    /// Behaviour configuration for spawned mobs
+    pub behaviour: Option<Behaviour>,
+    /// Custom game data of spawned mobs
+    pub properties: Properties,
+}
+
+/// Source of mobs that appear over time at random points of an area.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spawner {
+    /// Name of the spawner
+    pub name: String,
+    /// Coordinate space of the area
+    pub space: RegionSpace,
+    /// Area mobs appear in
+    pub shape: RegionShape,
+    /// Mob to spawn
+    pub template: MobTemplate,
+    /// Total number of mobs to spawn
+    pub count: u32,
+    /// Seconds between two spawns, zero spawns everything at once
+    pub interval: f32,
+    /// Upper limit of spawned mobs alive at the same time
+    pub max_alive: Option<u32>,
+    /// Seed of spawn positions
+    pub seed: u64,
+}
+
+// ============================
+// Implementation
+// ============================
+
+impl Spawner {
+    /// Builds a spawner from its JSON definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the spawner
+    /// * `json` - Parsed JSON definition
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - Ok(Spawner) if the definition is valid, problem description otherwise.
+    pub(super) fn from_json(name: &str, json: JsonSpawner) -> Result<Self, String> {
+        let (space, shape) = json.area.parse(&format!("spawner `{name}`"))?;
+        if !(json.interval >= 0.0 && json.interval.is_finite()) {
+            return Err(format!("spawner `{name}` has invalid interval {}", json.interval));
+        }
+        if json.max_alive == Some(0) {
+            return Err(format!("spawner `{name}` allows no mobs alive"));
+        }
+
+        let template = MobTemplate {
+            asset: json.template.asset,
+            behaviour: json.template.behaviour.as_ref().map(Behaviour::from_json),
+            properties: json.template.properties,
+        };
+        Ok(Spawner {
+            name: name.to_string(),
+            space,
+            shape,
+            template,
+            count: json.count,
+            interval: json.interval,
+            max_alive: json.max_alive,
+            seed: json.seed,
+        })
+    }
+
+    /// Converts the spawner back into its JSON definition.
+    pub(super) fn to_json(&self) -> JsonSpawner {
+        let template = JsonMobTemplate {
+            asset: self.template.asset.clone(),
+            behaviour: self.template.behaviour.as_ref().map(Behaviour::to_json),
+            properties: self.template.properties.clone(),
+        };
+        JsonSpawner {
+            area: JsonArea::new(self.space, &self.shape),
+            template,
+            count: self.count,
+            interval: self.interval,
+            max_alive: self.max_alive,
+            seed: self.seed,
+        }
+    }
+}
+
+impl GameMap {
+    /// Picks a random spawn position of a spawner.
+    ///
+    /// # Arguments
+    ///
+    /// * `spawner` - Spawner of this map
+    /// * `rng` - Source of randomness
+    ///
+    /// # Returns
+    ///
+    /// * `(f32, f32)` - Position inside the spawner area in world pixels.
+    pub fn spawn_point(&self, spawner: &Spawner, rng: &mut Rng) -> (f32, f32) {
+        let (x, y) = spawner.shape.sample(rng);
+        match spawner.space {
+            RegionSpace::World => (x, y),
+            RegionSpace::Tile => self.tile_to_world(x, y),
+        }
+    }
+
+    /// Retrieves a spawner by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the spawner to retrieve
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Spawner>` - Reference to the spawner if found, None otherwise.
+    pub fn get_spawner(&self, name: &str) -> Option<&Spawner> {
+        self.spawners.get(name)
+    }
+
+    /// Returns an iterator over all spawners in the map.
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = &Spawner>` - Iterator over spawner references.
+    pub fn iter_spawners(&self) -> impl Iterator<Item = &Spawner> {
+        self.spawners.values()
+    }
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::BehaviourType;
+    use std::str::FromStr;
+
+    fn map_with(spawner: &str) -> Result<GameMap, crate::assets::AssetError> {
+        GameMap::from_str(&format!(
+            r#"{{"meta": {{"name": "m", "tile_size": 16, "size": [4, 4]}},
+                "objects": {{}}, "mobs": {{}}, "spawners": {{"s": {{{spawner}}}}}}}"#
+        ))
+    }
+
+    // Test loading spawners, saving them back and rejecting broken ones
+    #[test]
+    fn test_load_spawners() {
+        let map = map_with(
+            r#""area": {"rect": [1, 1, 2, 2]}, "count": 10, "interval": 0.5, "max_alive": 3,
+               "seed": 9, "template": {"asset": "imp_20",
+               "behaviour": {"type": "walker", "direction": "left", "speed": 0.5}}"#,
+        )
+        .unwrap();
+
+        let spawner = map.get_spawner("s").unwrap();
+        assert_eq!((spawner.count, spawner.interval, spawner.max_alive), (10, 0.5, Some(3)));
+        assert_eq!(spawner.space, RegionSpace::Tile);
+        let behaviour = spawner.template.behaviour.as_ref().unwrap();
+        assert_eq!(behaviour.behaviour_type, BehaviourType::Walker);
+        assert_eq!(map.iter_spawners().count(), 1);
+        assert_eq!(GameMap::from_str(&map.to_json()).unwrap(), map);
+
+        // Tile area [1, 3) x [1, 3) lies between these world corners
+        let mut rng = Rng::new(spawner.seed);
+        for _ in 0..50 {
+            let (x, y) = map.spawn_point(spawner, &mut rng);
+            let (tx, ty) = map.world_to_tile(x, y);
+            assert!((1.0..3.001).contains(&tx) && (1.0..3.001).contains(&ty), "{tx} {ty}");
+        }
+
+        for broken in [
+            r#""area": {"rect": [0, 0, 1, 1]}, "count": 1, "max_alive": 0,
+               "template": {"asset": "imp"}"#,
+            r#""area": {"rect": [0, 0, 1, 1]}, "count": 1, "interval": -1,
+               "template": {"asset": "imp"}"#,
+            r#""area": {"polygon": [[0, 0]]}, "count": 1, "template": {"asset": "imp"}"#,
+        ] {
+            let err = map_with(broken).unwrap_err();
+            assert!(err.to_string().contains("spawner `s`"), "{err}");
+        }
+    }
+}
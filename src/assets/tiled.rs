@@ -0,0 +1,661 @@
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::{
+    AssetError, Atlas, Behaviour, BehaviourType, GameMap, Mob, Object, Properties, Property,
+    TileLayer,
+};
+
+/// Bits of a Tiled global tile id that store flipping, not the tile.
+const GID_FLAGS: u32 = 0xF000_0000;
+
+/// Object properties read by the importer, the rest become custom properties.
+const OBJECT_PROPERTIES: [&str; 4] = ["asset", "collidable", "shadow", "z"];
+
+/// Mob properties read by the importer, the rest become custom properties.
+const MOB_PROPERTIES: [&str; 5] = ["asset", "is_player", "behaviour", "direction", "speed"];
+
+// ============================
+// JSON-level structs
+// ============================
+
+/// Custom property of a Tiled map, layer, object or tile.
+#[derive(Deserialize, Debug, Clone)]
+struct TiledProperty {
+    /// Name of the property
+    name: String,
+    /// `string`, `int`, `float`, `bool`, `color`, `file`, `object` or `class`
+    #[serde(default, rename = "type")]
+    property_type: String,
+    /// Value of the property, its JSON type follows the property type
+    #[serde(default)]
+    value: Value,
+}
+
+/// Cell data of a tile layer, only the plain array encoding is supported.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum TiledData {
+    /// Global tile ids in row-major order
+    Array(Vec<u32>),
+    /// Base64 data, possibly compressed
+    Encoded(IgnoredAny),
+}
+
+/// Object of a Tiled object layer.
+#[derive(Deserialize, Debug)]
+struct TiledObject {
+    /// Unique id of the object in the map
+    id: u32,
+    /// Name given in the editor, may be empty
+    #[serde(default)]
+    name: String,
+    /// Global tile id for tile objects
+    #[serde(default)]
+    gid: Option<u32>,
+    /// X coordinate in object space
+    x: f32,
+    /// Y coordinate in object space
+    y: f32,
+    /// Whether the object is a point
+    #[serde(default)]
+    point: bool,
+    /// Custom properties of the object
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+/// Layer of a Tiled map.
+#[derive(Deserialize, Debug)]
+struct TiledLayer {
+    /// `tilelayer`, `objectgroup`, `imagelayer` or `group`
+    #[serde(rename = "type")]
+    layer_type: String,
+    /// Name of the layer
+    #[serde(default)]
+    name: String,
+    /// Cells of a tile layer
+    #[serde(default)]
+    data: Option<TiledData>,
+    /// Objects of an object layer
+    #[serde(default)]
+    objects: Vec<TiledObject>,
+    /// Child layers of a group
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+    /// Custom properties of the layer
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+/// Tile of a tileset with its own image or properties.
+#[derive(Deserialize, Debug)]
+struct TiledTile {
+    /// Local id of the tile in its tileset
+    id: u32,
+    /// Image of the tile in image collection tilesets
+    #[serde(default)]
+    image: Option<String>,
+    /// Class of the tile, called type before Tiled 1.9
+    #[serde(default, rename = "type", alias = "class")]
+    class: Option<String>,
+    /// Custom properties of the tile
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+/// Tileset embedded in a map, a reference to a `.tsj` file, or the file itself.
+#[derive(Deserialize, Debug, Default)]
+struct TiledTileset {
+    /// Global id of the first tile
+    #[serde(default)]
+    firstgid: u32,
+    /// Path to the external tileset file
+    #[serde(default)]
+    source: Option<String>,
+    /// Name of the tileset
+    #[serde(default)]
+    name: String,
+    /// Number of tile columns in the tileset image
+    #[serde(default)]
+    columns: u32,
+    /// Width of a tile in the tileset image
+    #[serde(default)]
+    tilewidth: u32,
+    /// Height of a tile in the tileset image
+    #[serde(default)]
+    tileheight: u32,
+    /// Pixels around the tiles in the tileset image
+    #[serde(default)]
+    margin: u32,
+    /// Pixels between the tiles in the tileset image
+    #[serde(default)]
+    spacing: u32,
+    /// Tiles with their own images or properties
+    #[serde(default)]
+    tiles: Vec<TiledTile>,
+}
+
+/// Root structure of a Tiled JSON map.
+#[derive(Deserialize, Debug)]
+struct TiledMap {
+    /// `orthogonal`, `isometric`, `staggered` or `hexagonal`
+    orientation: String,
+    /// Map width in tiles
+    width: u32,
+    /// Map height in tiles
+    height: u32,
+    /// Width of a tile in pixels
+    tilewidth: u32,
+    /// Height of a tile in pixels
+    tileheight: u32,
+    /// Whether layers are stored in chunks
+    #[serde(default)]
+    infinite: bool,
+    /// Layers from bottom to top
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+    /// Tilesets used by layers and tile objects
+    #[serde(default)]
+    tilesets: Vec<TiledTileset>,
+    /// Custom properties of the map
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+// ============================
+// Implementation
+// ============================
+
+/// Finds a string property by name.
+fn prop_str<'a>(properties: &'a [TiledProperty], name: &str) -> Option<&'a str> {
+    properties.iter().find(|p| p.name == name)?.value.as_str()
+}
+
+/// Finds a boolean property by name.
+fn prop_bool(properties: &[TiledProperty], name: &str) -> Option<bool> {
+    properties.iter().find(|p| p.name == name)?.value.as_bool()
+}
+
+/// Finds a non-negative integer property by name.
+fn prop_u32(properties: &[TiledProperty], name: &str) -> Option<u32> {
+    properties.iter().find(|p| p.name == name)?.value.as_u64().map(|v| v as u32)
+}
+
+/// Finds a numeric property by name.
+fn prop_f32(properties: &[TiledProperty], name: &str) -> Option<f32> {
+    properties.iter().find(|p| p.name == name)?.value.as_f64().map(|v| v as f32)
+}
+
+/// Converts a Tiled property value, None for class values.
+fn tiled_value(property_type: &str, value: &Value) -> Option<Property> {
+    match (property_type, value) {
+        ("float", Value::Number(n)) => n.as_f64().map(Property::Float),
+        (_, Value::Number(n)) => n.as_i64().map(Property::Int).or(n.as_f64().map(Property::Float)),
+        (_, Value::Bool(b)) => Some(Property::Bool(*b)),
+        (_, Value::String(s)) => Some(Property::String(s.clone())),
+        _ => None,
+    }
+}
+
+/// Copies custom properties that the importer does not consume itself.
+fn custom_properties(properties: &[TiledProperty], consumed: &[&str]) -> Properties {
+    properties
+        .iter()
+        .filter(|p| !consumed.contains(&p.name.as_str()))
+        .filter_map(|p| Some((p.name.clone(), tiled_value(&p.property_type, &p.value)?)))
+        .collect()
+}
+
+impl TiledTileset {
+    /// Finds the atlas frame of a tile of this tileset.
+    ///
+    /// Checks, in order, an `asset` property of the tile, its class, the
+    /// file name of its image, and the frame at its position in the tileset image.
+    fn frame_name(&self, id: u32, atlas: &Atlas) -> Option<String> {
+        let tile = self.tiles.iter().find(|tile| tile.id == id);
+
+        if let Some(tile) = tile {
+            let image_stem = tile
+                .image
+                .as_deref()
+                .and_then(|image| Path::new(image).file_stem())
+                .and_then(|stem| stem.to_str());
+            let candidates =
+                [prop_str(&tile.properties, "asset"), tile.class.as_deref(), image_stem];
+            if let Some(name) =
+                candidates.into_iter().flatten().find(|&name| atlas.contains_frame(name))
+            {
+                return Some(name.to_string());
+            }
+        }
+
+        if self.columns == 0 {
+            return None;
+        }
+        let x = self.margin + (id % self.columns) * (self.tilewidth + self.spacing);
+        let y = self.margin + (id / self.columns) * (self.tileheight + self.spacing);
+        atlas
+            .iter_frames()
+            .filter(|frame| (frame.x, frame.y) == (x, y))
+            .map(|frame| frame.name.clone())
+            .min()
+    }
+}
+
+/// Resolves global tile ids to atlas frame names.
+struct TileResolver<'a> {
+    /// Tilesets sorted by their first global id
+    tilesets: Vec<TiledTileset>,
+    /// Atlas the frame names come from
+    atlas: &'a Atlas,
+    /// Already resolved global ids
+    names: HashMap<u32, String>,
+}
+
+impl<'a> TileResolver<'a> {
+    /// Loads external tilesets relative to the map file.
+    fn new(
+        map_path: &Path,
+        tilesets: Vec<TiledTileset>,
+        atlas: &'a Atlas,
+    ) -> Result<Self, AssetError> {
+        let base = map_path.parent().unwrap_or(Path::new(""));
+        let mut loaded = Vec::with_capacity(tilesets.len());
+
+        for tileset in tilesets {
+            let Some(source) = &tileset.source else {
+                loaded.push(tileset);
+                continue;
+            };
+            let path = base.join(source);
+            let text = fs::read_to_string(&path).map_err(|e| AssetError::io(&path, e))?;
+            let mut external: TiledTileset =
+                serde_json::from_str(&text).map_err(|e| AssetError::json(&path, e))?;
+            external.firstgid = tileset.firstgid;
+            loaded.push(external);
+        }
+        loaded.sort_by_key(|tileset| tileset.firstgid);
+
+        Ok(Self { tilesets: loaded, atlas, names: HashMap::new() })
+    }
+
+    /// Resolves a global tile id, flip flags are ignored.
+    fn resolve(&mut self, gid: u32) -> Result<&str, String> {
+        let gid = gid & !GID_FLAGS;
+        if !self.names.contains_key(&gid) {
+            let tileset = self
+                .tilesets
+                .iter()
+                .rev()
+                .find(|tileset| tileset.firstgid <= gid)
+                .ok_or_else(|| format!("tile {gid} belongs to no tileset"))?;
+            let name = tileset.frame_name(gid - tileset.firstgid, self.atlas).ok_or_else(|| {
+                format!(
+                    "tile {} of tileset `{}` matches no atlas frame",
+                    gid - tileset.firstgid,
+                    tileset.name
+                )
+            })?;
+            self.names.insert(gid, name);
+        }
+        Ok(&self.names[&gid])
+    }
+}
+
+/// Placement of Tiled object coordinates on the map.
+///
+/// Tiled measures both axes of isometric object space in tile heights.
+struct Projection {
+    /// Length of one cell in object space
+    object_unit: f32,
+    /// Width of a tile in pixels
+    tile_size: f32,
+    /// Map dimensions [width, height]
+    size: [u32; 2],
+}
+
+impl Projection {
+    /// Finds the cell under a point, tile objects are anchored at the bottom corner of their cell.
+    fn cell(&self, object: &TiledObject) -> (u32, u32) {
+        let shift = if object.gid.is_some() { 0.5 } else { 0.0 };
+        let cell = |coord: f32| (coord / self.object_unit - shift).floor().max(0.0) as u32;
+        (cell(object.x), cell(object.y))
+    }
+
+    /// Projects a point into world buffer pixels.
+    ///
+    /// Follows the projection of cell centres in the renderer, for a world
+    /// buffer of `size * tile_size * 2` pixels.
+    fn world(&self, object: &TiledObject) -> (u32, u32) {
+        let ts = self.tile_size;
+        let x = object.x / self.object_unit - 0.5;
+        let y = object.y / self.object_unit - 0.5;
+        let world_x = (x - y) * (ts / 2.0) + self.size[0] as f32 * ts + ts / 2.0;
+        let world_y = (x + y) * (ts / 4.0) + self.size[1] as f32 * ts - ts - ts / 4.0;
+        (world_x.round().max(0.0) as u32, world_y.round().max(0.0) as u32)
+    }
+}
+
+/// Picks a map key for a Tiled object, unnamed and repeated names get the object id.
+fn unique_name<V>(entries: &HashMap<String, V>, object: &TiledObject, prefix: &str) -> String {
+    if object.name.is_empty() {
+        format!("{prefix}_{}", object.id)
+    } else if entries.contains_key(&object.name) {
+        format!("{}_{}", object.name, object.id)
+    } else {
+        object.name.clone()
+    }
+}
+
+/// Builds a mob from a point object.
+fn tiled_mob(name: String, object: &TiledObject, projection: &Projection) -> Result<Mob, String> {
+    let props = &object.properties;
+    let is_player = prop_bool(props, "is_player").unwrap_or(false);
+    let asset = prop_str(props, "asset")
+        .ok_or_else(|| format!("mob object {} has no `asset` property", object.id))?;
+
+    let behaviour = match prop_str(props, "behaviour") {
+        Some(behaviour_type) => Some(Behaviour {
+            behaviour_type: BehaviourType::from_name(behaviour_type),
+            direction: prop_str(props, "direction").map(str::to_string),
+            speed: prop_f32(props, "speed"),
+            params: Properties::new(),
+        }),
+        None if is_player => Some(Behaviour {
+            behaviour_type: BehaviourType::Controlled,
+            direction: None,
+            speed: None,
+            params: Properties::new(),
+        }),
+        None => None,
+    };
+
+    let (x_start, y_start) = projection.world(object);
+    Ok(Mob {
+        name,
+        x_start,
+        y_start,
+        asset: asset.to_string(),
+        is_player,
+        behaviour,
+        properties: custom_properties(props, &MOB_PROPERTIES),
+    })
+}
+
+/// Collects tile and object layers of a layer tree in drawing order.
+fn flatten_layers(layers: Vec<TiledLayer>, flat: &mut Vec<TiledLayer>) {
+    for mut layer in layers {
+        if layer.layer_type == "group" {
+            flatten_layers(std::mem::take(&mut layer.layers), flat);
+        } else {
+            flat.push(layer);
+        }
+    }
+}
+
+impl GameMap {
+    /// Imports a map made in the Tiled editor and saved as JSON (`.tmj`).
+    ///
+    /// The map must be isometric and finite, with tile layers stored as
+    /// plain arrays. Tile layers become [`TileLayer`]s, group layers are
+    /// flattened. Objects become [`Object`]s on the cell under them.
+    /// Point objects with an `is_player` or `behaviour` property become
+    /// [`Mob`]s, placed at the world buffer pixel of their position.
+    ///
+    /// Tiles are matched with frames of `tiles_atlas` by an `asset` tile
+    /// property, the tile class, the image file name, or the position in
+    /// the tileset image. Objects and mobs without a tile use an `asset`
+    /// property. Objects read `collidable`, `shadow` and `z` properties, tile
+    /// layers read `z`, mobs read `is_player`, `behaviour`, `direction` and `speed`.
+    /// Other properties of the map, objects and mobs are kept as custom
+    /// [`Properties`], `float` properties stay floats even when whole.
+    ///
+    /// # Arguments
+    ///
+    /// * `tmj_path` - Path to the Tiled map file, external `.tsj` tilesets are resolved against it
+    /// * `tiles_atlas` - Atlas of static map elements
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, AssetError>` - Imported GameMap on success, error on failure.
+    pub fn load_tiled<P: AsRef<Path>>(
+        tmj_path: P,
+        tiles_atlas: &Atlas,
+    ) -> Result<Self, AssetError> {
+        let tmj_path = tmj_path.as_ref();
+        let text = fs::read_to_string(tmj_path).map_err(|e| AssetError::io(tmj_path, e))?;
+        let tiled: TiledMap =
+            serde_json::from_str(&text).map_err(|e| AssetError::json(tmj_path, e))?;
+        let invalid = |message: String| AssetError::invalid(tmj_path, message);
+
+        if tiled.orientation != "isometric" {
+            return Err(invalid(format!("{} maps are not supported", tiled.orientation)));
+        }
+        if tiled.infinite {
+            return Err(invalid("infinite maps are not supported".to_string()));
+        }
+
+        let mut resolver = TileResolver::new(tmj_path, tiled.tilesets, tiles_atlas)?;
+        let mut layers = Vec::new();
+        flatten_layers(tiled.layers, &mut layers);
+
+        let name = match prop_str(&tiled.properties, "name") {
+            Some(name) => name.to_string(),
+            None => tmj_path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+        };
+        let mut game_map = GameMap {
+            name,
+            tile_size: tiled.tilewidth,
+            size: [tiled.width, tiled.height],
+            mobs: HashMap::new(),
+            objects: HashMap::new(),
+            tiles: HashMap::new(),
+            tileset: Vec::new(),
+            layers: Vec::new(),
+            regions: HashMap::new(),
+            spawners: HashMap::new(),
+            prefabs: HashMap::new(),
+            instances: HashMap::new(),
+            properties: custom_properties(&tiled.properties, &["name"]),
+        };
+        let projection = Projection {
+            object_unit: tiled.tileheight.max(1) as f32,
+            tile_size: tiled.tilewidth as f32,
+            size: game_map.size,
+        };
+        let cell_count = tiled.width as usize * tiled.height as usize;
+        let mut tileset_index: HashMap<String, u32> = HashMap::new();
+
+        for layer in layers {
+            match layer.layer_type.as_str() {
+                "tilelayer" => {
+                    let data = match layer.data {
+                        Some(TiledData::Array(data)) => data,
+                        Some(TiledData::Encoded(_)) => {
+                            return Err(invalid(format!(
+                                "layer `{}` is encoded, save it as CSV",
+                                layer.name
+                            )));
+                        }
+                        None => vec![0; cell_count],
+                    };
+                    if data.len() != cell_count {
+                        return Err(invalid(format!(
+                            "layer `{}` has {} cells, the map needs {cell_count}",
+                            layer.name,
+                            data.len()
+                        )));
+                    }
+
+                    let mut cells = Vec::with_capacity(cell_count);
+                    for gid in data {
+                        if gid & !GID_FLAGS == 0 {
+                            cells.push(0);
+                            continue;
+                        }
+                        let asset = resolver.resolve(gid).map_err(invalid)?;
+                        if !tileset_index.contains_key(asset) {
+                            game_map.tileset.push(asset.to_string());
+                            tileset_index.insert(asset.to_string(), game_map.tileset.len() as u32);
+                        }
+                        cells.push(tileset_index[asset]);
+                    }
+                    game_map.layers.push(TileLayer {
+                        name: layer.name,
+                        cells,
+                        z: prop_u32(&layer.properties, "z").unwrap_or(0),
+                        ..TileLayer::default()
+                    });
+                }
+                "objectgroup" => {
+                    for object in &layer.objects {
+                        let props = &object.properties;
+                        let is_mob = prop_bool(props, "is_player").is_some()
+                            || prop_str(props, "behaviour").is_some();
+
+                        if object.point && is_mob {
+                            let name = unique_name(&game_map.mobs, object, "mob");
+                            let mob = tiled_mob(name, object, &projection).map_err(invalid)?;
+                            game_map.mobs.insert(mob.name.clone(), mob);
+                            continue;
+                        }
+
+                        let asset = match (object.gid, prop_str(props, "asset")) {
+                            (Some(gid), _) => resolver.resolve(gid).map_err(invalid)?.to_string(),
+                            (None, Some(asset)) => asset.to_string(),
+                            (None, None) => {
+                                return Err(invalid(format!(
+                                    "object {} has no tile and no `asset` property",
+                                    object.id
+                                )));
+                            }
+                        };
+                        let (x, y) = projection.cell(object);
+                        let name = unique_name(&game_map.objects, object, "object");
+                        let object = Object {
+                            name: name.clone(),
+                            x,
+                            y,
+                            asset,
+                            collidable: prop_bool(props, "collidable").unwrap_or(false),
+                            shadow: prop_bool(props, "shadow").unwrap_or(false),
+                            z: prop_u32(props, "z").unwrap_or(0),
+                            properties: custom_properties(props, &OBJECT_PROPERTIES),
+                        };
+                        game_map.objects.insert(name, object);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(game_map)
+    }
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::Frame;
+    use crate::test_util::TempDir;
+    use image::RgbaImage;
+    use std::path::PathBuf;
+
+    fn tiles_atlas() -> Atlas {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        Atlas::load(project_root.join("assets/tiles/atlas.json")).unwrap()
+    }
+
+    // Test importing the bundled Tiled example with an external tileset
+    #[test]
+    fn test_load_tiled_example() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let map = GameMap::load_tiled(project_root.join("examples/tiled/map.tmj"), &tiles_atlas())
+            .unwrap();
+
+        assert_eq!(map.name, "tiled_demo");
+        assert_eq!(map.properties.get_str("music"), Some("desert.ogg"));
+        assert!(map.properties.get("name").is_none());
+        assert_eq!(map.tile_size, 16);
+        assert_eq!(map.size, [4, 4]);
+        assert_eq!(map.tileset, vec!["grass_tile_big_0_1", "dirt_tile_big_0_0"]);
+
+        let ground = map.get_layer("ground").unwrap();
+        assert_eq!(map.layer_asset(ground, 0, 0), Some("grass_tile_big_0_1"));
+        assert_eq!(map.layer_asset(ground, 1, 1), Some("dirt_tile_big_0_0"));
+
+        let cactus = map.get_object("cactus").unwrap();
+        assert_eq!((cactus.position(), cactus.asset.as_str()), ((2, 1), "cactus_long_3_9"));
+        assert!(cactus.collidable && cactus.shadow);
+        assert_eq!(cactus.properties.get("damage"), Some(&Property::Float(1.0)));
+        assert_eq!(cactus.properties.len(), 1);
+        let fence = map.get_object("object_2").unwrap();
+        assert_eq!((fence.position(), fence.asset.as_str()), ((0, 3), "fence_rising_11_10"));
+        assert!(!fence.collidable && !fence.shadow);
+
+        let player = map.get_mob("player").unwrap();
+        assert!(player.is_player);
+        assert_eq!(player.start_position(), (72, 44));
+        assert_eq!(player.behaviour.as_ref().unwrap().behaviour_type, BehaviourType::Controlled);
+
+        let imp = map.get_mob("imp").unwrap();
+        assert_eq!(imp.start_position(), (88, 60));
+        let behaviour = imp.behaviour.as_ref().unwrap();
+        assert_eq!(behaviour.behaviour_type, BehaviourType::Walker);
+        assert_eq!(behaviour.direction.as_deref(), Some("left"));
+        assert_eq!(behaviour.speed, Some(0.5));
+        assert_eq!(imp.properties.get_i64("health"), Some(3));
+        assert_eq!(imp.properties.len(), 1);
+        assert!(player.properties.is_empty());
+    }
+
+    // Test resolving tiles of an embedded image tileset by position, and import errors
+    #[test]
+    fn test_load_tiled_embedded_tileset() {
+        let frames = HashMap::from([
+            ("a".to_string(), Frame::new("a", 1, 1, 16, 8)),
+            ("b".to_string(), Frame::new("b", 18, 1, 16, 8)),
+        ]);
+        let atlas = Atlas::new(RgbaImage::new(35, 10), frames, HashMap::new(), 16, 1);
+        let dir = TempDir::new("tiled");
+        let map_path = dir.join("map.tmj");
+
+        let write = |orientation: &str, data: &str| {
+            let json = format!(
+                r#"{{"orientation": "{orientation}", "width": 2, "height": 1,
+                    "tilewidth": 16, "tileheight": 8,
+                    "tilesets": [{{"firstgid": 5, "name": "grid", "image": "atlas.png",
+                                   "columns": 2, "tilewidth": 16, "tileheight": 8,
+                                   "margin": 1, "spacing": 1}}],
+                    "layers": [{{"type": "tilelayer", "name": "g", "data": {data}}}]}}"#
+            );
+            fs::write(&map_path, json).unwrap();
+        };
+
+        write("isometric", "[6, 2147483653]");
+        let map = GameMap::load_tiled(&map_path, &atlas).unwrap();
+        assert_eq!(map.name, "map");
+        assert_eq!(map.tileset, vec!["b", "a"]);
+        assert_eq!(map.layers[0].cells, vec![1, 2]);
+
+        write("isometric", "[7, 0]");
+        let err = GameMap::load_tiled(&map_path, &atlas).unwrap_err();
+        assert!(err.to_string().contains("tile 2 of tileset `grid`"), "{err}");
+
+        write("isometric", "[1, 0]");
+        assert!(GameMap::load_tiled(&map_path, &atlas).is_err());
+
+        write("orthogonal", "[5, 5]");
+        let err = GameMap::load_tiled(&map_path, &atlas).unwrap_err();
+        assert!(matches!(err, AssetError::Invalid { .. }));
+    }
+}
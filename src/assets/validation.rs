@@ -0,0 +1,721 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::atlas::opaque_bounds;
+use super::{Atlas, Frame, GameMap};
+
+// ============================
+// Atlas report
+// ============================
+
+/// A single problem found in an atlas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AtlasIssue {
+    /// Frame rectangle reaches outside of the atlas image
+    OutOfBounds {
+        /// Name of the frame
+        frame: String,
+        /// Width of the atlas image
+        image_w: u32,
+        /// Height of the atlas image
+        image_h: u32,
+    },
+    /// Frame has zero width or height
+    ZeroSized {
+        /// Name of the frame
+        frame: String,
+    },
+    /// Two frame rectangles share pixels without being the same rectangle
+    Overlapping {
+        /// Name of the first frame (in name order)
+        first: String,
+        /// Name of the second frame (in name order)
+        second: String,
+    },
+    /// Every pixel of the frame is fully transparent
+    Transparent {
+        /// Name of the frame
+        frame: String,
+    },
+    /// A tile frame is not as wide as `meta.tile_size`
+    TileSizeMismatch {
+        /// Name of the frame
+        frame: String,
+        /// Width of the frame
+        width: u32,
+        /// Tile size declared by the atlas
+        tile_size: u32,
+    },
+}
+
+/// Result of [`Atlas::validate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AtlasReport {
+    /// Per-frame problems ordered by frame name, followed by overlaps
+    pub issues: Vec<AtlasIssue>,
+}
+
+impl AtlasReport {
+    /// Checks if no problems were found.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the atlas is valid, false otherwise.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns an iterator over issues related to the given frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the frame
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = &AtlasIssue>` - Iterator over matching issues.
+    pub fn issues_for<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a AtlasIssue> {
+        self.issues.iter().filter(move |issue| issue.frames().contains(&name))
+    }
+}
+
+impl AtlasIssue {
+    /// Returns names of the frames involved in the issue.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<&str>` - One name, or two for overlapping frames.
+    pub fn frames(&self) -> Vec<&str> {
+        match self {
+            AtlasIssue::OutOfBounds { frame, .. }
+            | AtlasIssue::ZeroSized { frame }
+            | AtlasIssue::Transparent { frame }
+            | AtlasIssue::TileSizeMismatch { frame, .. } => vec![frame],
+            AtlasIssue::Overlapping { first, second } => vec![first, second],
+        }
+    }
+}
+
+impl fmt::Display for AtlasIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasIssue::OutOfBounds { frame, image_w, image_h } => {
+                write!(f, "frame `{frame}` is outside of the {image_w}x{image_h} image")
+            }
+            AtlasIssue::ZeroSized { frame } => write!(f, "frame `{frame}` has zero size"),
+            AtlasIssue::Overlapping { first, second } => {
+                write!(f, "frames `{first}` and `{second}` overlap")
+            }
+            AtlasIssue::Transparent { frame } => {
+                write!(f, "frame `{frame}` is fully transparent")
+            }
+            AtlasIssue::TileSizeMismatch { frame, width, tile_size } => {
+                write!(f, "tile frame `{frame}` is {width}px wide, but tile_size is {tile_size}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for AtlasReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+// ============================
+// Map report
+// ============================
+
+/// Named element of a map that a problem refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapEntity {
+    /// Named tile
+    Tile(String),
+    /// Static object
+    Object(String),
+    /// Mob or player
+    Mob(String),
+    /// Tile layer
+    Layer(String),
+    /// Entry of the layer tileset, 1 for the first entry
+    Tileset(u32),
+    /// Mob spawner
+    Spawner(String),
+}
+
+/// A single problem found in a map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapIssue {
+    /// Asset is not a frame of the atlas it is drawn from
+    UnknownAsset {
+        /// Element using the asset
+        entity: MapEntity,
+        /// Name of the asset
+        asset: String,
+    },
+    /// Cell lies outside of `meta.size`
+    OutOfBounds {
+        /// Tile or object
+        entity: MapEntity,
+        /// X coordinate of the cell
+        x: u32,
+        /// Y coordinate of the cell
+        y: u32,
+    },
+    /// Two named tiles share a cell
+    DuplicateCell {
+        /// Name of the first tile (in name order)
+        first: String,
+        /// Name of the second tile (in name order)
+        second: String,
+        /// X coordinate of the cell
+        x: u32,
+        /// Y coordinate of the cell
+        y: u32,
+    },
+    /// Layer does not have one value per cell
+    LayerSize {
+        /// Name of the layer
+        layer: String,
+        /// Number of values in the layer
+        cells: usize,
+        /// Number of cells in the map
+        expected: usize,
+    },
+    /// No mob is marked with `is_player`
+    NoPlayer,
+    /// Several mobs are marked with `is_player`
+    MultiplePlayers {
+        /// Names of the player mobs, sorted
+        mobs: Vec<String>,
+    },
+    /// Behaviour type is not registered with the game
+    UnknownBehaviour {
+        /// Mob or spawner with the behaviour
+        entity: MapEntity,
+        /// Name of the behaviour type
+        behaviour: String,
+    },
+    /// Behaviour parameters were rejected by the behaviour factory
+    InvalidBehaviour {
+        /// Mob or spawner with the behaviour
+        entity: MapEntity,
+        /// Problem reported by the factory
+        reason: String,
+    },
+    /// Start position lies outside of the world buffer
+    MobOutsideWorld {
+        /// Name of the mob
+        mob: String,
+        /// Start X coordinate in world pixels
+        x: u32,
+        /// Start Y coordinate in world pixels
+        y: u32,
+        /// Width of the world in pixels
+        world_w: u32,
+        /// Height of the world in pixels
+        world_h: u32,
+    },
+}
+
+/// Result of [`GameMap::validate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MapReport {
+    /// Problems of tiles, objects and mobs, each in name order, followed by player checks
+    pub issues: Vec<MapIssue>,
+}
+
+impl MapEntity {
+    /// Returns the name of the element.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Name, or the entry number for tileset entries.
+    pub fn name(&self) -> String {
+        match self {
+            MapEntity::Tile(name)
+            | MapEntity::Object(name)
+            | MapEntity::Mob(name)
+            | MapEntity::Layer(name)
+            | MapEntity::Spawner(name) => name.clone(),
+            MapEntity::Tileset(index) => index.to_string(),
+        }
+    }
+}
+
+impl MapReport {
+    /// Checks if no problems were found.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the map is valid, false otherwise.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns an iterator over issues related to the given map element.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the tile, object, mob or layer
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = &MapIssue>` - Iterator over matching issues.
+    pub fn issues_for<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a MapIssue> {
+        self.issues.iter().filter(move |issue| issue.names().iter().any(|n| n == name))
+    }
+}
+
+impl MapIssue {
+    /// Returns names of the map elements involved in the issue.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - Names of tiles, objects, mobs or layers, empty for `NoPlayer`.
+    pub fn names(&self) -> Vec<String> {
+        match self {
+            MapIssue::UnknownAsset { entity, .. }
+            | MapIssue::OutOfBounds { entity, .. }
+            | MapIssue::UnknownBehaviour { entity, .. }
+            | MapIssue::InvalidBehaviour { entity, .. } => vec![entity.name()],
+            MapIssue::DuplicateCell { first, second, .. } => vec![first.clone(), second.clone()],
+            MapIssue::LayerSize { layer, .. } => vec![layer.clone()],
+            MapIssue::NoPlayer => Vec::new(),
+            MapIssue::MultiplePlayers { mobs } => mobs.clone(),
+            MapIssue::MobOutsideWorld { mob, .. } => vec![mob.clone()],
+        }
+    }
+}
+
+impl fmt::Display for MapEntity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapEntity::Tile(name) => write!(f, "tile `{name}`"),
+            MapEntity::Object(name) => write!(f, "object `{name}`"),
+            MapEntity::Mob(name) => write!(f, "mob `{name}`"),
+            MapEntity::Layer(name) => write!(f, "layer `{name}`"),
+            MapEntity::Tileset(index) => write!(f, "tileset entry {index}"),
+            MapEntity::Spawner(name) => write!(f, "spawner `{name}`"),
+        }
+    }
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapIssue::UnknownAsset { entity, asset } => {
+                write!(f, "{entity} uses unknown asset `{asset}`")
+            }
+            MapIssue::OutOfBounds { entity, x, y } => {
+                write!(f, "{entity} at ({x}, {y}) is outside of the map")
+            }
+            MapIssue::DuplicateCell { first, second, x, y } => {
+                write!(f, "tiles `{first}` and `{second}` share the cell ({x}, {y})")
+            }
+            MapIssue::LayerSize { layer, cells, expected } => {
+                write!(f, "layer `{layer}` has {cells} cells, the map needs {expected}")
+            }
+            MapIssue::NoPlayer => write!(f, "no mob is marked as the player"),
+            MapIssue::MultiplePlayers { mobs } => {
+                write!(f, "several mobs are marked as the player: {}", mobs.join(", "))
+            }
+            MapIssue::UnknownBehaviour { entity, behaviour } => {
+                write!(f, "{entity} uses unknown behaviour `{behaviour}`")
+            }
+            MapIssue::InvalidBehaviour { entity, reason } => {
+                write!(f, "{entity} has an invalid behaviour: {reason}")
+            }
+            MapIssue::MobOutsideWorld { mob, x, y, world_w, world_h } => {
+                write!(
+                    f,
+                    "mob `{mob}` starts at ({x}, {y}), outside of the {world_w}x{world_h} world"
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for MapReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+// ============================
+// Implementation
+// ============================
+
+impl Atlas {
+    /// Checks frame definitions against the atlas image.
+    ///
+    /// Frames whose name has a `tile` part (like `grass_tile_big_0_1`) are
+    /// treated as tiles and must be exactly `tile_size` pixels wide.
+    ///
+    /// # Returns
+    ///
+    /// * `AtlasReport` - Report with every found problem.
+    pub fn validate(&self) -> AtlasReport {
+        let (image_w, image_h) = self.image.dimensions();
+
+        let mut frames: Vec<&Frame> = self.frames.values().collect();
+        frames.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut issues = Vec::new();
+        for frame in &frames {
+            if frame.w == 0 || frame.h == 0 {
+                issues.push(AtlasIssue::ZeroSized { frame: frame.name.clone() });
+                continue;
+            }
+
+            let right = frame.x as u64 + frame.w as u64;
+            let bottom = frame.y as u64 + frame.h as u64;
+            if right > image_w as u64 || bottom > image_h as u64 {
+                issues.push(AtlasIssue::OutOfBounds {
+                    frame: frame.name.clone(),
+                    image_w,
+                    image_h,
+                });
+            }
+
+            if self.is_transparent(frame) {
+                issues.push(AtlasIssue::Transparent { frame: frame.name.clone() });
+            }
+
+            if frame.name.split('_').any(|part| part == "tile") && frame.w != self.tile_size {
+                issues.push(AtlasIssue::TileSizeMismatch {
+                    frame: frame.name.clone(),
+                    width: frame.w,
+                    tile_size: self.tile_size,
+                });
+            }
+        }
+
+        // Sweep over frames sorted by left edge
+        let mut by_x: Vec<&Frame> = frames.iter().copied().filter(|f| f.w > 0 && f.h > 0).collect();
+        by_x.sort_by_key(|f| f.x);
+        for (i, a) in by_x.iter().enumerate() {
+            let a_right = a.x as u64 + a.w as u64;
+            for b in &by_x[i + 1..] {
+                if b.x as u64 >= a_right {
+                    break;
+                }
+                // Frames with the same rectangle are aliases of one sprite
+                if (a.x, a.y, a.w, a.h) == (b.x, b.y, b.w, b.h) {
+                    continue;
+                }
+                let a_bottom = a.y as u64 + a.h as u64;
+                let b_bottom = b.y as u64 + b.h as u64;
+                if (a.y as u64) < b_bottom && (b.y as u64) < a_bottom {
+                    let (first, second) = if a.name <= b.name { (a, b) } else { (b, a) };
+                    issues.push(AtlasIssue::Overlapping {
+                        first: first.name.clone(),
+                        second: second.name.clone(),
+                    });
+                }
+            }
+        }
+
+        AtlasReport { issues }
+    }
+
+    /// Checks if every pixel of the frame inside the image is transparent.
+    fn is_transparent(&self, frame: &Frame) -> bool {
+        opaque_bounds(&self.image, frame.x, frame.y, frame.w, frame.h).is_none()
+    }
+}
+
+impl GameMap {
+    /// Checks the map against the atlases it is drawn with.
+    ///
+    /// Mob start positions are world buffer pixels, the world is
+    /// `size * tile_size * 2` pixels wide and high. Mob and spawner assets may be
+    /// frames or animation clips of the entity atlas. Behaviour types depend on
+    /// what the game registers, they are checked by `BehaviourRegistry::validate`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tiles_atlas` - Atlas of static map elements
+    /// * `entities_atlas` - Atlas of mobs
+    ///
+    /// # Returns
+    ///
+    /// * `MapReport` - Report with every found problem.
+    pub fn validate(&self, tiles_atlas: &Atlas, entities_atlas: &Atlas) -> MapReport {
+        let mut issues = Vec::new();
+        let [width, height] = self.size;
+        let inside = |x: u32, y: u32| x < width && y < height;
+
+        let mut tiles: Vec<_> = self.tiles.values().collect();
+        tiles.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut cells: HashMap<(u32, u32), &str> = HashMap::new();
+        for tile in tiles {
+            let entity = MapEntity::Tile(tile.name.clone());
+            for asset in std::iter::once(&tile.asset).chain(&tile.side) {
+                if !tiles_atlas.contains_frame(asset) {
+                    issues.push(MapIssue::UnknownAsset {
+                        entity: entity.clone(),
+                        asset: asset.clone(),
+                    });
+                }
+            }
+            if !inside(tile.x, tile.y) {
+                issues.push(MapIssue::OutOfBounds { entity, x: tile.x, y: tile.y });
+            }
+            if let Some(first) = cells.insert((tile.x, tile.y), &tile.name) {
+                issues.push(MapIssue::DuplicateCell {
+                    first: first.to_string(),
+                    second: tile.name.clone(),
+                    x: tile.x,
+                    y: tile.y,
+                });
+            }
+        }
+
+        let mut objects: Vec<_> = self.objects.values().collect();
+        objects.sort_by(|a, b| a.name.cmp(&b.name));
+        for object in objects {
+            let entity = MapEntity::Object(object.name.clone());
+            if !tiles_atlas.contains_frame(&object.asset) {
+                issues.push(MapIssue::UnknownAsset {
+                    entity: entity.clone(),
+                    asset: object.asset.clone(),
+                });
+            }
+            if !inside(object.x, object.y) {
+                issues.push(MapIssue::OutOfBounds { entity, x: object.x, y: object.y });
+            }
+        }
+
+        for (i, asset) in self.tileset.iter().enumerate() {
+            if !tiles_atlas.contains_frame(asset) {
+                let entity = MapEntity::Tileset(i as u32 + 1);
+                issues.push(MapIssue::UnknownAsset { entity, asset: asset.clone() });
+            }
+        }
+        let expected = width as usize * height as usize;
+        for layer in &self.layers {
+            if let Some(side) = layer.side.as_ref().filter(|side| !tiles_atlas.contains_frame(side))
+            {
+                let entity = MapEntity::Layer(layer.name.clone());
+                issues.push(MapIssue::UnknownAsset { entity, asset: side.clone() });
+            }
+            if layer.cells.len() != expected {
+                issues.push(MapIssue::LayerSize {
+                    layer: layer.name.clone(),
+                    cells: layer.cells.len(),
+                    expected,
+                });
+            }
+        }
+
+        let tile_size = if self.tile_size > 0 { self.tile_size } else { tiles_atlas.tile_size };
+        let (world_w, world_h) = (width * tile_size * 2, height * tile_size * 2);
+        let mut mobs: Vec<_> = self.mobs.values().collect();
+        mobs.sort_by(|a, b| a.name.cmp(&b.name));
+        for mob in &mobs {
+            if !entities_atlas.contains_frame(&mob.asset)
+                && entities_atlas.get_animation(&mob.asset).is_none()
+            {
+                let entity = MapEntity::Mob(mob.name.clone());
+                issues.push(MapIssue::UnknownAsset { entity, asset: mob.asset.clone() });
+            }
+            if mob.x_start >= world_w || mob.y_start >= world_h {
+                issues.push(MapIssue::MobOutsideWorld {
+                    mob: mob.name.clone(),
+                    x: mob.x_start,
+                    y: mob.y_start,
+                    world_w,
+                    world_h,
+                });
+            }
+        }
+
+        let mut spawners: Vec<_> = self.spawners.values().collect();
+        spawners.sort_by(|a, b| a.name.cmp(&b.name));
+        for spawner in spawners {
+            let asset = &spawner.template.asset;
+            if !entities_atlas.contains_frame(asset)
+                && entities_atlas.get_animation(asset).is_none()
+            {
+                let entity = MapEntity::Spawner(spawner.name.clone());
+                issues.push(MapIssue::UnknownAsset { entity, asset: asset.clone() });
+            }
+        }
+
+        let players: Vec<String> =
+            mobs.iter().filter(|mob| mob.is_player).map(|mob| mob.name.clone()).collect();
+        match players.len() {
+            0 => issues.push(MapIssue::NoPlayer),
+            1 => {}
+            _ => issues.push(MapIssue::MultiplePlayers { mobs: players }),
+        }
+
+        MapReport { issues }
+    }
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn frame(name: &str, x: u32, y: u32, w: u32, h: u32) -> (String, Frame) {
+        (name.to_string(), Frame::new(name, x, y, w, h))
+    }
+
+    fn test_atlas(frames: Vec<(String, Frame)>) -> Atlas {
+        let mut image = RgbaImage::new(32, 32);
+        for y in 0..16 {
+            for x in 0..32 {
+                image.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+            }
+        }
+
+        Atlas::new(image, frames.into_iter().collect(), HashMap::new(), 16, 1)
+    }
+
+    // Test that bundled atlases are valid
+    #[test]
+    fn test_validate_bundled_atlases() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+
+        for path in ["assets/tiles/atlas.json", "assets/entities/atlas.json"] {
+            let atlas = Atlas::load(project_root.join(path)).unwrap();
+            let report = atlas.validate();
+            assert!(report.is_ok(), "{path}: {report}");
+        }
+    }
+
+    // Test that every kind of problem is detected
+    #[test]
+    fn test_validate_reports_issues() {
+        let atlas = test_atlas(vec![
+            frame("a_tile", 0, 0, 16, 16),
+            frame("b_overlap", 8, 8, 4, 4),
+            frame("c_outside", 24, 24, 16, 16),
+            frame("d_empty", 0, 0, 0, 5),
+            frame("e_transparent", 0, 20, 8, 8),
+            frame("f_tile", 16, 0, 10, 10),
+        ]);
+
+        let report = atlas.validate();
+        let expected = vec![
+            AtlasIssue::OutOfBounds { frame: "c_outside".into(), image_w: 32, image_h: 32 },
+            AtlasIssue::Transparent { frame: "c_outside".into() },
+            AtlasIssue::ZeroSized { frame: "d_empty".into() },
+            AtlasIssue::Transparent { frame: "e_transparent".into() },
+            AtlasIssue::TileSizeMismatch { frame: "f_tile".into(), width: 10, tile_size: 16 },
+            AtlasIssue::Overlapping { first: "a_tile".into(), second: "b_overlap".into() },
+        ];
+
+        assert!(!report.is_ok());
+        assert_eq!(report.issues, expected);
+        assert_eq!(report.issues_for("b_overlap").count(), 1);
+        assert_eq!(report.issues_for("c_outside").count(), 2);
+    }
+
+    // Test that adjacent frames do not count as overlapping
+    #[test]
+    fn test_validate_adjacent_frames() {
+        let atlas = test_atlas(vec![
+            frame("left", 0, 0, 16, 16),
+            frame("right", 16, 0, 16, 16),
+            frame("below", 0, 8, 16, 8),
+        ]);
+        let report = atlas.validate();
+        assert_eq!(
+            report.issues,
+            vec![AtlasIssue::Overlapping { first: "below".into(), second: "left".into() }]
+        );
+    }
+
+    // Test that bundled maps are valid against bundled atlases
+    #[test]
+    fn test_validate_bundled_maps() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let tiles_atlas = Atlas::load(project_root.join("assets/tiles/atlas.json")).unwrap();
+        let entities_atlas = Atlas::load(project_root.join("assets/entities/atlas.json")).unwrap();
+
+        for path in [
+            "examples/input.json",
+            "examples/layered.json",
+            "examples/crowd.json",
+            "examples/prefabs.json",
+        ] {
+            let map = GameMap::load(project_root.join(path)).unwrap();
+            let report = map.validate(&tiles_atlas, &entities_atlas);
+            assert!(report.is_ok(), "{path}: {report}");
+        }
+    }
+
+    // Test that every kind of map problem is detected
+    #[test]
+    fn test_validate_map_reports_issues() {
+        let atlas = test_atlas(vec![frame("grass", 0, 0, 16, 16), frame("hero", 16, 0, 16, 16)]);
+        let map: GameMap = r#"{
+            "meta": {"name": "broken", "tile_size": 16, "size": [2, 2]},
+            "tileset": ["grass", "lava"],
+            "layers": [{"name": "ground", "data": [1, 1, 1, 2]}],
+            "tiles": {
+                "a": {"x": 0, "y": 0, "asset": "grass"},
+                "b": {"x": 0, "y": 0, "asset": "grass"},
+                "c": {"x": 5, "y": 1, "asset": "gras", "z": 1, "side": "cliff"}
+            },
+            "objects": {"rock": {"x": 1, "y": 2, "asset": "grass"}},
+            "mobs": {
+                "p1": {"x_start": 0, "y_start": 0, "asset": "hero", "is_player": true},
+                "p2": {"x_start": 64, "y_start": 10, "asset": "hero", "is_player": true,
+                       "behaviour": {"type": "dancer", "direction": "sideways"}}
+            },
+            "spawners": {"imps": {"area": {"rect": [0, 0, 1, 1]}, "count": 3,
+                                  "template": {"asset": "imp"}}}
+        }"#
+        .parse()
+        .unwrap();
+
+        let report = map.validate(&atlas, &atlas);
+        let expected = vec![
+            MapIssue::DuplicateCell { first: "a".into(), second: "b".into(), x: 0, y: 0 },
+            MapIssue::UnknownAsset { entity: MapEntity::Tile("c".into()), asset: "gras".into() },
+            MapIssue::UnknownAsset { entity: MapEntity::Tile("c".into()), asset: "cliff".into() },
+            MapIssue::OutOfBounds { entity: MapEntity::Tile("c".into()), x: 5, y: 1 },
+            MapIssue::OutOfBounds { entity: MapEntity::Object("rock".into()), x: 1, y: 2 },
+            MapIssue::UnknownAsset { entity: MapEntity::Tileset(2), asset: "lava".into() },
+            MapIssue::MobOutsideWorld { mob: "p2".into(), x: 64, y: 10, world_w: 64, world_h: 64 },
+            MapIssue::UnknownAsset {
+                entity: MapEntity::Spawner("imps".into()),
+                asset: "imp".into(),
+            },
+            MapIssue::MultiplePlayers { mobs: vec!["p1".into(), "p2".into()] },
+        ];
+        assert_eq!(report.issues, expected);
+        assert_eq!(report.issues_for("p2").count(), 2);
+        assert!(report.to_string().contains("tile `c` uses unknown asset `gras`"));
+
+        let mut map = map;
+        map.mobs.clear();
+        map.layers[0].cells.pop();
+        let report = map.validate(&atlas, &atlas);
+        assert!(report.issues.contains(&MapIssue::NoPlayer));
+        assert_eq!(report.issues_for("ground").count(), 1);
+    }
+}
@@ -0,0 +1,362 @@
+use image::{imageops, Rgba};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::atlas::append_sprites;
+use super::Atlas;
+
+// ============================
+// JSON-level structs
+// ============================
+
+/// HSV tint from JSON.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub(super) struct JsonTint {
+    /// Hue shift in degrees
+    #[serde(default)]
+    pub hue: f32,
+    /// Saturation multiplier
+    #[serde(default = "default_multiplier")]
+    pub saturation: f32,
+    /// Value (brightness) multiplier
+    #[serde(default = "default_multiplier")]
+    pub value: f32,
+}
+
+fn default_multiplier() -> f32 {
+    1.0
+}
+
+/// Recoloured frame variant from JSON atlas data.
+#[derive(Deserialize, Debug)]
+pub(super) struct JsonVariant {
+    /// Name of the frame to recolour
+    pub source: String,
+    /// Colour replacements as `"#rrggbb": "#rrggbb"`
+    #[serde(default)]
+    pub palette: HashMap<String, String>,
+    /// Tint applied after the palette
+    #[serde(default)]
+    pub tint: Option<JsonTint>,
+}
+
+// ============================
+// Game-level structs
+// ============================
+
+/// Shift and scale of pixel colours in HSV space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tint {
+    /// Hue shift in degrees
+    pub hue: f32,
+    /// Saturation multiplier
+    pub saturation: f32,
+    /// Value (brightness) multiplier
+    pub value: f32,
+}
+
+/// Recoloured copy of an atlas frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    /// Name of the new frame
+    pub name: String,
+    /// Name of the frame to recolour
+    pub source: String,
+    /// Exact RGB replacements, alpha is kept
+    pub palette: HashMap<[u8; 3], [u8; 3]>,
+    /// Tint applied after the palette
+    pub tint: Option<Tint>,
+}
+
+// ============================
+// Implementation
+// ============================
+
+impl Default for Tint {
+    /// Creates a tint that keeps colours unchanged.
+    ///
+    /// # Returns
+    ///
+    /// A new `Tint` instance with zero shift and unit multipliers.
+    fn default() -> Self {
+        Self { hue: 0.0, saturation: 1.0, value: 1.0 }
+    }
+}
+
+impl Tint {
+    /// Applies the tint to a single colour.
+    ///
+    /// # Arguments
+    ///
+    /// * `rgb` - Colour to tint
+    ///
+    /// # Returns
+    ///
+    /// * `[u8; 3]` - Tinted colour.
+    pub fn apply(&self, [r, g, b]: [u8; 3]) -> [u8; 3] {
+        let (h, s, v) = rgb_to_hsv([r, g, b]);
+        let h = (h + self.hue).rem_euclid(360.0);
+        let s = (s * self.saturation).clamp(0.0, 1.0);
+        let v = (v * self.value).clamp(0.0, 1.0);
+        hsv_to_rgb(h, s, v)
+    }
+}
+
+impl Variant {
+    /// Builds a variant from its JSON definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the new frame
+    /// * `json` - Parsed JSON definition
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - Ok(Variant) if colours are valid, problem description otherwise.
+    pub(super) fn from_json(name: &str, json: JsonVariant) -> Result<Self, String> {
+        let mut palette = HashMap::with_capacity(json.palette.len());
+        for (from, to) in &json.palette {
+            let parse = |colour: &str| {
+                parse_colour(colour)
+                    .ok_or_else(|| format!("variant `{name}` has invalid colour `{colour}`"))
+            };
+            palette.insert(parse(from)?, parse(to)?);
+        }
+
+        let tint = json.tint.map(|t| Tint { hue: t.hue, saturation: t.saturation, value: t.value });
+        Ok(Variant { name: name.to_string(), source: json.source, palette, tint })
+    }
+
+    /// Recolours a single pixel.
+    fn recolour(&self, pixel: Rgba<u8>) -> Rgba<u8> {
+        let [r, g, b, a] = pixel.0;
+        if a == 0 {
+            return pixel;
+        }
+
+        let mut rgb = self.palette.get(&[r, g, b]).copied().unwrap_or([r, g, b]);
+        if let Some(tint) = &self.tint {
+            rgb = tint.apply(rgb);
+        }
+        Rgba([rgb[0], rgb[1], rgb[2], a])
+    }
+}
+
+impl Atlas {
+    /// Adds recoloured copies of frames.
+    ///
+    /// Recoloured pixels are placed below the current image. New frames keep
+    /// pivot, trim offset and untrimmed size of their source frames.
+    ///
+    /// # Arguments
+    ///
+    /// * `variants` - Variants to add, their sources must be frames of the atlas
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), String>` - Ok if all variants were added, problem description otherwise.
+    ///   Nothing is added on error.
+    pub fn add_variants(&mut self, variants: &[Variant]) -> Result<(), String> {
+        let mut sprites = Vec::with_capacity(variants.len());
+        let mut frames = self.frames.clone();
+
+        for variant in variants {
+            let Some(source) = self.frames.get(&variant.source) else {
+                return Err(format!(
+                    "variant `{}` refers to unknown frame `{}`",
+                    variant.name, variant.source
+                ));
+            };
+            if frames.contains_key(&variant.name) {
+                return Err(format!("variant `{}` replaces an existing frame", variant.name));
+            }
+            let (image_w, image_h) = self.image.dimensions();
+            if source.x as u64 + source.w as u64 > image_w as u64
+                || source.y as u64 + source.h as u64 > image_h as u64
+            {
+                return Err(format!(
+                    "variant `{}` copies frame `{}`, which is outside of the {image_w}x{image_h} image",
+                    variant.name, variant.source
+                ));
+            }
+
+            let mut pixels =
+                imageops::crop_imm(&self.image, source.x, source.y, source.w, source.h).to_image();
+            for pixel in pixels.pixels_mut() {
+                *pixel = variant.recolour(*pixel);
+            }
+
+            let mut frame = source.clone();
+            frame.name = variant.name.clone();
+            frames.insert(variant.name.clone(), frame);
+            sprites.push((variant.name.clone(), pixels));
+        }
+
+        if sprites.is_empty() {
+            return Ok(());
+        }
+
+        let image = std::mem::take(&mut self.image);
+        self.image = append_sprites(image, &sprites, &mut frames);
+        self.frames = frames;
+        self.decode_frames();
+        Ok(())
+    }
+}
+
+/// Parses a `#rrggbb` colour.
+fn parse_colour(colour: &str) -> Option<[u8; 3]> {
+    let hex = colour.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Converts a colour into hue in degrees, saturation and value.
+fn rgb_to_hsv([r, g, b]: [u8; 3]) -> (f32, f32, f32) {
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    (hue, saturation, max)
+}
+
+/// Converts hue in degrees, saturation and value into a colour.
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [u8; 3] {
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let channel = |value: f32| ((value + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    [channel(r), channel(g), channel(b)]
+}
+
+// ============================
+// Tests
+// ============================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::Frame;
+    use image::RgbaImage;
+    use std::path::PathBuf;
+
+    // Test HSV round trips and hue shifts
+    #[test]
+    fn test_tint() {
+        for rgb in [[0, 0, 0], [255, 255, 255], [169, 59, 59], [18, 200, 90], [10, 20, 250]] {
+            assert_eq!(Tint::default().apply(rgb), rgb);
+        }
+
+        let shift = Tint { hue: 120.0, ..Tint::default() };
+        assert_eq!(shift.apply([255, 0, 0]), [0, 255, 0]);
+        assert_eq!(shift.apply([0, 0, 255]), [255, 0, 0]);
+
+        let darker = Tint { value: 0.5, ..Tint::default() };
+        assert_eq!(darker.apply([200, 100, 0]), [100, 50, 0]);
+    }
+
+    // Test that variants recolour copies and keep the source frame intact
+    #[test]
+    fn test_add_variants() {
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, Rgba([10, 20, 30, 255]));
+        image.put_pixel(1, 0, Rgba([40, 50, 60, 128]));
+        let mut source = Frame::new("hero", 0, 0, 2, 1);
+        source.pivot = Some((1, 1));
+        let frames = HashMap::from([("hero".to_string(), source)]);
+        let mut atlas = Atlas::new(image, frames, HashMap::new(), 2, 1);
+
+        let blue = Variant {
+            name: "hero_blue".to_string(),
+            source: "hero".to_string(),
+            palette: HashMap::from([([40, 50, 60], [0, 0, 255])]),
+            tint: None,
+        };
+        atlas.add_variants(std::slice::from_ref(&blue)).unwrap();
+
+        let frame = atlas.get_frame("hero_blue").unwrap().clone();
+        assert_eq!(frame.pivot, Some((1, 1)));
+        assert_eq!(*atlas.image.get_pixel(frame.x, frame.y), Rgba([10, 20, 30, 255]));
+        assert_eq!(*atlas.image.get_pixel(frame.x + 1, frame.y), Rgba([0, 0, 255, 128]));
+        assert_eq!(*atlas.image.get_pixel(1, 0), Rgba([40, 50, 60, 128]));
+        assert!(atlas.sprite_id("hero_blue").is_some());
+
+        assert!(atlas.add_variants(&[blue]).unwrap_err().contains("existing"));
+        let unknown = Variant {
+            name: "x".to_string(),
+            source: "missing".to_string(),
+            palette: HashMap::new(),
+            tint: None,
+        };
+        assert!(atlas.add_variants(&[unknown]).is_err());
+
+        // Source frames past the image edge are not cropped silently
+        let mut wide = atlas.get_frame("hero").unwrap().clone();
+        wide.name = "wide".to_string();
+        wide.w = 3;
+        atlas.insert_frame(wide);
+        let clipped = Variant {
+            name: "wide_blue".to_string(),
+            source: "wide".to_string(),
+            palette: HashMap::new(),
+            tint: None,
+        };
+        assert_eq!(
+            atlas.add_variants(&[clipped]).unwrap_err(),
+            "variant `wide_blue` copies frame `wide`, which is outside of the 2x2 image"
+        );
+        assert!(!atlas.contains_frame("wide_blue"));
+    }
+
+    // Test variants declared in the bundled entity atlas
+    #[test]
+    fn test_bundled_variants() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let atlas = Atlas::load(project_root.join("assets/entities/atlas.json")).unwrap();
+
+        let imp = atlas.get_frame("imp_20_0").unwrap();
+        let blue = atlas.get_frame("imp_blue_20_0").unwrap();
+        assert_eq!((blue.w, blue.h), (imp.w, imp.h));
+        let red = Rgba([0xa9, 0x3b, 0x3b, 255]);
+        let count = |x: u32, y: u32, colour: Rgba<u8>| {
+            (0..imp.h)
+                .flat_map(|dy| (0..imp.w).map(move |dx| (dx, dy)))
+                .filter(|&(dx, dy)| *atlas.image.get_pixel(x + dx, y + dy) == colour)
+                .count()
+        };
+        assert!(count(imp.x, imp.y, red) > 0);
+        assert_eq!(count(blue.x, blue.y, red), 0);
+        assert_eq!(count(blue.x, blue.y, Rgba([0x3b, 0x5b, 0xa9, 255])), count(imp.x, imp.y, red));
+        assert!(atlas.get_animation("imp_blue_20").is_some());
+        assert!(atlas.get_animation("ghost_elite_30").is_some());
+        assert!(atlas.validate().is_ok(), "{}", atlas.validate());
+
+        let json: JsonVariant =
+            serde_json::from_str(r##"{"source": "a", "palette": {"#12": "#000000"}}"##).unwrap();
+        assert!(Variant::from_json("v", json).is_err());
+    }
+}
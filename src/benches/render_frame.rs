@@ -0,0 +1,209 @@
+//! Measures `Render::render_frame` with many mobs in view, and compares
+//! sprite blits from pre-decoded frames with per-pixel atlas image reads.
+//!
+//! Run via `cargo bench -p ferari --bench render_frame`.
+
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ferari::assets::{Atlas, GameMap, SpriteId};
+use ferari::world::Camera;
+use ferari::{Render, RenderableEntity};
+
+/// Logical screen size in pixels, same as in the demo.
+const VIEW_SIZE: u16 = 200;
+/// Tile size in pixels, same as in the demo.
+const TILE_SIZE: usize = 16;
+/// Mob counts from the README benchmark table.
+const MOB_COUNTS: [usize; 4] = [5_000, 10_000, 50_000, 100_000];
+/// Minimal measured time per mob count.
+const MIN_TIME: Duration = Duration::from_secs(2);
+
+/// Spreads mobs over the camera view with a fixed linear congruential sequence.
+fn mobs(count: usize, camera: &Camera, atlas: &Atlas) -> Vec<RenderableEntity> {
+    let mut seed: u32 = 0x2545_f491;
+    let mut next = |range: f32| {
+        seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (seed >> 8) as f32 / (1 << 24) as f32 * range
+    };
+
+    let imp = atlas.sprite_id("imp_20_0").unwrap();
+    let ghost = atlas.sprite_id("ghost_30_0").unwrap();
+
+    let left = camera.center_x - camera.width as f32 / 2.0;
+    let top = camera.center_y - camera.height as f32 / 2.0;
+    (0..count)
+        .map(|i| {
+            let sprite = if i % 2 == 0 { imp } else { ghost };
+            let x = left + next(camera.width as f32);
+            let y = top + next(camera.height as f32);
+            RenderableEntity::from_id(x, y, sprite)
+        })
+        .collect()
+}
+
+/// Times a closure until it ran at least three times and for `MIN_TIME`.
+///
+/// # Returns
+///
+/// * `f64` - Seconds per run.
+fn measure(mut run: impl FnMut()) -> f64 {
+    let mut runs = 0u32;
+    let start = Instant::now();
+    while runs < 3 || start.elapsed() < MIN_TIME {
+        run();
+        runs += 1;
+    }
+    start.elapsed().as_secs_f64() / runs as f64
+}
+
+/// Blends an ARGB pixel over another by its alpha.
+fn blend(src: u32, dst: u32) -> u32 {
+    let alpha = src >> 24;
+    let channel = |shift: u32| {
+        let s = (src >> shift) & 0xFF;
+        let d = (dst >> shift) & 0xFF;
+        ((s * alpha + d * (255 - alpha)) / 255) << shift
+    };
+    0xFF00_0000 | channel(16) | channel(8) | channel(0)
+}
+
+/// Places the frames of entities on a view like the renderer does.
+fn placements(
+    entities: &[RenderableEntity],
+    camera: &Camera,
+    atlas: &Atlas,
+) -> Vec<(SpriteId, i32, i32)> {
+    entities
+        .iter()
+        .filter_map(|entity| {
+            let sprite = entity.sprite_id(atlas)?;
+            let frame = atlas.sprite(sprite)?;
+            let anchor_x = (entity.x - camera.center_x) as i32 + camera.width as i32 / 2;
+            let anchor_y = (entity.y - camera.center_y) as i32 + camera.height as i32 / 2;
+            let (source_w, source_h) = frame.untrimmed_size();
+            let (x, y) = frame.origin(anchor_x, anchor_y, (source_w as i32 / 2, source_h as i32));
+            Some((sprite, x, y))
+        })
+        .collect()
+}
+
+/// Blits frames by reading every pixel from the atlas image, the baseline.
+fn blit_per_pixel(atlas: &Atlas, placed: &[(SpriteId, i32, i32)], buf: &mut [u32], width: i32) {
+    let height = buf.len() as i32 / width;
+    for &(sprite, screen_x, screen_y) in placed {
+        let Some(frame) = atlas.sprite(sprite) else {
+            continue;
+        };
+        for dy in 0..frame.h {
+            let y = screen_y + dy as i32;
+            if y < 0 || y >= height {
+                continue;
+            }
+            for dx in 0..frame.w {
+                let x = screen_x + dx as i32;
+                if x < 0 || x >= width {
+                    continue;
+                }
+                let [r, g, b, a] = atlas.image.get_pixel(frame.x + dx, frame.y + dy).0;
+                if a == 0 {
+                    continue;
+                }
+                let color = (a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32;
+                let dst = &mut buf[(y * width + x) as usize];
+                *dst = if a == 255 { color } else { blend(color, *dst) };
+            }
+        }
+    }
+}
+
+/// Blits frames from their pre-decoded pixel spans.
+fn blit_decoded(atlas: &Atlas, placed: &[(SpriteId, i32, i32)], buf: &mut [u32], width: i32) {
+    let height = buf.len() as i32 / width;
+    for &(sprite, screen_x, screen_y) in placed {
+        let Some(pixels) = atlas.sprite_pixels(sprite) else {
+            continue;
+        };
+        for dy in 0..pixels.h {
+            let y = screen_y + dy as i32;
+            if y < 0 || y >= height {
+                continue;
+            }
+            let row = pixels.row(dy);
+            let buf_row = &mut buf[(y * width) as usize..][..width as usize];
+            for span in pixels.row_spans(dy) {
+                let start = (screen_x + span.start as i32).max(0);
+                let end = (screen_x + (span.start + span.len) as i32).min(width);
+                if start >= end {
+                    continue;
+                }
+                let src = &row[(start - screen_x) as usize..(end - screen_x) as usize];
+                let dst = &mut buf_row[start as usize..end as usize];
+                if span.opaque {
+                    dst.copy_from_slice(src);
+                } else {
+                    dst.iter_mut().zip(src).for_each(|(dst, &color)| *dst = blend(color, *dst));
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+    let tiles_atlas = Arc::new(Atlas::load(project_root.join("assets/tiles/atlas.json")).unwrap());
+    let entities_atlas = Atlas::load(project_root.join("assets/entities/atlas.json")).unwrap();
+    let game = Arc::new(GameMap::load(project_root.join("examples/input.json")).unwrap());
+
+    let world_width = game.size[0] as usize * TILE_SIZE * 2;
+    let world_height = game.size[1] as usize * TILE_SIZE * 2;
+    let mut render = Render::new(
+        vec![195213255; world_width * world_height],
+        world_height,
+        world_width,
+        entities_atlas,
+        vec![0; world_width * world_height],
+    );
+    render.init(game, tiles_atlas);
+
+    let camera =
+        Camera::new((world_width / 2) as f32, (world_height / 2) as f32, VIEW_SIZE, VIEW_SIZE);
+    let mut buf = vec![0; VIEW_SIZE as usize * VIEW_SIZE as usize];
+
+    println!("| Mobs in view | ms per frame | FPS |");
+    println!("|--------------|--------------|-----|");
+    for count in MOB_COUNTS {
+        let entities = mobs(count, &camera, &render.entity_atlas);
+        let per_frame = measure(|| {
+            render.render_frame(black_box(&entities), &camera, &mut buf);
+            black_box(&buf);
+        });
+        println!("| {count} | {:.2} | {:.1} |", per_frame * 1000.0, 1.0 / per_frame);
+    }
+
+    println!();
+    println!("| Mobs in view | Per-pixel reads, ms | Pre-decoded frames, ms | Speedup |");
+    println!("|--------------|---------------------|------------------------|---------|");
+    let atlas = &render.entity_atlas;
+    let width = VIEW_SIZE as i32;
+    for count in MOB_COUNTS {
+        let entities = mobs(count, &camera, atlas);
+        let placed = placements(&entities, &camera, atlas);
+        let per_pixel = measure(|| {
+            blit_per_pixel(atlas, black_box(&placed), &mut buf, width);
+            black_box(&buf);
+        });
+        let decoded = measure(|| {
+            blit_decoded(atlas, black_box(&placed), &mut buf, width);
+            black_box(&buf);
+        });
+        println!(
+            "| {count} | {:.2} | {:.2} | {:.1}x |",
+            per_pixel * 1000.0,
+            decoded * 1000.0,
+            per_pixel / decoded
+        );
+    }
+}
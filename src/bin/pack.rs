@@ -0,0 +1,84 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use ferari::assets::pack::{pack_dir, write_atlas, PackOptions};
+
+const USAGE: &str = "\
+Usage: ferari-pack <sprites_dir> <atlas.json> [options]
+
+Packs every PNG of <sprites_dir> into <atlas.json> and a PNG image next to it.
+
+Options:
+  --padding <px>      Empty pixels between sprites (default: 1)
+  --max-width <px>    Maximum width of the atlas image (default: 2048)
+  --tile-size <px>    Value of meta.tile_size (default: 16)
+  --pivot <x>,<y>     Pivot as a fraction of the sprite size, e.g. 0.5,1.0
+  --no-trim           Keep transparent borders of sprites";
+
+/// Parses command line arguments into input directory, output path and options.
+fn parse_args(args: &[String]) -> Result<(PathBuf, PathBuf, PackOptions), String> {
+    let mut options = PackOptions::default();
+    let mut positional = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or(format!("missing value for {name}"));
+        match arg.as_str() {
+            "--padding" => options.padding = parse_number(value(arg)?)?,
+            "--max-width" => options.max_width = parse_number(value(arg)?)?,
+            "--tile-size" => options.tile_size = parse_number(value(arg)?)?,
+            "--pivot" => {
+                let pivot = value(arg)?;
+                let (x, y) = pivot.split_once(',').ok_or(format!("invalid pivot `{pivot}`"))?;
+                options.pivot = Some((parse_number(x)?, parse_number(y)?));
+            }
+            "--no-trim" => options.trim = false,
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{flag}`")),
+            _ => positional.push(PathBuf::from(arg)),
+        }
+    }
+
+    match <[PathBuf; 2]>::try_from(positional) {
+        Ok([input, output]) => Ok((input, output, options)),
+        Err(_) => Err("expected <sprites_dir> and <atlas.json>".to_string()),
+    }
+}
+
+/// Parses a numeric option value.
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("invalid number `{value}`"))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let (input, output, options) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = pack_dir(&input, &options).and_then(|atlas| {
+        write_atlas(&atlas, &output)?;
+        Ok(atlas)
+    });
+
+    match result {
+        Ok(atlas) => {
+            let (w, h) = atlas.image.dimensions();
+            println!("Packed {} sprites into {} ({w}x{h})", atlas.frame_count(), output.display());
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,8 +1,13 @@
 pub mod assets;
 pub mod draw;
 pub mod input;
+pub mod reload;
 pub mod render;
+pub mod rng;
 pub mod time;
 pub mod world;
 
+#[cfg(test)]
+mod test_util;
+
 pub use render::{Render, RenderableEntity};
@@ -0,0 +1,347 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::assets::{AssetError, Atlas, GameMap};
+use crate::render::Render;
+use crate::world::State;
+
+/// Default time between two checks of modification times.
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Files whose modification times are compared between polls.
+#[derive(Debug, Clone, Default)]
+struct WatchedFiles {
+    /// Watched paths with their last seen modification times
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+/// Outcome of a single [`HotReload::poll`].
+#[derive(Debug, Default)]
+pub struct ReloadReport {
+    /// Files that were reloaded and swapped in
+    pub reloaded: Vec<PathBuf>,
+    /// Problems with changed files, their last good versions stay in place
+    pub errors: Vec<AssetError>,
+    /// Mobs were spawned again from the reloaded map, spawners must start over
+    pub respawned: bool,
+}
+
+/// Reloads the map and atlases of a running game when their files change.
+///
+/// Modification times of the map file, the prefab files it uses, both atlas
+/// JSON files and their images are polled. Changed files are loaded again.
+/// The map and the tiles atlas are baked into the world buffer again, the
+/// entity atlas is swapped in the renderer. A file that fails to load is
+/// reported and the last good version is kept.
+pub struct HotReload {
+    /// Last good map, shared with the renderer
+    pub map: Arc<GameMap>,
+    /// Last good atlas of static map elements, shared with the renderer
+    pub tiles_atlas: Arc<Atlas>,
+    /// Minimal time between two checks of modification times
+    pub interval: Duration,
+    /// Colour of world buffer pixels not covered by the map
+    pub background: u32,
+    /// Path to the map JSON file
+    map_path: PathBuf,
+    /// Path to the tiles atlas JSON file
+    tiles_path: PathBuf,
+    /// Path to the entity atlas JSON file
+    entities_path: PathBuf,
+    /// Watched map file and its prefab files
+    map_files: WatchedFiles,
+    /// Watched tiles atlas JSON and image
+    tiles_files: WatchedFiles,
+    /// Watched entity atlas JSON and image
+    entity_files: WatchedFiles,
+    /// Time of the last check, None before the first one
+    last_poll: Option<Instant>,
+}
+
+impl WatchedFiles {
+    /// Starts watching files from their current modification times.
+    fn new(paths: Vec<PathBuf>) -> Self {
+        Self { files: paths.into_iter().map(|path| (path.clone(), modified(&path))).collect() }
+    }
+
+    /// Starts watching a map file and the prefab files it uses.
+    fn map(map_path: &Path, map: &GameMap) -> Self {
+        let dir = map_path.parent().unwrap_or(Path::new(""));
+        let mut paths = vec![map_path.to_path_buf()];
+        let mut prefab_files: Vec<_> =
+            map.prefabs.values().filter_map(|source| source.file.as_ref()).collect();
+        prefab_files.sort();
+        paths.extend(prefab_files.into_iter().map(|file| dir.join(file)));
+        Self::new(paths)
+    }
+
+    /// Starts watching an atlas JSON file and the image it names.
+    fn atlas(json_path: &Path) -> Self {
+        let mut paths = vec![json_path.to_path_buf()];
+        paths.extend(Atlas::image_path(json_path).ok());
+        Self::new(paths)
+    }
+
+    /// Checks for changed files and remembers their new modification times.
+    fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, last) in &mut self.files {
+            let current = modified(path);
+            if current != *last {
+                *last = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Reads the modification time of a file, None if it cannot be read.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+impl ReloadReport {
+    /// Checks if nothing was reloaded and no error occurred.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the poll changed nothing, false otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.reloaded.is_empty() && self.errors.is_empty()
+    }
+}
+
+impl HotReload {
+    /// Starts watching the files of already loaded assets.
+    ///
+    /// # Arguments
+    ///
+    /// * `map_path` - Path to the map JSON file
+    /// * `tiles_path` - Path to the atlas JSON file of static map elements
+    /// * `entities_path` - Path to the atlas JSON file of entities
+    /// * `map` - Map loaded from `map_path`
+    /// * `tiles_atlas` - Atlas loaded from `tiles_path`
+    /// * `background` - Colour of world buffer pixels not covered by the map
+    ///
+    /// # Returns
+    ///
+    /// A new `HotReload` instance polling every half a second.
+    pub fn new<P: AsRef<Path>>(
+        map_path: P,
+        tiles_path: P,
+        entities_path: P,
+        map: Arc<GameMap>,
+        tiles_atlas: Arc<Atlas>,
+        background: u32,
+    ) -> Self {
+        let map_path = map_path.as_ref().to_path_buf();
+        let tiles_path = tiles_path.as_ref().to_path_buf();
+        let entities_path = entities_path.as_ref().to_path_buf();
+        let map_files = WatchedFiles::map(&map_path, &map);
+
+        Self {
+            map,
+            tiles_atlas,
+            interval: DEFAULT_INTERVAL,
+            background,
+            map_files,
+            tiles_files: WatchedFiles::atlas(&tiles_path),
+            entity_files: WatchedFiles::atlas(&entities_path),
+            map_path,
+            tiles_path,
+            entities_path,
+            last_poll: None,
+        }
+    }
+
+    /// Reloads changed files and applies them to the running game.
+    ///
+    /// Does nothing if less than `interval` passed since the last check.
+    /// The state keeps its units where possible, see [`State::reload`].
+    /// Sprite ids issued by a replaced entity atlas must be resolved again.
+    ///
+    /// # Arguments
+    ///
+    /// * `render` - Renderer to re-bake and to swap the entity atlas in
+    /// * `state` - Running game state
+    ///
+    /// # Returns
+    ///
+    /// * `ReloadReport` - Reloaded files and errors of this poll.
+    pub fn poll(&mut self, render: &mut Render, state: &mut State) -> ReloadReport {
+        let mut report = ReloadReport::default();
+        let now = Instant::now();
+        if self.last_poll.is_some_and(|last| now.duration_since(last) < self.interval) {
+            return report;
+        }
+        self.last_poll = Some(now);
+
+        let mut rebake = false;
+
+        if self.map_files.changed() {
+            match GameMap::load(&self.map_path) {
+                Ok(map) => {
+                    report.respawned = state.reload(&self.map, &map);
+                    self.map_files = WatchedFiles::map(&self.map_path, &map);
+                    self.map = Arc::new(map);
+                    rebake = true;
+                    report.reloaded.push(self.map_path.clone());
+                }
+                Err(err) => report.errors.push(err),
+            }
+        }
+
+        if self.tiles_files.changed() {
+            match Atlas::load(&self.tiles_path) {
+                Ok(atlas) => {
+                    self.tiles_atlas = Arc::new(atlas);
+                    self.tiles_files = WatchedFiles::atlas(&self.tiles_path);
+                    rebake = true;
+                    report.reloaded.push(self.tiles_path.clone());
+                }
+                Err(err) => report.errors.push(err),
+            }
+        }
+
+        if self.entity_files.changed() {
+            match Atlas::load(&self.entities_path) {
+                Ok(atlas) => {
+                    render.entity_atlas = atlas;
+                    self.entity_files = WatchedFiles::atlas(&self.entities_path);
+                    report.reloaded.push(self.entities_path.clone());
+                }
+                Err(err) => report.errors.push(err),
+            }
+        }
+
+        if rebake {
+            render.rebake(Arc::clone(&self.map), Arc::clone(&self.tiles_atlas), self.background);
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use image::{Rgba, RgbaImage};
+    use std::fs::File;
+
+    const BACKGROUND: u32 = 0xFF102030;
+
+    fn atlas_json(frame: &str) -> String {
+        format!(
+            r#"{{"frames": {{"{frame}": {{"x": 0, "y": 0, "w": 4, "h": 4}}}},
+                "meta": {{"image": "atlas.png", "tile_size": 4, "version": 1}}}}"#
+        )
+    }
+
+    fn map_json(tiles: &[(u32, u32)]) -> String {
+        let tiles: Vec<String> = tiles
+            .iter()
+            .enumerate()
+            .map(|(i, (x, y))| format!(r#""t{i}": {{"x": {x}, "y": {y}, "asset": "tile"}}"#))
+            .collect();
+        format!(
+            r#"{{"meta": {{"name": "m", "tile_size": 4, "size": [4, 4]}},
+                "tiles": {{{}}}, "objects": {{}},
+                "mobs": {{"p": {{"x_start": 1, "y_start": 1, "asset": "hero", "is_player": true}}}}}}"#,
+            tiles.join(", ")
+        )
+    }
+
+    // Writes a file and moves its modification time forward, so polls see it
+    fn write(path: &Path, contents: &str, step: u64) {
+        fs::write(path, contents).unwrap();
+        let time = SystemTime::now() + Duration::from_secs(10 * step);
+        File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+    }
+
+    // Test reloading a map, keeping the last good version on errors, and swapping atlases
+    #[test]
+    fn test_hot_reload() {
+        let dir = TempDir::new("reload");
+        let mut image = RgbaImage::new(4, 4);
+        for pixel in image.pixels_mut() {
+            *pixel = Rgba([0, 200, 0, 255]);
+        }
+        image.save(dir.join("atlas.png")).unwrap();
+
+        let atlas_path = dir.join("atlas.json");
+        let entities_path = dir.join("entities.json");
+        let map_path = dir.join("map.json");
+        write(&atlas_path, &atlas_json("tile"), 0);
+        write(&entities_path, &atlas_json("hero"), 0);
+        write(&map_path, &map_json(&[(0, 0)]), 0);
+
+        let map = Arc::new(GameMap::load(&map_path).unwrap());
+        let tiles_atlas = Arc::new(Atlas::load(&atlas_path).unwrap());
+        let mut render = Render::new(
+            vec![BACKGROUND; 32 * 32],
+            32,
+            32,
+            Atlas::load(&entities_path).unwrap(),
+            vec![0; 32 * 32],
+        );
+        render.init(Arc::clone(&map), Arc::clone(&tiles_atlas));
+        let mut state = State::new(&map);
+        state.player.x = 3.5;
+
+        let mut hot_reload =
+            HotReload::new(&map_path, &atlas_path, &entities_path, map, tiles_atlas, BACKGROUND);
+        hot_reload.interval = Duration::ZERO;
+        assert!(hot_reload.poll(&mut render, &mut state).is_empty());
+
+        let covered =
+            |render: &Render| render.world_buf.iter().filter(|&&p| p != BACKGROUND).count();
+        let before = covered(&render);
+
+        write(&map_path, &map_json(&[(0, 0), (2, 2)]), 1);
+        let report = hot_reload.poll(&mut render, &mut state);
+        assert_eq!(report.reloaded, vec![map_path.clone()]);
+        assert!(!report.respawned);
+        assert_eq!(hot_reload.map.tile_count(), 2);
+        assert_eq!(covered(&render), 2 * before);
+        assert_eq!(state.player.x, 3.5);
+
+        write(&map_path, "{ broken", 2);
+        let report = hot_reload.poll(&mut render, &mut state);
+        assert!(report.reloaded.is_empty());
+        assert!(matches!(report.errors.as_slice(), [AssetError::Json { .. }]));
+        assert_eq!(hot_reload.map.tile_count(), 2);
+        assert!(hot_reload.poll(&mut render, &mut state).is_empty());
+
+        write(&entities_path, &atlas_json("ghost"), 3);
+        let report = hot_reload.poll(&mut render, &mut state);
+        assert_eq!(report.reloaded, vec![entities_path.clone()]);
+        assert!(render.entity_atlas.contains_frame("ghost"));
+
+        // Prefab files of the map are watched too
+        let prefab_path = dir.join("hut.json");
+        write(&prefab_path, r#"{"tiles": {"t": {"x": 0, "y": 0, "asset": "tile"}}}"#, 4);
+        let map = map_json(&[]).replacen(
+            "\"objects\"",
+            r#""prefabs": {"hut": "hut.json"},
+               "instances": {"a": {"prefab": "hut", "x": 1, "y": 1}}, "objects""#,
+            1,
+        );
+        write(&map_path, &map, 4);
+        assert_eq!(hot_reload.poll(&mut render, &mut state).reloaded, vec![map_path.clone()]);
+        assert_eq!(hot_reload.map.tile_count(), 1);
+
+        write(
+            &prefab_path,
+            r#"{"tiles": {"t": {"x": 0, "y": 0, "asset": "tile"},
+                                          "u": {"x": 1, "y": 0, "asset": "tile"}}}"#,
+            5,
+        );
+        let report = hot_reload.poll(&mut render, &mut state);
+        assert_eq!(report.reloaded, vec![map_path.clone()]);
+        assert_eq!(hot_reload.map.tile_count(), 2);
+    }
+}
@@ -0,0 +1,621 @@
+use std::ops::Range;
+
+use crate::assets::{Atlas, Frame, FramePixels, GameMap, Object, Tile};
+
+/// Tile cell placed for baking into the world buffer
+#[derive(Clone, Copy)]
+struct TileCell<'a> {
+    x: u32,
+    y: u32,
+    /// Elevation in height units
+    z: u32,
+    asset: &'a str,
+    /// Asset of the column below a raised cell
+    side: Option<&'a str>,
+}
+
+impl TileCell<'_> {
+    /// Depth ordering key, farther cells first and lower cells first
+    fn depth(&self) -> (u32, u32) {
+        (self.x + self.y, self.z)
+    }
+}
+
+/// Named tiles and objects of a map grouped by cell
+///
+/// Built once per map so that baking a part of the world only visits the
+/// elements of the cells around it. Elements are kept in bake order, by
+/// (x+y), elevation and name.
+pub(super) struct CellIndex {
+    /// Names of the named tiles in bake order
+    tiles: Vec<String>,
+    /// Names of the objects in bake order
+    objects: Vec<String>,
+    /// Positions in `tiles` by cell
+    tile_cells: Buckets,
+    /// Positions in `objects` by cell
+    object_cells: Buckets,
+}
+
+/// Positions of elements in bake order grouped by cell
+struct Buckets {
+    /// Map width in cells
+    columns: usize,
+    /// Start of each cell's run in `positions`, row-major with one end entry
+    starts: Vec<usize>,
+    /// Ascending positions of the elements of each cell
+    positions: Vec<usize>,
+    /// Positions of elements outside of the map grid
+    outside: Vec<usize>,
+}
+
+impl CellIndex {
+    /// Groups the named tiles and objects of a map by cell
+    ///
+    /// # Arguments
+    ///
+    /// * `game` - The game map containing tiles and objects
+    ///
+    /// # Returns
+    ///
+    /// A new `CellIndex` instance for `game`.
+    pub(super) fn new(game: &GameMap) -> Self {
+        let mut tiles: Vec<(&String, &Tile)> = game.tiles.iter().collect();
+        tiles.sort_by_key(|(name, tile)| (tile.x + tile.y, tile.z, *name));
+        let mut objects: Vec<(&String, &Object)> = game.objects.iter().collect();
+        objects.sort_by_key(|(name, object)| (object.x + object.y, object.z, *name));
+
+        let tile_cells: Vec<_> = tiles.iter().map(|(_, tile)| (tile.x, tile.y)).collect();
+        let object_cells: Vec<_> = objects.iter().map(|(_, object)| (object.x, object.y)).collect();
+        Self {
+            tiles: tiles.into_iter().map(|(name, _)| name.clone()).collect(),
+            objects: objects.into_iter().map(|(name, _)| name.clone()).collect(),
+            tile_cells: Buckets::new(game.size, &tile_cells),
+            object_cells: Buckets::new(game.size, &object_cells),
+        }
+    }
+}
+
+impl Buckets {
+    /// Groups element cells given in bake order
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - Map dimensions [width, height]
+    /// * `cells` - Cell of each element
+    ///
+    /// # Returns
+    ///
+    /// A new `Buckets` instance.
+    fn new(size: [u32; 2], cells: &[(u32, u32)]) -> Self {
+        let (columns, rows) = (size[0] as usize, size[1] as usize);
+        let cell_index = |&(x, y): &(u32, u32)| {
+            let (x, y) = (x as usize, y as usize);
+            (x < columns && y < rows).then_some(y * columns + x)
+        };
+
+        let mut starts = vec![0; columns * rows + 1];
+        for index in cells.iter().filter_map(cell_index) {
+            starts[index] += 1;
+        }
+        let mut total = 0;
+        for start in &mut starts {
+            let count = *start;
+            *start = total;
+            total += count;
+        }
+
+        let mut next = starts.clone();
+        let mut positions = vec![0; total];
+        let mut outside = Vec::new();
+        for (position, cell) in cells.iter().enumerate() {
+            match cell_index(cell) {
+                Some(index) => {
+                    positions[next[index]] = position;
+                    next[index] += 1;
+                }
+                None => outside.push(position),
+            }
+        }
+        Self { columns, starts, positions, outside }
+    }
+
+    /// Collects the elements of a window of cells and the ones outside of the map grid
+    ///
+    /// # Arguments
+    ///
+    /// * `xs` - Columns of the window, inside the map
+    /// * `ys` - Rows of the window, inside the map
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<usize>` - Ascending positions in bake order.
+    fn window(&self, xs: Range<u32>, ys: Range<u32>) -> Vec<usize> {
+        let mut found = self.outside.clone();
+        for y in ys {
+            let row = y as usize * self.columns;
+            let (start, end) = (row + xs.start as usize, row + xs.end as usize);
+            found.extend_from_slice(&self.positions[self.starts[start]..self.starts[end]]);
+        }
+        found.sort_unstable();
+        found
+    }
+}
+
+/// Part of the world buffer and static shadow map that is being baked
+///
+/// Positions passed to the drawing methods are world buffer coordinates,
+/// pixels outside of the part are clipped.
+pub(super) struct Canvas<'a> {
+    /// Row-major pixels of the part
+    pub pixels: &'a mut [u32],
+    /// Row-major static shadow intensities of the part
+    pub shadow: &'a mut [u8],
+    /// World buffer X of the leftmost column
+    pub left: i32,
+    /// World buffer Y of the top row
+    pub top: i32,
+    /// Width of the part in pixels
+    pub width: usize,
+    /// Height of the part in pixels
+    pub height: usize,
+}
+
+/// Computes the top-left world buffer position of a static map element
+///
+/// The cell is projected onto the centre of its tile's top face and the
+/// frame pivot is placed there. Frames without a pivot are anchored at
+/// the centre of the top face of a tile-sized block. Each height unit
+/// raises the element by one block, half a tile size.
+///
+/// # Arguments
+///
+/// * `world` - Size of the whole world buffer (width, height)
+/// * `x` - X coordinate of the cell
+/// * `y` - Y coordinate of the cell
+/// * `z` - Elevation in height units
+/// * `frame` - Sprite frame of the element
+/// * `static_atlas` - Sprite atlas for static map elements
+///
+/// # Returns
+///
+/// * `(i32, i32)` - Position of the top-left pixel of the frame.
+fn static_origin(
+    world: (usize, usize),
+    x: u32,
+    y: u32,
+    z: u32,
+    frame: &Frame,
+    static_atlas: &Atlas,
+) -> (i32, i32) {
+    let ts = static_atlas.tile_size as i32;
+    let (anchor_x, anchor_y) = anchor(world, ts, x, y);
+    let anchor_y = anchor_y - z as i32 * (ts / 2);
+
+    let (source_w, source_h) = frame.untrimmed_size();
+    let default_pivot = (source_w as i32 / 2, source_h as i32 / 2 - ts / 4);
+    frame.origin(anchor_x, anchor_y, default_pivot)
+}
+
+/// Projects the centre of a cell's ground-level top face into the world buffer
+///
+/// # Arguments
+///
+/// * `world` - Size of the whole world buffer (width, height)
+/// * `ts` - Tile size of the static atlas
+/// * `x` - X coordinate of the cell
+/// * `y` - Y coordinate of the cell
+///
+/// # Returns
+///
+/// * `(i32, i32)` - World buffer position of the anchor.
+fn anchor(world: (usize, usize), ts: i32, x: u32, y: u32) -> (i32, i32) {
+    // Base screen offsets
+    let offset_x = world.0 as i32 / 2;
+    let offset_y = (world.1 as i32 / 2) - ts;
+
+    let anchor_x = (x as i32 - y as i32) * (ts / 2) + offset_x + ts / 2;
+    let anchor_y = (x as i32 + y as i32) * (ts / 4) + offset_y - ts / 4;
+    (anchor_x, anchor_y)
+}
+
+/// Computes how far from its anchor a static element of a map may draw
+///
+/// Covers the largest frame with its pivot and trim offset, its shadow,
+/// the highest elevation and the blur of shadow edges.
+///
+/// # Arguments
+///
+/// * `game` - The game map containing tiles and objects
+/// * `static_atlas` - Sprite atlas for static map elements
+///
+/// # Returns
+///
+/// * `i32` - Distance in world buffer pixels.
+pub(super) fn reach(game: &GameMap, static_atlas: &Atlas) -> i32 {
+    let ts = static_atlas.tile_size as i32;
+    let sprite = static_atlas
+        .iter_frames()
+        .map(|frame| {
+            let (w, h) = frame.untrimmed_size();
+            let (pivot_x, pivot_y) = frame.pivot.unwrap_or((0, 0));
+            let (offset_x, offset_y) = frame.offset;
+            w as i32 + h as i32 + pivot_x.abs() + pivot_y.abs() + offset_x.abs() + offset_y.abs()
+        })
+        .max()
+        .unwrap_or(0);
+
+    let tiles = game.tiles.values().map(|tile| tile.z);
+    let objects = game.objects.values().map(|object| object.z);
+    let layers =
+        game.layers.iter().map(|layer| layer.z + layer.heights.iter().copied().max().unwrap_or(0));
+    let z = tiles.chain(objects).chain(layers).max().unwrap_or(0);
+
+    sprite + ts + z as i32 * (ts / 2) + 2
+}
+
+/// Bakes the static map elements that reach into a canvas
+///
+/// Renders tiles and objects from the game map building isometric projection.
+/// Named tiles are drawn first, then every tile layer in order.
+/// Sorts elements by their (x+y) coordinate and then by elevation for depth ordering.
+/// Raised tiles stand on columns of side frames, and are drawn again over
+/// objects behind them. Elements are skipped only when they cannot touch the
+/// canvas, so a part baked alone equals the same part of a whole-world bake.
+///
+/// # Arguments
+///
+/// * `canvas` - Part of the world buffer to draw into
+/// * `world` - Size of the whole world buffer (width, height)
+/// * `game` - The game map containing tiles and objects
+/// * `index` - Elements of `game` grouped by cell
+/// * `static_atlas` - Sprite atlas for static map elements
+/// * `reach` - Result of [`reach`] for the map, None bakes every element
+pub(super) fn bake(
+    canvas: &mut Canvas,
+    world: (usize, usize),
+    game: &GameMap,
+    index: &CellIndex,
+    static_atlas: &Atlas,
+    reach: Option<i32>,
+) {
+    let ts = static_atlas.tile_size as i32;
+    let (left, top) = (canvas.left, canvas.top);
+    let (right, bottom) = (left + canvas.width as i32, top + canvas.height as i32);
+    let touches = |x: u32, y: u32| {
+        reach.is_none_or(|reach| {
+            let (anchor_x, anchor_y) = anchor(world, ts, x, y);
+            anchor_x >= left - reach
+                && anchor_x < right + reach
+                && anchor_y >= top - reach
+                && anchor_y < bottom + reach
+        })
+    };
+
+    let (xs, ys) = match reach {
+        Some(reach) => canvas.cell_window(world, game.size, ts, reach),
+        None => (0..game.size[0], 0..game.size[1]),
+    };
+
+    let mut passes: Vec<Vec<TileCell>> = Vec::with_capacity(game.layers.len() + 1);
+    passes.push(
+        index
+            .tile_cells
+            .window(xs.clone(), ys.clone())
+            .into_iter()
+            .map(|position| &game.tiles[&index.tiles[position]])
+            .filter(|tile| touches(tile.x, tile.y))
+            .map(|tile| TileCell {
+                x: tile.x,
+                y: tile.y,
+                z: tile.z,
+                asset: &tile.asset,
+                side: tile.side.as_deref(),
+            })
+            .collect(),
+    );
+    for layer in &game.layers {
+        let mut cells = Vec::new();
+        for y in ys.clone() {
+            for x in xs.clone().filter(|&x| touches(x, y)) {
+                if let Some(asset) = game.layer_asset(layer, x, y) {
+                    let z = game.layer_height(layer, x, y);
+                    cells.push(TileCell { x, y, z, asset, side: layer.side.as_deref() });
+                }
+            }
+        }
+        passes.push(cells);
+    }
+
+    for pass in &mut passes {
+        pass.sort_by_key(TileCell::depth);
+        for cell in pass.iter() {
+            canvas.render_column(cell, world, static_atlas);
+        }
+    }
+
+    let objects: Vec<&Object> = index
+        .object_cells
+        .window(xs, ys)
+        .into_iter()
+        .map(|position| &game.objects[&index.objects[position]])
+        .filter(|object| touches(object.x, object.y))
+        .collect();
+    // Raised tiles in front of the farthest object of the whole map may cover objects
+    let farthest = index.objects.first().map(|name| {
+        let object = &game.objects[name];
+        (object.x + object.y, object.z)
+    });
+
+    // First render all shadows using references
+    for object in &objects {
+        if let Some(frame) = static_atlas.get_frame(&object.asset) {
+            let (screen_x, screen_y) =
+                static_origin(world, object.x, object.y, object.z, frame, static_atlas);
+            canvas.render_shadow(&static_atlas.frame_pixels(frame), screen_x, screen_y);
+        }
+    }
+
+    // Apply blur once after all shadows are rendered
+    canvas.soft_blur_shadows(world);
+
+    let mut raised: Vec<TileCell> = passes
+        .iter()
+        .flatten()
+        .filter(|cell| cell.z > 0 && farthest.is_some_and(|far| cell.depth() > far))
+        .copied()
+        .collect();
+    raised.sort_by_key(TileCell::depth);
+    let mut raised = raised.into_iter().peekable();
+
+    // Then render all objects using references
+    for object in &objects {
+        while let Some(cell) =
+            raised.next_if(|cell| cell.depth() <= (object.x + object.y, object.z))
+        {
+            canvas.render_column(&cell, world, static_atlas);
+        }
+        if let Some(frame) = static_atlas.get_frame(&object.asset) {
+            let (screen_x, screen_y) =
+                static_origin(world, object.x, object.y, object.z, frame, static_atlas);
+            canvas.render_object(&static_atlas.frame_pixels(frame), screen_x, screen_y);
+        }
+    }
+    for cell in raised {
+        canvas.render_column(&cell, world, static_atlas);
+    }
+}
+
+impl Canvas<'_> {
+    /// Finds the cells whose anchors may lie within `reach` of the canvas
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - Size of the whole world buffer (width, height)
+    /// * `size` - Map dimensions [width, height]
+    /// * `ts` - Tile size of the static atlas
+    /// * `reach` - Distance of element pixels from their anchor
+    ///
+    /// # Returns
+    ///
+    /// * `(Range<u32>, Range<u32>)` - Column and row ranges of the bounding box.
+    fn cell_window(
+        &self,
+        world: (usize, usize),
+        size: [u32; 2],
+        ts: i32,
+        reach: i32,
+    ) -> (std::ops::Range<u32>, std::ops::Range<u32>) {
+        let (half, quarter) = (ts as i64 / 2, ts as i64 / 4);
+        if half == 0 || quarter == 0 {
+            return (0..size[0], 0..size[1]);
+        }
+
+        // Anchors grow by `half` per step of x - y and by `quarter` per step of x + y
+        let (origin_x, origin_y) = anchor(world, ts, 0, 0);
+        let left = (self.left - reach - origin_x) as i64;
+        let right = (self.left + self.width as i32 + reach - origin_x) as i64;
+        let top = (self.top - reach - origin_y) as i64;
+        let bottom = (self.top + self.height as i32 + reach - origin_y) as i64;
+        let (u0, u1) = (left.div_euclid(half) - 1, right.div_euclid(half) + 1);
+        let (v0, v1) = (top.div_euclid(quarter) - 1, bottom.div_euclid(quarter) + 1);
+
+        let clamp = |value: i64, max: u32| value.clamp(0, max as i64) as u32;
+        let xs = clamp((u0 + v0).div_euclid(2), size[0])..clamp((u1 + v1) / 2 + 1, size[0]);
+        let ys = clamp((v0 - u1).div_euclid(2), size[1])..clamp((v1 - u0) / 2 + 1, size[1]);
+        (xs, ys)
+    }
+
+    /// Renders a tile on top of its column of side frames
+    ///
+    /// # Arguments
+    ///
+    /// * `cell` - Tile cell to render
+    /// * `world` - Size of the whole world buffer (width, height)
+    /// * `static_atlas` - Sprite atlas for static map elements
+    fn render_column(&mut self, cell: &TileCell, world: (usize, usize), static_atlas: &Atlas) {
+        let Some(frame) = static_atlas.get_frame(cell.asset) else {
+            return;
+        };
+        let side = cell.side.and_then(|side| static_atlas.get_frame(side)).unwrap_or(frame);
+
+        for level in 0..cell.z {
+            let (screen_x, screen_y) =
+                static_origin(world, cell.x, cell.y, level, side, static_atlas);
+            self.render_tile(&static_atlas.frame_pixels(side), screen_x, screen_y);
+        }
+        let (screen_x, screen_y) =
+            static_origin(world, cell.x, cell.y, cell.z, frame, static_atlas);
+        self.render_tile(&static_atlas.frame_pixels(frame), screen_x, screen_y);
+    }
+
+    /// Copies visible runs of a decoded frame into the canvas
+    ///
+    /// # Arguments
+    ///
+    /// * `pixels` - Decoded sprite frame
+    /// * `screen_x` - X position in world buffer coordinates
+    /// * `screen_y` - Y position in world buffer coordinates
+    fn blit_static(&mut self, pixels: &FramePixels, screen_x: i32, screen_y: i32) {
+        let width = self.width as i32;
+        let screen_x = screen_x - self.left;
+        let screen_y = screen_y - self.top;
+
+        for dy in 0..pixels.h {
+            let dest_y = screen_y + dy as i32;
+            if dest_y < 0 || dest_y >= self.height as i32 {
+                continue;
+            }
+
+            let row = pixels.row(dy);
+            let dest_row = dest_y as usize * self.width;
+            for span in pixels.row_spans(dy) {
+                let start = (screen_x + span.start as i32).max(0);
+                let end = (screen_x + (span.start + span.len) as i32).min(width);
+                if start >= end {
+                    continue;
+                }
+
+                self.pixels[dest_row + start as usize..dest_row + end as usize]
+                    .copy_from_slice(&row[(start - screen_x) as usize..(end - screen_x) as usize]);
+            }
+        }
+    }
+
+    /// Renders a tile to the canvas
+    ///
+    /// # Arguments
+    ///
+    /// * `pixels` - Decoded sprite frame from the static atlas
+    /// * `screen_x` - X position in world buffer coordinates
+    /// * `screen_y` - Y position in world buffer coordinates
+    fn render_tile(&mut self, pixels: &FramePixels, screen_x: i32, screen_y: i32) {
+        self.blit_static(pixels, screen_x, screen_y);
+    }
+
+    /// Renders a static object to the canvas
+    ///
+    /// # Arguments
+    ///
+    /// * `pixels` - Decoded sprite frame from the static atlas
+    /// * `screen_x` - X position in world buffer coordinates
+    /// * `screen_y` - Y position in world buffer coordinates
+    fn render_object(&mut self, pixels: &FramePixels, screen_x: i32, screen_y: i32) {
+        self.blit_static(pixels, screen_x, screen_y);
+    }
+
+    /// Renders shadow for a static object
+    ///
+    /// # Arguments
+    ///
+    /// * `pixels` - Decoded sprite frame from the static atlas
+    /// * `screen_x` - X position in world buffer coordinates
+    /// * `screen_y` - Y position in world buffer coordinates
+    pub(super) fn render_shadow(&mut self, pixels: &FramePixels, screen_x: i32, screen_y: i32) {
+        let light_dir_x = 1.0;
+        let light_dir_y = 0.0;
+        let shadow_scale = 0.5;
+        let screen_x = screen_x - self.left;
+        let screen_y = screen_y - self.top;
+
+        for dy in 0..pixels.h {
+            let height_factor = (pixels.h as f32 - dy as f32) * shadow_scale;
+            let dest_y = screen_y + dy as i32 + (light_dir_y * height_factor) as i32;
+            if dest_y < 0 || dest_y >= self.height as i32 {
+                continue;
+            }
+            let shift_x = screen_x + (light_dir_x * height_factor) as i32;
+
+            for span in pixels.row_spans(dy) {
+                let start = (shift_x + span.start as i32).max(0);
+                let end = (shift_x + (span.start + span.len) as i32).min(self.width as i32);
+
+                for dest_x in start..end {
+                    let dest_index = (dest_y * self.width as i32 + dest_x) as usize;
+
+                    self.shadow[dest_index] = self.shadow[dest_index].saturating_add(8).min(48);
+
+                    let dst = self.pixels[dest_index];
+                    let shadow_strength = self.shadow[dest_index] as f32 / 255.0;
+                    let darken_factor = 1.0 - 0.4 * shadow_strength;
+
+                    let r = ((dst >> 16) & 0xFF) as f32 * darken_factor;
+                    let g = ((dst >> 8) & 0xFF) as f32 * darken_factor;
+                    let b = (dst & 0xFF) as f32 * darken_factor;
+
+                    self.pixels[dest_index] =
+                        (0xFF << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+                }
+            }
+        }
+    }
+
+    /// Soft blur for shadow areas only
+    ///
+    /// Pixels on the border of the world and of the canvas keep their values.
+    ///
+    /// # Arguments
+    ///
+    /// * `world` - Size of the whole world buffer (width, height)
+    pub(super) fn soft_blur_shadows(&mut self, world: (usize, usize)) {
+        let width = self.width as i32;
+        let height = self.height as i32;
+        let source = self.shadow.to_vec();
+
+        for y in 1..height - 1 {
+            let world_y = self.top + y;
+            if world_y < 1 || world_y >= world.1 as i32 - 1 {
+                continue;
+            }
+            for x in 1..width - 1 {
+                let world_x = self.left + x;
+                if world_x < 1 || world_x >= world.0 as i32 - 1 {
+                    continue;
+                }
+                let idx = (y * width + x) as usize;
+
+                if source[idx] > 0 {
+                    let mut sum = 0u32;
+                    for dy in -1..=1 {
+                        for dx in -1..=1 {
+                            sum += source[((y + dy) * width + x + dx) as usize] as u32;
+                        }
+                    }
+                    self.shadow[idx] = (sum / 9) as u8;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test that a window of cells yields its elements and the ones off the grid in bake order
+    #[test]
+    fn test_cell_index_window() {
+        let map: GameMap = r#"{
+            "meta": {"name": "m", "tile_size": 4, "size": [3, 3]},
+            "tiles": {"b": {"x": 1, "y": 1, "asset": "t"}, "a": {"x": 1, "y": 1, "asset": "t"},
+                      "c": {"x": 2, "y": 0, "asset": "t"}, "d": {"x": 0, "y": 2, "asset": "t"},
+                      "far": {"x": 7, "y": 0, "asset": "t"}},
+            "objects": {"tree": {"x": 2, "y": 2, "asset": "t"}, "rock": {"x": 0, "y": 0, "asset": "t"}},
+            "mobs": {}
+        }"#
+        .parse()
+        .unwrap();
+        let index = CellIndex::new(&map);
+        assert_eq!(index.tiles, ["a", "b", "c", "d", "far"]);
+        assert_eq!(index.objects, ["rock", "tree"]);
+
+        let names = |positions: Vec<usize>| -> Vec<&str> {
+            positions.into_iter().map(|position| index.tiles[position].as_str()).collect()
+        };
+        assert_eq!(names(index.tile_cells.window(1..3, 0..2)), ["a", "b", "c", "far"]);
+        assert_eq!(names(index.tile_cells.window(0..1, 2..3)), ["d", "far"]);
+        assert_eq!(names(index.tile_cells.window(0..0, 0..3)), ["far"]);
+        assert_eq!(index.object_cells.window(0..3, 0..3), [0, 1]);
+        assert_eq!(index.object_cells.window(1..3, 1..3), [1]);
+    }
+}
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::bake::{self, Canvas, CellIndex};
+use crate::assets::{Atlas, GameMap};
+
+/// Settings of the chunk cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions {
+    /// Side of a square chunk in world buffer pixels
+    pub chunk_size: usize,
+    /// Memory the baked chunks may hold, in bytes
+    pub budget: usize,
+    /// Colour of pixels not covered by the map
+    pub background: u32,
+}
+
+impl Default for ChunkOptions {
+    /// Creates options with 256 pixel chunks and a 64 MiB budget.
+    ///
+    /// # Returns
+    ///
+    /// A new `ChunkOptions` instance with default values.
+    fn default() -> Self {
+        Self { chunk_size: 256, budget: 64 << 20, background: 0 }
+    }
+}
+
+/// Baked pixels and static shadows of a world row run.
+pub(super) type StaticRun<'a> = (&'a [u32], &'a [u8]);
+
+/// Baked square of the static layer.
+struct Chunk {
+    /// Row-major pixels
+    pixels: Vec<u32>,
+    /// Row-major static shadow intensities
+    shadow: Vec<u8>,
+    /// Frame number of the last frame that showed the chunk
+    last_used: u64,
+}
+
+/// Map and atlas chunks are baked from.
+struct Scene {
+    game: Arc<GameMap>,
+    static_atlas: Arc<Atlas>,
+    /// Tiles and objects of `game` grouped by cell
+    index: CellIndex,
+    /// Result of `bake::reach` for the map
+    reach: i32,
+}
+
+/// Static layer split into square chunks that are baked on demand.
+///
+/// [`ChunkCache::prepare`] bakes the chunks a view needs. Once the baked
+/// chunks take more memory than the budget, the least recently shown ones
+/// are dropped and baked again when they come back into view. Chunks of the
+/// current view are never dropped, so a view bigger than the budget still
+/// renders whole.
+pub struct ChunkCache {
+    /// Cache settings
+    options: ChunkOptions,
+    /// Size of the whole world buffer (width, height)
+    world: (usize, usize),
+    /// Map and atlas set by `Render::init`
+    scene: Option<Scene>,
+    /// Baked chunks by (column, row)
+    chunks: HashMap<(usize, usize), Chunk>,
+    /// Number of prepared frames
+    frame: u64,
+    /// Number of chunks baked so far
+    bakes: u64,
+}
+
+impl ChunkCache {
+    /// Creates an empty cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Cache settings, a chunk size of zero is raised to one
+    /// * `world` - Size of the whole world buffer (width, height)
+    ///
+    /// # Returns
+    ///
+    /// A new `ChunkCache` instance with nothing baked.
+    pub(super) fn new(options: ChunkOptions, world: (usize, usize)) -> Self {
+        let options = ChunkOptions { chunk_size: options.chunk_size.max(1), ..options };
+        Self { options, world, scene: None, chunks: HashMap::new(), frame: 0, bakes: 0 }
+    }
+
+    /// Replaces the map and atlas and drops all baked chunks.
+    ///
+    /// # Arguments
+    ///
+    /// * `game` - The game map containing tiles and objects
+    /// * `static_atlas` - Sprite atlas for static map elements
+    pub(super) fn set_scene(&mut self, game: Arc<GameMap>, static_atlas: Arc<Atlas>) {
+        let reach = bake::reach(&game, &static_atlas);
+        let index = CellIndex::new(&game);
+        self.scene = Some(Scene { game, static_atlas, index, reach });
+        self.chunks.clear();
+    }
+
+    /// Sets the colour of pixels not covered by the map, for chunks baked from now on.
+    ///
+    /// # Arguments
+    ///
+    /// * `background` - ARGB colour
+    pub(super) fn set_background(&mut self, background: u32) {
+        self.options.background = background;
+    }
+
+    /// Bakes missing chunks of a view and evicts old chunks over the budget.
+    ///
+    /// Does nothing before a scene is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `left` - World buffer X of the leftmost view column
+    /// * `top` - World buffer Y of the top view row
+    /// * `right` - World buffer X after the rightmost view column
+    /// * `bottom` - World buffer Y after the bottom view row
+    pub(super) fn prepare(&mut self, left: i32, top: i32, right: i32, bottom: i32) {
+        if self.scene.is_none() {
+            return;
+        }
+        self.frame += 1;
+
+        let (width, height) = (self.world.0 as i32, self.world.1 as i32);
+        let (left, top) = (left.max(0), top.max(0));
+        let (right, bottom) = (right.min(width), bottom.min(height));
+        if left >= right || top >= bottom {
+            return;
+        }
+
+        let size = self.options.chunk_size as i32;
+        let (x0, x1) = ((left / size) as usize, ((right - 1) / size) as usize);
+        let (y0, y1) = ((top / size) as usize, ((bottom - 1) / size) as usize);
+        for cy in y0..=y1 {
+            for cx in x0..=x1 {
+                if !self.chunks.contains_key(&(cx, cy)) {
+                    let chunk = self.bake(cx, cy);
+                    self.chunks.insert((cx, cy), chunk);
+                }
+                if let Some(chunk) = self.chunks.get_mut(&(cx, cy)) {
+                    chunk.last_used = self.frame;
+                }
+            }
+        }
+        self.evict();
+    }
+
+    /// Bakes one chunk.
+    ///
+    /// The chunk is baked with a border of one pixel so that shadow blur
+    /// at its edges sees the neighbouring pixels.
+    ///
+    /// # Arguments
+    ///
+    /// * `cx` - Column of the chunk
+    /// * `cy` - Row of the chunk
+    ///
+    /// # Returns
+    ///
+    /// * `Chunk` - Baked chunk.
+    fn bake(&mut self, cx: usize, cy: usize) -> Chunk {
+        let size = self.options.chunk_size;
+        let padded = size + 2;
+        let mut pixels = vec![self.options.background; padded * padded];
+        let mut shadow = vec![0; padded * padded];
+
+        if let Some(scene) = &self.scene {
+            let mut canvas = Canvas {
+                pixels: &mut pixels,
+                shadow: &mut shadow,
+                left: (cx * size) as i32 - 1,
+                top: (cy * size) as i32 - 1,
+                width: padded,
+                height: padded,
+            };
+            bake::bake(
+                &mut canvas,
+                self.world,
+                &scene.game,
+                &scene.index,
+                &scene.static_atlas,
+                Some(scene.reach),
+            );
+        }
+        self.bakes += 1;
+
+        Chunk { pixels: crop(&pixels, size), shadow: crop(&shadow, size), last_used: self.frame }
+    }
+
+    /// Drops least recently shown chunks until the cache fits the budget.
+    fn evict(&mut self) {
+        while self.memory() > self.options.budget {
+            let oldest = self
+                .chunks
+                .iter()
+                .filter(|(_, chunk)| chunk.last_used < self.frame)
+                .min_by_key(|(key, chunk)| (chunk.last_used, **key))
+                .map(|(key, _)| *key);
+            match oldest {
+                Some(key) => self.chunks.remove(&key),
+                None => break,
+            };
+        }
+    }
+
+    /// Finds the baked static pixels of a world row run.
+    ///
+    /// # Arguments
+    ///
+    /// * `world_x` - X of the first pixel, inside the world
+    /// * `world_y` - Y of the row, inside the world
+    /// * `len` - Wanted number of pixels, at least one
+    ///
+    /// # Returns
+    ///
+    /// * `(usize, Option<StaticRun>)` - Length of the run inside one chunk, at most `len`,
+    ///   with its pixels and static shadows, None if the chunk is not baked.
+    pub(super) fn span(
+        &self,
+        world_x: usize,
+        world_y: usize,
+        len: usize,
+    ) -> (usize, Option<StaticRun<'_>>) {
+        let size = self.options.chunk_size;
+        let (local_x, local_y) = (world_x % size, world_y % size);
+        let len = len.min(size - local_x).min(self.world.0 - world_x);
+
+        let chunk = self.chunks.get(&(world_x / size, world_y / size));
+        let baked = chunk.map(|chunk| {
+            let start = local_y * size + local_x;
+            (&chunk.pixels[start..start + len], &chunk.shadow[start..start + len])
+        });
+        (len, baked)
+    }
+
+    /// Returns the cache settings.
+    ///
+    /// # Returns
+    ///
+    /// * `ChunkOptions` - Settings with the current background.
+    pub fn options(&self) -> ChunkOptions {
+        self.options
+    }
+
+    /// Returns the number of baked chunks held in memory.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - Resident chunk count.
+    pub fn resident(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns the memory held by baked chunks.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - Size of chunk pixels and shadows in bytes.
+    pub fn memory(&self) -> usize {
+        let per_chunk = self.options.chunk_size * self.options.chunk_size * 5;
+        self.chunks.len() * per_chunk
+    }
+
+    /// Returns the number of chunks baked so far, including ones baked again after eviction.
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - Bake count.
+    pub fn bakes(&self) -> u64 {
+        self.bakes
+    }
+
+    /// Drops all baked chunks, they are baked again when shown.
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+}
+
+/// Cuts the one pixel border off a baked chunk buffer
+///
+/// # Arguments
+///
+/// * `padded` - Row-major buffer of `size + 2` pixels squared
+/// * `size` - Side of the chunk
+///
+/// # Returns
+///
+/// * `Vec<T>` - Row-major buffer of `size` pixels squared.
+fn crop<T: Copy>(padded: &[T], size: usize) -> Vec<T> {
+    padded.chunks(size + 2).skip(1).take(size).flat_map(|row| &row[1..=size]).copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::procgen::{generate_dungeon, DungeonOptions};
+    use crate::render::Render;
+    use crate::world::Camera;
+    use std::path::PathBuf;
+
+    const BACKGROUND: u32 = 0xFF102030;
+
+    fn atlases() -> (Arc<Atlas>, Atlas) {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let tiles_atlas = Atlas::load(project_root.join("assets/tiles/atlas.json")).unwrap();
+        let entities_atlas = Atlas::load(project_root.join("assets/entities/atlas.json")).unwrap();
+        (Arc::new(tiles_atlas), entities_atlas)
+    }
+
+    fn frame(render: &mut Render, camera: &Camera) -> Vec<u32> {
+        let mut buf = vec![0; camera.width as usize * camera.height as usize];
+        render.render_frame(&[], camera, &mut buf);
+        buf
+    }
+
+    // Test that chunks baked one by one give the same frames and shadows as a whole-world bake
+    #[test]
+    fn test_chunks_match_full_bake() {
+        let (tiles_atlas, entities_atlas) = atlases();
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let maps = [
+            Arc::new(GameMap::load(project_root.join("examples/input.json")).unwrap()),
+            Arc::new(GameMap::load(project_root.join("examples/layered.json")).unwrap()),
+            Arc::new(generate_dungeon([40, 32], 3, &DungeonOptions::default())),
+        ];
+
+        for game in &maps {
+            let width = game.size[0] as usize * 16 * 2;
+            let height = game.size[1] as usize * 16 * 2;
+            let mut full = Render::new(
+                vec![BACKGROUND; width * height],
+                height,
+                width,
+                entities_atlas.clone(),
+                vec![0; width * height],
+            );
+            full.init(Arc::clone(game), Arc::clone(&tiles_atlas));
+            let options =
+                ChunkOptions { chunk_size: 100, budget: usize::MAX, background: BACKGROUND };
+            let mut chunked = Render::new_chunked(height, width, entities_atlas.clone(), options);
+            chunked.init(Arc::clone(game), Arc::clone(&tiles_atlas));
+            assert!(chunked.world_buf.is_empty() && chunked.shadow_map.is_empty());
+
+            let part = Camera::new(width as f32 / 3.0, height as f32 / 2.0, 210, 190);
+            assert_eq!(frame(&mut chunked, &part), frame(&mut full, &part), "{}", game.name);
+            let resident = chunked.chunks.as_ref().unwrap().resident();
+            assert!((4..=9).contains(&resident), "{resident}");
+
+            let whole =
+                Camera::new(width as f32 / 2.0, height as f32 / 2.0, width as u16, height as u16);
+            assert_eq!(frame(&mut chunked, &whole), frame(&mut full, &whole), "{}", game.name);
+            for (y, x) in (0..height as i32).flat_map(|y| (0..width as i32).map(move |x| (y, x))) {
+                assert_eq!(chunked.get_shadow_intensity(x, y), full.get_shadow_intensity(x, y));
+            }
+        }
+    }
+
+    // Test that chunks over the budget are evicted least recently used first and baked again
+    #[test]
+    fn test_chunk_budget() {
+        let (tiles_atlas, entities_atlas) = atlases();
+        let game = Arc::new(generate_dungeon([40, 40], 5, &DungeonOptions::default()));
+        let size = 40 * 16 * 2;
+        let options =
+            ChunkOptions { chunk_size: 64, budget: 6 * 64 * 64 * 5, background: BACKGROUND };
+        let mut render = Render::new_chunked(size, size, entities_atlas, options);
+
+        let camera = |x: f32| Camera::new(x, 640.0, 60, 60);
+        assert_eq!(frame(&mut render, &camera(300.0)), vec![0; 60 * 60]);
+        render.init(Arc::clone(&game), Arc::clone(&tiles_atlas));
+        let start = frame(&mut render, &camera(300.0));
+        assert!(!start.contains(&0));
+
+        let chunks = render.chunks.as_ref().unwrap();
+        let first_bakes = chunks.bakes();
+        assert_eq!(chunks.resident() as u64, first_bakes);
+        assert!(first_bakes <= 4);
+
+        for step in 1..10 {
+            frame(&mut render, &camera(300.0 + step as f32 * 80.0));
+            let chunks = render.chunks.as_ref().unwrap();
+            assert!(chunks.memory() <= chunks.options().budget);
+        }
+        let bakes = render.chunks.as_ref().unwrap().bakes();
+        assert_eq!(frame(&mut render, &camera(300.0)), start);
+        assert_eq!(render.chunks.as_ref().unwrap().bakes(), bakes + first_bakes);
+
+        // A view needing more chunks than the budget keeps all of them for the frame
+        let wide = Camera::new(640.0, 640.0, 400, 400);
+        let mut full = Render::new(
+            vec![BACKGROUND; size * size],
+            size,
+            size,
+            render.entity_atlas.clone(),
+            vec![0; size * size],
+        );
+        full.init(Arc::clone(&game), Arc::clone(&tiles_atlas));
+        assert_eq!(frame(&mut render, &wide), frame(&mut full, &wide));
+        render.rebake(Arc::clone(&game), Arc::clone(&tiles_atlas), BACKGROUND);
+        assert_eq!(render.chunks.as_ref().unwrap().resident(), 0);
+    }
+}
@@ -1,4 +1,7 @@
+mod bake;
+mod chunks;
 #[allow(clippy::module_inception)]
 mod render;
+pub use chunks::{ChunkCache, ChunkOptions};
 pub use render::Render;
 pub use render::RenderableEntity;
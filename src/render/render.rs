@@ -1,4 +1,8 @@
-use crate::assets::{Atlas, Frame, GameMap, Object, Tile};
+use std::sync::Arc;
+
+use super::bake::{self, Canvas, CellIndex};
+use super::chunks::{ChunkCache, ChunkOptions, StaticRun};
+use crate::assets::{Animator, Atlas, Frame, FramePixels, GameMap, SpriteId};
 use crate::world::Camera;
 
 /// Represents an entity that can be rendered
@@ -7,16 +11,72 @@ pub struct RenderableEntity {
     pub x: f32,
     pub y: f32,
     pub sprite_name: String,
+    /// Sprite resolved with [`Atlas::sprite_id`], takes precedence over `sprite_name`
+    pub sprite: Option<SpriteId>,
 }
 
 impl RenderableEntity {
     pub fn new(x: f32, y: f32, sprite_name: String) -> Self {
-        Self { x, y, sprite_name }
+        Self { x, y, sprite_name, sprite: None }
     }
 
     pub fn with_sprite(x: f32, y: f32, sprite_name: &str) -> Self {
         Self::new(x, y, sprite_name.to_string())
     }
+
+    /// Creates an entity from a resolved sprite id
+    ///
+    /// Faster than entities created by name, which look their frame up by
+    /// name every frame. Resolve ids once with [`Atlas::sprite_id`].
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X position of the entity
+    /// * `y` - Y position of the entity
+    /// * `sprite` - Sprite id issued by the entity atlas
+    pub fn from_id(x: f32, y: f32, sprite: SpriteId) -> Self {
+        Self { x, y, sprite_name: String::new(), sprite: Some(sprite) }
+    }
+
+    /// Creates an entity showing the current frame of an animation
+    ///
+    /// The frame is resolved once, create entities again after the animator advances.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X position of the entity
+    /// * `y` - Y position of the entity
+    /// * `animator` - Clip and playback position
+    /// * `atlas` - Sprite atlas for entities
+    pub fn with_animation(x: f32, y: f32, animator: &Animator, atlas: &Atlas) -> Self {
+        Self { x, y, sprite_name: String::new(), sprite: animator.current_sprite(atlas) }
+    }
+
+    /// Resolves the sprite to draw for this entity
+    ///
+    /// # Arguments
+    ///
+    /// * `atlas` - Sprite atlas for entities
+    ///
+    /// # Returns
+    ///
+    /// * `Option<SpriteId>` - The stored sprite or the named one, None if not found.
+    pub fn sprite_id(&self, atlas: &Atlas) -> Option<SpriteId> {
+        self.sprite.or_else(|| atlas.sprite_id(&self.sprite_name))
+    }
+
+    /// Resolves the frame to draw for this entity
+    ///
+    /// # Arguments
+    ///
+    /// * `atlas` - Sprite atlas for entities
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Frame>` - The stored sprite or the named one, None if not found.
+    pub fn frame<'a>(&self, atlas: &'a Atlas) -> Option<&'a Frame> {
+        atlas.sprite(self.sprite_id(atlas)?)
+    }
 }
 
 /// The `Render` struct handles isometric projection rendering with shadow mapping
@@ -29,12 +89,14 @@ pub struct Render {
     pub world_height: usize,
     /// Width of the world buffer in pixels
     pub world_width: usize,
-    /// Primary world pixel buffer storing rendered entities
+    /// Primary world pixel buffer storing rendered entities, empty when chunked
     pub world_buf: Vec<u32>,
-    /// Shadow intensity map for shadow calculations
+    /// Shadow intensity map for shadow calculations, empty when chunked
     pub shadow_map: Vec<u8>,
-    /// Temporary shadow buffer for dynamic objects in current frame
+    /// Temporary shadow buffer for dynamic objects in current frame, one value per view pixel
     pub dynamic_shadow_buf: Vec<u8>,
+    /// Chunks of the static layer baked around the camera, None if `world_buf` holds the whole world
+    pub chunks: Option<ChunkCache>,
 }
 
 impl Render {
@@ -64,83 +126,98 @@ impl Render {
             shadow_map: shadow,
             world_height: height,
             world_width: width,
-            dynamic_shadow_buf: vec![0; height * width],
+            dynamic_shadow_buf: Vec::new(),
+            chunks: None,
+        }
+    }
+
+    /// Creates a Render instance that bakes the world in chunks around the camera.
+    ///
+    /// No world-sized buffers are allocated: `world_buf` and `shadow_map` stay
+    /// empty, [`Render::init`] keeps the map and every frame bakes the chunks
+    /// under the camera, within the memory budget of `options`.
+    ///
+    /// # Arguments
+    ///
+    /// * `height` - Height of the world in pixels
+    /// * `width` - Width of the world in pixels
+    /// * `entity_atlas` - Sprite atlas for entities
+    /// * `options` - Chunk size, memory budget and background colour
+    ///
+    /// # Returns
+    ///
+    /// A new `Render` instance with an empty chunk cache.
+    pub fn new_chunked(
+        height: usize,
+        width: usize,
+        entity_atlas: Atlas,
+        options: ChunkOptions,
+    ) -> Self {
+        Self {
+            chunks: Some(ChunkCache::new(options, (width, height))),
+            ..Self::new(Vec::new(), height, width, entity_atlas, Vec::new())
         }
     }
 
     /// Initializes the world buffer by rendering static map elements
     ///
     /// Renders tiles and objects from the game map building isometric projection.
-    /// Sorts elements by their (x+y) coordinate for depth ordering.
+    /// Named tiles are drawn first, then every tile layer in order.
+    /// Sorts elements by their (x+y) coordinate and then by elevation for depth ordering.
+    /// Raised tiles stand on columns of side frames, and are drawn again over
+    /// objects behind them. A chunked renderer holds on to the shared map and
+    /// atlas and bakes chunks when they come into view.
     ///
     /// # Arguments
     ///
     /// * `game` - The game map containing tiles and objects
     /// * `static_atlas` - Sprite atlas for static map elements
-    pub fn init(&mut self, game: &GameMap, static_atlas: &Atlas) {
-        let mut tiles: Vec<Tile> = (*game).clone().tiles.into_values().collect();
-        tiles.sort_by(|a, b| (a.x + a.y).cmp(&(b.x + b.y)));
-        // Base screen offsets
-        let offset_x = self.world_width as i32 / 2;
-        let offset_y = (self.world_height as i32 / 2) - (static_atlas.tile_size as i32);
-
-        for tile in tiles {
-            if let Some(frame) = static_atlas.get_frame(&tile.asset) {
-                let fw = frame.w as i32;
-                let fh = frame.h as i32;
-
-                // Isometric projection
-                let screen_x = (tile.x as i32 - tile.y as i32) * (fw / 2) + offset_x;
-                let screen_y = (tile.x as i32 + tile.y as i32) * (fh / 4) + offset_y - (fh / 2);
-
-                self.render_tile(frame, screen_x, screen_y, static_atlas);
-            }
+    pub fn init(&mut self, game: Arc<GameMap>, static_atlas: Arc<Atlas>) {
+        if let Some(chunks) = &mut self.chunks {
+            chunks.set_scene(game, static_atlas);
+            return;
         }
+        let world = (self.world_width, self.world_height);
+        let index = CellIndex::new(&game);
+        bake::bake(&mut self.full_canvas(), world, &game, &index, &static_atlas, None);
+    }
 
-        let mut objects: Vec<Object> = (*game).clone().objects.into_values().collect();
-        objects.sort_by(|a, b| (a.x + a.y).cmp(&(b.x + b.y)));
-        let offset_x = self.world_width as i32 / 2;
-        let offset_y = (self.world_height as i32 / 2) - (static_atlas.tile_size as i32);
-
-        const TEXTURE_OFFSET: i32 = 0; // TODO: CURRENTLY DEPENDS ON TEXTURES
-
-        // First render all shadows using references
-        for object in &objects {
-            if let Some(frame) = static_atlas.get_frame(&object.asset) {
-                let fw = frame.w as i32;
-                let fh = frame.h as i32;
-
-                // Isometric projection
-                let screen_x = (object.x as i32 - object.y as i32) * (fw / 2) + offset_x;
-                let screen_y = (object.x as i32 + object.y as i32) * (fh / 4) + offset_y
-                    - (fh / 2)
-                    - TEXTURE_OFFSET;
-                self.render_shadow(frame, screen_x, screen_y, static_atlas);
-            }
+    /// Returns the whole world buffer and shadow map as a canvas to bake into
+    fn full_canvas(&mut self) -> Canvas<'_> {
+        Canvas {
+            pixels: &mut self.world_buf,
+            shadow: &mut self.shadow_map,
+            left: 0,
+            top: 0,
+            width: self.world_width,
+            height: self.world_height,
         }
+    }
 
-        // Apply blur once after all shadows are rendered
-        self.soft_blur_shadows();
-
-        // Then render all objects using references
-        for object in &objects {
-            if let Some(frame) = static_atlas.get_frame(&object.asset) {
-                let fw = frame.w as i32;
-                let fh = frame.h as i32;
-
-                let screen_x = (object.x as i32 - object.y as i32) * (fw / 2) + offset_x;
-                let screen_y = (object.x as i32 + object.y as i32) * (fh / 4) + offset_y
-                    - (fh / 2)
-                    - TEXTURE_OFFSET;
-                self.render_object(frame, screen_x, screen_y, static_atlas);
-            }
+    /// Clears the baked map and shadows and bakes them again
+    ///
+    /// The world buffer keeps its size, parts of a grown map outside of it are clipped.
+    /// A chunked renderer drops its chunks and bakes them again when shown.
+    ///
+    /// # Arguments
+    ///
+    /// * `game` - The game map containing tiles and objects
+    /// * `static_atlas` - Sprite atlas for static map elements
+    /// * `background` - Colour of world buffer pixels not covered by the map
+    pub fn rebake(&mut self, game: Arc<GameMap>, static_atlas: Arc<Atlas>, background: u32) {
+        if let Some(chunks) = &mut self.chunks {
+            chunks.set_background(background);
         }
+        self.world_buf.fill(background);
+        self.shadow_map.fill(0);
+        self.init(game, static_atlas);
     }
 
     /// Renders a complete frame
     ///
     /// Combines the pre-rendered world buffer with dynamic entities,
     /// applying camera transformation and time-based animations.
+    /// A chunked renderer first bakes the chunks under the camera.
     ///
     /// # Arguments
     ///
@@ -182,6 +259,10 @@ impl Render {
             "Buffer size must match camera viewport"
         );
 
+        if let Some(chunks) = &mut self.chunks {
+            chunks.prepare(cam_left, cam_top, cam_right, cam_bottom);
+        }
+
         // Copy visible world
         for y in 0..view_h {
             let world_y = cam_top + y as i32;
@@ -189,15 +270,15 @@ impl Render {
                 continue;
             }
 
-            for x in 0..view_w {
-                let world_x = cam_left + x as i32;
-                if world_x < 0 || world_x >= world_w {
-                    continue;
+            let mut x = (-cam_left).max(0) as usize;
+            let end = view_w.min((world_w - cam_left).max(0) as usize);
+            while x < end {
+                let (len, baked) = self.static_span(cam_left + x as i32, world_y, end - x);
+                if let Some((pixels, _)) = baked {
+                    let buf_idx = y * camera.width as usize + x;
+                    buf[buf_idx..buf_idx + len].copy_from_slice(pixels);
                 }
-
-                let world_idx = (world_y * world_w + world_x) as usize;
-                let buf_idx = y * camera.width as usize + x;
-                buf[buf_idx] = self.world_buf[world_idx];
+                x += len;
             }
         }
 
@@ -217,271 +298,169 @@ impl Render {
         });
 
         // Reset the shadow temporary buffer
-        self.dynamic_shadow_buf.fill(0);
+        self.dynamic_shadow_buf.clear();
+        self.dynamic_shadow_buf.resize(buf.len(), 0);
 
         // Collect all shadow rendering data first
         let mut shadow_render_data = Vec::new();
         for entity in sorted_entities.iter() {
-            if let Some(frame) = self.entity_atlas.get_frame(&entity.sprite_name) {
-                let fw = frame.w as i32;
-                let fh = frame.h as i32;
-
-                let screen_x =
-                    (entity.x as i32 - camera.center_x as i32) + camera.width as i32 / 2 - fw / 2;
-                let screen_y =
-                    (entity.y as i32 - camera.center_y as i32) + camera.height as i32 / 2 - fh;
-
-                shadow_render_data.push((frame.clone(), screen_x, screen_y));
+            let Some(sprite) = entity.sprite_id(&self.entity_atlas) else {
+                continue;
+            };
+            if let (Some(frame), Some(pixels)) =
+                (self.entity_atlas.sprite(sprite), self.entity_atlas.sprite_pixels(sprite))
+            {
+                let anchor_x = (entity.x as i32 - camera.center_x as i32) + camera.width as i32 / 2;
+                let anchor_y =
+                    (entity.y as i32 - camera.center_y as i32) + camera.height as i32 / 2;
+
+                // Entities stand on their bottom-centre unless the frame has a pivot
+                let (source_w, source_h) = frame.untrimmed_size();
+                let default_pivot = (source_w as i32 / 2, source_h as i32);
+                let (screen_x, screen_y) = frame.origin(anchor_x, anchor_y, default_pivot);
+
+                shadow_render_data.push((Arc::clone(pixels), screen_x, screen_y));
             }
         }
 
         // Render shadows
-        for (frame, screen_x, screen_y) in &shadow_render_data {
-            self.render_shadow_unit(frame, *screen_x, *screen_y, buf, camera);
+        for (pixels, screen_x, screen_y) in &shadow_render_data {
+            self.render_shadow_unit(pixels, *screen_x, *screen_y, buf, camera);
         }
 
         // Then render all objects
-        for (frame, screen_x, screen_y) in &shadow_render_data {
-            self.render_unit(frame, *screen_x, *screen_y, buf, camera);
+        for (pixels, screen_x, screen_y) in &shadow_render_data {
+            self.render_unit(pixels, *screen_x, *screen_y, buf, camera);
         }
     }
 
     /// Gets shadow intensity at world coordinates
+    ///
+    /// A chunked renderer reads the chunks baked for recent frames, other
+    /// parts of the world count as unshadowed.
     pub fn get_shadow_intensity(&self, world_x: i32, world_y: i32) -> f32 {
+        self.static_shadow(world_x, world_y) as f32 / 255.0
+    }
+
+    /// Reads the static shadow at world coordinates, 0 outside the world or baked chunks
+    fn static_shadow(&self, world_x: i32, world_y: i32) -> u8 {
         if world_x >= 0
             && world_y >= 0
             && world_x < self.world_width as i32
             && world_y < self.world_height as i32
         {
-            let shadow_idx = (world_y * self.world_width as i32 + world_x) as usize;
-            self.shadow_map[shadow_idx] as f32 / 255.0
+            match self.static_span(world_x, world_y, 1) {
+                (_, Some((_, shadow))) => shadow[0],
+                (_, None) => 0,
+            }
         } else {
-            0.0
+            0
         }
     }
 
-    /// Renders a unit to the world buffer
+    /// Finds the baked static pixels of a world row run
     ///
     /// # Arguments
     ///
-    /// * `frame` - Sprite frame to render from the entity atlas
-    /// * `screen_x` - X position in screen coordinates (output buffer space)
-    /// * `screen_y` - Y position in screen coordinates (output buffer space)  
-    /// * `buf` - Output pixel buffer to render into
-    /// * `camera` - Camera configuration defining viewport and position
-    fn render_unit(
+    /// * `world_x` - X of the first pixel, inside the world
+    /// * `world_y` - Y of the row, inside the world
+    /// * `len` - Wanted number of pixels, at least one
+    ///
+    /// # Returns
+    ///
+    /// * `(usize, Option<StaticRun>)` - Length of the run held by one buffer, at most `len`,
+    ///   with its pixels and static shadows, None if its chunk is not baked.
+    fn static_span(
         &self,
-        frame: &Frame,
-        screen_x: i32,
-        screen_y: i32,
-        buf: &mut [u32],
-        camera: &Camera,
-    ) {
-        let (atlas_w, atlas_h) = self.entity_atlas.image.dimensions();
-
-        for dy in 0..frame.h as i32 {
-            for dx in 0..frame.w as i32 {
-                let src_x = frame.x as i32 + dx;
-                let src_y = frame.y as i32 + dy;
-
-                if src_x < 0 || src_y < 0 || src_x >= atlas_w as i32 || src_y >= atlas_h as i32 {
-                    continue;
-                }
-
-                let color = self.entity_atlas.image.get_pixel(src_x as u32, src_y as u32);
-                let src_a = color[3] as u32;
-                if src_a == 0 {
-                    continue;
-                }
-
-                let dest_x = screen_x + dx;
-                let dest_y = screen_y + dy;
-
-                if dest_x < 0
-                    || dest_y < 0
-                    || dest_x >= camera.width as i32
-                    || dest_y >= camera.height as i32
-                {
-                    continue;
-                }
-
-                let dest_idx = (dest_y * camera.width as i32 + dest_x) as usize;
-
-                let world_x = (camera.center_x as i32 - camera.width as i32 / 2) + dest_x;
-                let world_y = (camera.center_y as i32 - camera.height as i32 / 2) + dest_y;
-
-                let shadow_intensity = self.get_shadow_intensity(world_x, world_y);
-                let brightness = 1.0 - 0.6 * shadow_intensity;
-
-                let [r, g, b, _] = color.0;
-                let src_r = (r as f32 * brightness) as u32;
-                let src_g = (g as f32 * brightness) as u32;
-                let src_b = (b as f32 * brightness) as u32;
-
-                let dst_pixel = buf[dest_idx];
-
-                let dst_r = (dst_pixel >> 16) & 0xFF;
-                let dst_g = (dst_pixel >> 8) & 0xFF;
-                let dst_b = dst_pixel & 0xFF;
-
-                let alpha_factor = src_a as f32 / 255.0;
-                let inv_alpha_factor = 1.0 - alpha_factor;
-
-                let new_r = (src_r as f32 * alpha_factor + dst_r as f32 * inv_alpha_factor)
-                    .min(255.0) as u32;
-                let new_g = (src_g as f32 * alpha_factor + dst_g as f32 * inv_alpha_factor)
-                    .min(255.0) as u32;
-                let new_b = (src_b as f32 * alpha_factor + dst_b as f32 * inv_alpha_factor)
-                    .min(255.0) as u32;
-
-                buf[dest_idx] = (0xFF << 24) | (new_r << 16) | (new_g << 8) | new_b;
-            }
+        world_x: i32,
+        world_y: i32,
+        len: usize,
+    ) -> (usize, Option<StaticRun<'_>>) {
+        let (world_x, world_y) = (world_x as usize, world_y as usize);
+        if let Some(chunks) = &self.chunks {
+            return chunks.span(world_x, world_y, len);
         }
+
+        let len = len.min(self.world_width - world_x);
+        let start = world_y * self.world_width + world_x;
+        let end = start + len;
+        (len, Some((&self.world_buf[start..end], &self.shadow_map[start..end])))
     }
 
-    /// Renders a tile to the world buffer
+    /// Checks if a world row has no static shadow in the given column range
     ///
-    /// # Arguments
-    ///
-    /// * `frame` - Sprite frame to render from the entity atlas
-    /// * `screen_x` - X position in screen coordinates (output buffer space)
-    /// * `screen_y` - Y position in screen coordinates (output buffer space)  
-    /// * `atlas` - Sprite atlas for map elements
-    fn render_tile(&mut self, frame: &Frame, screen_x: i32, screen_y: i32, atlas: &Atlas) {
-        let (atlas_w, atlas_h) = atlas.image.dimensions();
-
-        for dy in 0..frame.h as i32 {
-            for dx in 0..frame.w as i32 {
-                let src_x = frame.x as i32 + dx;
-                let src_y = frame.y as i32 + dy;
-
-                if src_x < 0 || src_y < 0 || src_x >= atlas_w as i32 || src_y >= atlas_h as i32 {
-                    continue;
-                }
-
-                let color = atlas.image.get_pixel(src_x as u32, src_y as u32);
-                if color[3] == 0 {
-                    continue;
-                }
-
-                let dest_x = screen_x + dx;
-                let dest_y = screen_y + dy;
-
-                if dest_x < 0
-                    || dest_y < 0
-                    || dest_x >= self.world_width as i32
-                    || dest_y >= self.world_height as i32
-                {
-                    continue;
-                }
+    /// Columns outside the world count as unshadowed.
+    fn shadow_free(&self, world_x0: i32, world_x1: i32, world_y: i32) -> bool {
+        if world_y < 0 || world_y >= self.world_height as i32 {
+            return true;
+        }
+        let mut x = world_x0.clamp(0, self.world_width as i32);
+        let x1 = world_x1.clamp(0, self.world_width as i32);
 
-                let dest_index = (dest_y * self.world_width as i32 + dest_x) as usize;
-                let [r, g, b, a] = color.0;
-                self.world_buf[dest_index] =
-                    ((a as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+        while x < x1 {
+            let (len, baked) = self.static_span(x, world_y, (x1 - x) as usize);
+            if baked.is_some_and(|(_, shadow)| shadow.iter().any(|&v| v != 0)) {
+                return false;
             }
+            x += len as i32;
         }
+        true
     }
 
-    /// Renders a static object to the world buffer
+    /// Renders a unit to the world buffer
+    ///
+    /// Opaque runs outside static shadows are copied as is, other pixels are
+    /// darkened by the shadow and blended over the buffer.
     ///
     /// # Arguments
     ///
-    /// * `frame` - Sprite frame to render from the entity atlas
+    /// * `pixels` - Decoded sprite frame from the entity atlas
     /// * `screen_x` - X position in screen coordinates (output buffer space)
     /// * `screen_y` - Y position in screen coordinates (output buffer space)  
-    /// * `atlas` - Sprite atlas for map elements
-    fn render_object(&mut self, frame: &Frame, screen_x: i32, screen_y: i32, atlas: &Atlas) {
-        let (atlas_w, atlas_h) = atlas.image.dimensions();
-
-        for dy in 0..frame.h as i32 {
-            for dx in 0..frame.w as i32 {
-                let src_x = frame.x as i32 + dx;
-                let src_y = frame.y as i32 + dy;
-
-                if src_x < 0 || src_y < 0 || src_x >= atlas_w as i32 || src_y >= atlas_h as i32 {
-                    continue;
-                }
-
-                let color = atlas.image.get_pixel(src_x as u32, src_y as u32);
-                if color[3] == 0 {
-                    continue;
-                }
-
-                let dest_x = screen_x + dx;
-                let dest_y = screen_y + dy;
-
-                if dest_x < 0
-                    || dest_y < 0
-                    || dest_x >= self.world_width as i32
-                    || dest_y >= self.world_height as i32
-                {
-                    continue;
-                }
-
-                let dest_index = (dest_y * self.world_width as i32 + dest_x) as usize;
-                let [r, g, b, a] = color.0;
-                self.world_buf[dest_index] =
-                    ((a as u32) << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+    /// * `buf` - Output pixel buffer to render into
+    /// * `camera` - Camera configuration defining viewport and position
+    fn render_unit(
+        &self,
+        pixels: &FramePixels,
+        screen_x: i32,
+        screen_y: i32,
+        buf: &mut [u32],
+        camera: &Camera,
+    ) {
+        let cam_w = camera.width as i32;
+        let cam_h = camera.height as i32;
+        let world_left = camera.center_x as i32 - cam_w / 2;
+        let world_top = camera.center_y as i32 - cam_h / 2;
+
+        for dy in 0..pixels.h {
+            let dest_y = screen_y + dy as i32;
+            if dest_y < 0 || dest_y >= cam_h {
+                continue;
             }
-        }
-    }
 
-    /// Renders shadow for a static object
-    ///
-    /// # Arguments
-    ///
-    /// * `frame` - Sprite frame to render from the entity atlas
-    /// * `screen_x` - X position in screen coordinates (output buffer space)
-    /// * `screen_y` - Y position in screen coordinates (output buffer space)
-    /// * `atlas` - Sprite atlas for map elements
-    fn render_shadow(&mut self, frame: &Frame, screen_x: i32, screen_y: i32, atlas: &Atlas) {
-        let (atlas_w, atlas_h) = atlas.image.dimensions();
-        let light_dir_x = 1.0;
-        let light_dir_y = 0.0;
-        let shadow_scale = 0.5;
-
-        for dy in 0..frame.h as i32 {
-            for dx in 0..frame.w as i32 {
-                let src_x = frame.x as i32 + dx;
-                let src_y = frame.y as i32 + dy;
+            let row = pixels.row(dy);
+            let buf_row = &mut buf[(dest_y * cam_w) as usize..][..cam_w as usize];
+            let world_y = world_top + dest_y;
 
-                if src_x < 0 || src_y < 0 || src_x >= atlas_w as i32 || src_y >= atlas_h as i32 {
+            for span in pixels.row_spans(dy) {
+                let start = (screen_x + span.start as i32).max(0);
+                let end = (screen_x + (span.start + span.len) as i32).min(cam_w);
+                if start >= end {
                     continue;
                 }
+                let src = &row[(start - screen_x) as usize..(end - screen_x) as usize];
+                let dst = &mut buf_row[start as usize..end as usize];
 
-                let color = atlas.image.get_pixel(src_x as u32, src_y as u32);
-                if color[3] == 0 {
+                if span.opaque && self.shadow_free(world_left + start, world_left + end, world_y) {
+                    dst.copy_from_slice(src);
                     continue;
                 }
 
-                let height_factor = (frame.h as f32 - dy as f32) * shadow_scale;
-
-                let dest_x = screen_x + dx + (light_dir_x * height_factor) as i32;
-                let dest_y = screen_y + dy + (light_dir_y * height_factor) as i32;
-
-                if dest_x < 0
-                    || dest_y < 0
-                    || dest_x >= self.world_width as i32
-                    || dest_y >= self.world_height as i32
-                {
-                    continue;
+                for (i, (dst, &color)) in dst.iter_mut().zip(src).enumerate() {
+                    let world_x = world_left + start + i as i32;
+                    let brightness = 1.0 - 0.6 * self.get_shadow_intensity(world_x, world_y);
+                    *dst = blend_pixel(color, *dst, brightness);
                 }
-
-                let dest_index = (dest_y * self.world_width as i32 + dest_x) as usize;
-
-                self.shadow_map[dest_index] = self.shadow_map[dest_index].saturating_add(8).min(48);
-
-                let dst = self.world_buf[dest_index];
-                let shadow_strength = self.shadow_map[dest_index] as f32 / 255.0;
-                let darken_factor = 1.0 - 0.4 * shadow_strength;
-
-                let r = ((dst >> 16) & 0xFF) as f32 * darken_factor;
-                let g = ((dst >> 8) & 0xFF) as f32 * darken_factor;
-                let b = (dst & 0xFF) as f32 * darken_factor;
-
-                self.world_buf[dest_index] =
-                    (0xFF << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
             }
         }
     }
@@ -490,139 +469,134 @@ impl Render {
     ///
     /// # Arguments
     ///
-    /// * `frame` - Sprite frame to render from the entity atlas
+    /// * `pixels` - Decoded sprite frame from the entity atlas
     /// * `screen_x` - X position in screen coordinates (output buffer space)
     /// * `screen_y` - Y position in screen coordinates (output buffer space)  
     /// * `buf` - Output pixel buffer to render into
     /// * `camera` - Camera configuration defining viewport and position
     fn render_shadow_unit(
         &mut self,
-        frame: &Frame,
+        pixels: &FramePixels,
         screen_x: i32,
         screen_y: i32,
         buf: &mut [u32],
         camera: &Camera,
     ) {
-        let (atlas_w, atlas_h) = self.entity_atlas.image.dimensions();
-
         let light_dir_x = 1.0;
         let light_dir_y = 0.0;
         let shadow_scale = 0.5;
 
-        for dy in 0..frame.h as i32 {
-            for dx in 0..frame.w as i32 {
-                let src_x = frame.x as i32 + dx;
-                let src_y = frame.y as i32 + dy;
-                if src_x < 0 || src_y < 0 || src_x >= atlas_w as i32 || src_y >= atlas_h as i32 {
-                    continue;
-                }
-
-                let color = self.entity_atlas.image.get_pixel(src_x as u32, src_y as u32);
-                if color[3] == 0 {
-                    continue;
-                }
-
-                let height_factor = (frame.h as f32 - dy as f32) * shadow_scale;
+        for dy in 0..pixels.h {
+            let height_factor = (pixels.h as f32 - dy as f32) * shadow_scale;
+            let shadow_y = screen_y as f32 + dy as f32 + (light_dir_y * height_factor);
+            let dest_y = shadow_y.round() as i32;
+            if dest_y < 0 || dest_y >= camera.height as i32 {
+                continue;
+            }
 
-                let shadow_x = screen_x as f32 + dx as f32 + (light_dir_x * height_factor);
-                let shadow_y = screen_y as f32 + dy as f32 + (light_dir_y * height_factor);
-                let dest_x = shadow_x.round() as i32;
-                let dest_y = shadow_y.round() as i32;
+            for span in pixels.row_spans(dy) {
+                for dx in span.start..span.start + span.len {
+                    let shadow_x = screen_x as f32 + dx as f32 + (light_dir_x * height_factor);
+                    let dest_x = shadow_x.round() as i32;
+                    if dest_x < 0 || dest_x >= camera.width as i32 {
+                        continue;
+                    }
 
-                if dest_x < 0
-                    || dest_y < 0
-                    || dest_x >= camera.width as i32
-                    || dest_y >= camera.height as i32
-                {
-                    continue;
-                }
+                    // Convert camera coordinates to world coordinates
+                    let world_x = (camera.center_x as i32 - camera.width as i32 / 2) + dest_x;
+                    let world_y = (camera.center_y as i32 - camera.height as i32 / 2) + dest_y;
+
+                    let dest_idx = (dest_y * camera.width as i32 + dest_x) as usize;
+
+                    // Check if there's already a shadow from a static object
+                    let mut has_shadow = false;
+                    if world_x >= 0
+                        && world_y >= 0
+                        && world_x < self.world_width as i32
+                        && world_y < self.world_height as i32
+                    {
+                        // Check static shadow
+                        if self.static_shadow(world_x, world_y) > 16 {
+                            has_shadow = true;
+                        }
 
-                // Convert camera coordinates to world coordinates
-                let world_x = (camera.center_x as i32 - camera.width as i32 / 2) + dest_x;
-                let world_y = (camera.center_y as i32 - camera.height as i32 / 2) + dest_y;
-
-                let dest_idx = (dest_y * camera.width as i32 + dest_x) as usize;
-
-                // Check if there's already a shadow from a static object
-                let mut has_shadow = false;
-                if world_x >= 0
-                    && world_y >= 0
-                    && world_x < self.world_width as i32
-                    && world_y < self.world_height as i32
-                {
-                    let shadow_idx = (world_y * self.world_width as i32 + world_x) as usize;
-                    // Check static shadow
-                    if self.shadow_map[shadow_idx] > 16 {
-                        has_shadow = true;
+                        // Check dynamic shadow (per view pixel)
+                        if self.dynamic_shadow_buf[dest_idx] > 0 {
+                            has_shadow = true;
+                        } else {
+                            // Mark that there's now a dynamic shadow here
+                            self.dynamic_shadow_buf[dest_idx] = 64;
+                        }
                     }
 
-                    // Check dynamic shadow (in world coordinates)
-                    if self.dynamic_shadow_buf[shadow_idx] > 0 {
-                        has_shadow = true;
-                    } else {
-                        // Mark that there's now a dynamic shadow here
-                        self.dynamic_shadow_buf[shadow_idx] = 64;
+                    if has_shadow {
+                        continue;
                     }
-                }
 
-                if has_shadow {
-                    continue;
-                }
+                    let dst = buf[dest_idx];
 
-                let dst = buf[dest_idx];
+                    const SHADOW_INTNS: f32 = 0.5;
+                    let r = ((dst >> 16) & 0xFF) as f32 * SHADOW_INTNS;
+                    let g = ((dst >> 8) & 0xFF) as f32 * SHADOW_INTNS;
+                    let b = (dst & 0xFF) as f32 * SHADOW_INTNS;
 
-                const SHADOW_INTNS: f32 = 0.5;
-                let r = ((dst >> 16) & 0xFF) as f32 * SHADOW_INTNS;
-                let g = ((dst >> 8) & 0xFF) as f32 * SHADOW_INTNS;
-                let b = (dst & 0xFF) as f32 * SHADOW_INTNS;
-
-                buf[dest_idx] = (0xFF << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+                    buf[dest_idx] =
+                        (0xFF << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+                }
             }
         }
     }
 
     /// Soft blur for shadow areas only
+    ///
+    /// Chunks of a chunked renderer are blurred while baked, this does nothing for them.
     pub fn soft_blur_shadows(&mut self) {
-        let width = self.world_width as i32;
-        let height = self.world_height as i32;
-        let mut blurred = self.shadow_map.clone();
-
-        for y in 1..height - 1 {
-            for x in 1..width - 1 {
-                let idx = (y * width + x) as usize;
-
-                if self.shadow_map[idx] > 0 {
-                    let mut sum = 0u32;
-                    let mut count = 0u32;
-
-                    for dy in -1..=1 {
-                        for dx in -1..=1 {
-                            let sx = x + dx;
-                            let sy = y + dy;
-                            if sx >= 0 && sy >= 0 && sx < width && sy < height {
-                                let sidx = (sy * width + sx) as usize;
-                                sum += self.shadow_map[sidx] as u32;
-                                count += 1;
-                            }
-                        }
-                    }
-
-                    blurred[idx] = (sum / count) as u8;
-                }
-            }
+        if self.chunks.is_none() {
+            let world = (self.world_width, self.world_height);
+            self.full_canvas().soft_blur_shadows(world);
         }
-
-        self.shadow_map = blurred;
     }
 
     pub fn create_entity(&self, x: f32, y: f32, sprite_name: &str) -> RenderableEntity {
-        RenderableEntity::with_sprite(x, y, sprite_name)
+        let sprite = self.entity_atlas.sprite_id(sprite_name);
+        RenderableEntity { sprite, ..RenderableEntity::with_sprite(x, y, sprite_name) }
     }
 }
 
+/// Darkens a sprite pixel and blends it over a buffer pixel by its alpha
+///
+/// # Arguments
+///
+/// * `color` - ARGB pixel of the sprite
+/// * `dst` - ARGB pixel of the buffer
+/// * `brightness` - Multiplier of the sprite colour, 1.0 keeps it unchanged
+///
+/// # Returns
+///
+/// * `u32` - Opaque ARGB result.
+fn blend_pixel(color: u32, dst: u32, brightness: f32) -> u32 {
+    let src_r = (((color >> 16) & 0xFF) as f32 * brightness) as u32;
+    let src_g = (((color >> 8) & 0xFF) as f32 * brightness) as u32;
+    let src_b = ((color & 0xFF) as f32 * brightness) as u32;
+
+    let dst_r = (dst >> 16) & 0xFF;
+    let dst_g = (dst >> 8) & 0xFF;
+    let dst_b = dst & 0xFF;
+
+    let alpha_factor = (color >> 24) as f32 / 255.0;
+    let inv_alpha_factor = 1.0 - alpha_factor;
+
+    let new_r = (src_r as f32 * alpha_factor + dst_r as f32 * inv_alpha_factor).min(255.0) as u32;
+    let new_g = (src_g as f32 * alpha_factor + dst_g as f32 * inv_alpha_factor).min(255.0) as u32;
+    let new_b = (src_b as f32 * alpha_factor + dst_b as f32 * inv_alpha_factor).min(255.0) as u32;
+
+    (0xFF << 24) | (new_r << 16) | (new_g << 8) | new_b
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::assets::{Animation, AnimationFrame, PlaybackMode};
     use image::{Rgba, RgbaImage};
     use std::collections::HashMap;
 
@@ -635,9 +609,9 @@ mod tests {
         }
 
         let mut frames = HashMap::new();
-        frames.insert("dummy".into(), Frame { name: String::new(), x: 0, y: 0, w: 4, h: 4 });
+        frames.insert("dummy".into(), Frame::new("dummy", 0, 0, 4, 4));
 
-        Atlas { image: img, frames, tile_size: 4, version: 1 }
+        Atlas::new(img, frames, HashMap::new(), 4, 1)
     }
 
     fn dummy_camera() -> Camera {
@@ -648,11 +622,11 @@ mod tests {
     fn test_render_unit_changes_buffer() {
         let atlas = dummy_atlas([255, 0, 0, 255]);
         let mut buf = vec![0; 100];
-        let frame = atlas.get_frame("dummy").unwrap();
+        let pixels = atlas.frame_pixels(atlas.get_frame("dummy").unwrap());
         let cam = dummy_camera();
 
         let render = Render::new(vec![0; 100], 10, 10, atlas.clone(), vec![0; 100]);
-        render.render_unit(frame, 3, 3, &mut buf, &cam);
+        render.render_unit(&pixels, 3, 3, &mut buf, &cam);
 
         assert!(buf.iter().any(|&p| p != 0), "Buffer must have changed pixels");
     }
@@ -661,9 +635,202 @@ mod tests {
     fn test_render_shadow_modifies_shadow_map() {
         let atlas = dummy_atlas([255, 255, 255, 255]);
         let mut render = Render::new(vec![0; 100], 10, 10, atlas.clone(), vec![0; 100]);
-        let frame = atlas.get_frame("dummy").unwrap();
+        let pixels = atlas.frame_pixels(atlas.get_frame("dummy").unwrap());
 
-        render.render_shadow(frame, 2, 2, &atlas);
+        render.full_canvas().render_shadow(&pixels, 2, 2);
         assert!(render.shadow_map.iter().any(|&v| v > 0), "Shadow map must change");
     }
+
+    #[test]
+    fn test_renderable_entity_resolves_animation() {
+        let mut atlas = dummy_atlas([255, 255, 255, 255]);
+        atlas.insert_frame(Frame::new("other", 0, 0, 2, 2));
+        atlas.animations.insert(
+            "blink".into(),
+            Animation {
+                name: "blink".into(),
+                frames: vec![
+                    AnimationFrame { frame: "dummy".into(), duration: 0.5 },
+                    AnimationFrame { frame: "other".into(), duration: 0.5 },
+                ],
+                mode: PlaybackMode::Loop,
+            },
+        );
+
+        let mut animator = Animator::new("blink");
+        animator.update(0.75);
+
+        let entity = RenderableEntity::with_animation(1.0, 2.0, &animator, &atlas);
+        assert_eq!(entity.frame(&atlas).unwrap().w, 2);
+
+        let entity = RenderableEntity::with_sprite(1.0, 2.0, "dummy");
+        assert_eq!(entity.frame(&atlas).unwrap().w, 4);
+
+        let entity = RenderableEntity::with_sprite(1.0, 2.0, "missing");
+        assert!(entity.frame(&atlas).is_none());
+
+        let entity = RenderableEntity::from_id(1.0, 2.0, atlas.sprite_id("other").unwrap());
+        assert_eq!(entity.frame(&atlas).unwrap().name, "other");
+        let entity = RenderableEntity::new(1.0, 2.0, "other".to_string());
+        assert_eq!(entity.sprite_id(&atlas), atlas.sprite_id("other"));
+    }
+
+    #[test]
+    fn test_render_frame_honours_pivot() {
+        let mut atlas = dummy_atlas([255, 0, 0, 255]);
+        let cam = dummy_camera();
+
+        // Default pivot is bottom-centre: 4x4 frame at (5, 5) covers x 3..7, y 1..5
+        let mut render = Render::new(vec![0; 100], 10, 10, atlas.clone(), vec![0; 100]);
+        let mut buf = vec![0; 100];
+        let entity = RenderableEntity::with_sprite(5.0, 5.0, "dummy");
+        render.render_frame(std::slice::from_ref(&entity), &cam, &mut buf);
+        assert_ne!(buf[10 + 3], 0);
+        assert_eq!(buf[5 * 10 + 5], 0);
+
+        // Top-left pivot with a trim offset of one pixel: covers x 6..10, y 6..10
+        let mut frame = atlas.get_frame("dummy").unwrap().clone();
+        frame.pivot = Some((0, 0));
+        frame.offset = (1, 1);
+        atlas.insert_frame(frame);
+        let mut render = Render::new(vec![0; 100], 10, 10, atlas, vec![0; 100]);
+        let mut buf = vec![0; 100];
+        render.render_frame(&[entity], &cam, &mut buf);
+        assert_eq!(buf[5 * 10 + 5], 0);
+        assert_ne!(buf[6 * 10 + 6], 0);
+        assert_ne!(buf[9 * 10 + 9], 0);
+    }
+
+    // Test that span blitting matches per-pixel blending, with shadows and clipping
+    #[test]
+    fn test_render_unit_matches_per_pixel_blend() {
+        let mut img = RgbaImage::new(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                let alpha = [0, 255, 128, 255][((x + y) % 4) as usize];
+                img.put_pixel(x, y, Rgba([200, 100 + x as u8, 50 + y as u8, alpha]));
+            }
+        }
+        let mut frames = HashMap::new();
+        frames.insert("mixed".to_string(), Frame::new("mixed", 0, 0, 4, 4));
+        let atlas = Atlas::new(img.clone(), frames, HashMap::new(), 4, 1);
+
+        let mut shadow = vec![0; 100];
+        shadow[2 * 10 + 1] = 48;
+        shadow[3 * 10 + 2] = 20;
+        let render = Render::new(vec![0; 100], 10, 10, atlas.clone(), shadow);
+        let cam = dummy_camera();
+
+        let background: Vec<u32> = (0..100).map(|i| 0xFF000000 | (i * 0x020301)).collect();
+        let mut buf = background.clone();
+        let pixels = atlas.frame_pixels(atlas.get_frame("mixed").unwrap());
+        render.render_unit(&pixels, -1, 1, &mut buf, &cam);
+
+        let mut expected = background;
+        for dy in 0..4i32 {
+            for dx in 0..4i32 {
+                let (dest_x, dest_y) = (dx - 1, dy + 1);
+                let [r, g, b, a] = img.get_pixel(dx as u32, dy as u32).0;
+                if a == 0 || dest_x < 0 {
+                    continue;
+                }
+                let color = (a as u32) << 24 | (r as u32) << 16 | (g as u32) << 8 | b as u32;
+                let idx = (dest_y * 10 + dest_x) as usize;
+                let brightness = 1.0 - 0.6 * render.get_shadow_intensity(dest_x, dest_y);
+                expected[idx] = blend_pixel(color, expected[idx], brightness);
+            }
+        }
+        assert_eq!(buf, expected);
+    }
+
+    // Test that layer cells bake like named tiles and later layers cover earlier ones
+    #[test]
+    fn test_init_draws_layers_in_order() {
+        let mut img = RgbaImage::new(8, 4);
+        for (x, _, pixel) in img.enumerate_pixels_mut() {
+            *pixel = if x < 4 { Rgba([255, 0, 0, 255]) } else { Rgba([0, 0, 255, 255]) };
+        }
+        let frames = HashMap::from([
+            ("red".to_string(), Frame::new("red", 0, 0, 4, 4)),
+            ("blue".to_string(), Frame::new("blue", 4, 0, 4, 4)),
+        ]);
+        let atlas = Atlas::new(img, frames, HashMap::new(), 4, 1);
+        let bake = |json: &str| {
+            let map: GameMap = json.parse().unwrap();
+            let mut render = Render::new(vec![0; 400], 20, 20, atlas.clone(), vec![0; 400]);
+            render.init(Arc::new(map), Arc::new(atlas.clone()));
+            render.world_buf
+        };
+
+        let meta =
+            r#""meta": {"name": "m", "tile_size": 4, "size": [2, 1]}, "objects": {}, "mobs": {}"#;
+        let named = bake(&format!(
+            r#"{{{meta}, "tiles": {{"a": {{"x": 0, "y": 0, "asset": "red"}},
+                                   "b": {{"x": 1, "y": 0, "asset": "red"}}}}}}"#
+        ));
+        let layered = bake(&format!(
+            r#"{{{meta}, "tileset": ["red", "blue"], "layers": [{{"name": "g", "data": [1, 1]}}]}}"#
+        ));
+        assert!(named.contains(&0xFFFF0000));
+        assert_eq!(named, layered);
+
+        let covered = bake(&format!(
+            r#"{{{meta}, "tileset": ["red", "blue"],
+                "layers": [{{"name": "g", "data": [1, 1]}}, {{"name": "o", "data": [0, 2]}}]}}"#
+        ));
+        assert!(covered.contains(&0xFFFF0000));
+        assert!(covered.contains(&0xFF0000FF));
+        assert_ne!(covered, layered);
+    }
+
+    // Test that raised tiles stand on side columns and cover objects behind them
+    #[test]
+    fn test_init_raises_tiles() {
+        let mut img = RgbaImage::new(12, 4);
+        for (x, _, pixel) in img.enumerate_pixels_mut() {
+            *pixel = Rgba([[255, 0, 0, 255], [0, 0, 255, 255], [0, 255, 0, 255]][x as usize / 4]);
+        }
+        let frames = HashMap::from([
+            ("top".to_string(), Frame::new("top", 0, 0, 4, 4)),
+            ("side".to_string(), Frame::new("side", 4, 0, 4, 4)),
+            ("tree".to_string(), Frame::new("tree", 8, 0, 4, 4)),
+        ]);
+        let atlas = Atlas::new(img, frames, HashMap::new(), 4, 1);
+        let bake = |json: &str| {
+            let map: GameMap = json.parse().unwrap();
+            let mut render = Render::new(vec![0; 400], 20, 20, atlas.clone(), vec![0; 400]);
+            render.init(Arc::new(map), Arc::new(atlas.clone()));
+            render.world_buf
+        };
+        let rows = |buf: &[u32], colour: u32| -> Vec<usize> {
+            let mut rows: Vec<usize> =
+                (0..400).filter(|&i| buf[i] == colour).map(|i| i / 20).collect();
+            rows.dedup();
+            rows
+        };
+        let (red, blue, green) = (0xFFFF0000, 0xFF0000FF, 0xFF00FF00);
+
+        let meta = r#""meta": {"name": "m", "tile_size": 4, "size": [2, 2]}, "mobs": {}"#;
+        let flat = bake(&format!(
+            r#"{{{meta}, "objects": {{}}, "tiles": {{"t": {{"x": 0, "y": 0, "asset": "top"}}}}}}"#
+        ));
+        let raised = bake(&format!(
+            r#"{{{meta}, "objects": {{}},
+                "tiles": {{"t": {{"x": 0, "y": 0, "asset": "top", "z": 2, "side": "side"}}}}}}"#
+        ));
+        // One height unit is half a tile size
+        assert_eq!(rows(&raised, red)[0] + 4, rows(&flat, red)[0]);
+        assert_eq!(rows(&raised, blue).last(), rows(&flat, red).last());
+
+        // The tile in front covers the tree only when raised above it
+        let scene = |z: u32| {
+            bake(&format!(
+                r#"{{{meta}, "objects": {{"tree": {{"x": 0, "y": 0, "asset": "tree"}}}},
+                    "tiles": {{"t": {{"x": 1, "y": 0, "asset": "top", "z": {z}}}}}}}"#
+            ))
+        };
+        let count = |buf: &[u32], colour: u32| buf.iter().filter(|&&p| p == colour).count();
+        assert_eq!(count(&scene(0), green), 16);
+        assert!(count(&scene(2), green) < 16);
+    }
 }
@@ -0,0 +1,44 @@
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// Scratch directory for tests that read or write files.
+///
+/// The directory is created under the system temp directory with a name unique
+/// to the test and process, and is removed with its contents when dropped.
+#[derive(Debug)]
+pub struct TempDir {
+    /// Absolute path of the directory
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates a fresh, empty scratch directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name unique among the tests of the crate
+    ///
+    /// # Returns
+    ///
+    /// * `TempDir` - Directory removed when the value is dropped
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("ferari_{}_{}", name, std::process::id()));
+        std::fs::remove_dir_all(&path).ok();
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.path).ok();
+    }
+}
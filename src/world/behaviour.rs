@@ -0,0 +1,620 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::Unit;
+use crate::assets::{Behaviour, BehaviourType, GameMap, MapEntity, MapIssue, MapReport, Property};
+use crate::rng::Rng;
+
+/// World around a mob during a behaviour update.
+pub struct BehaviourContext<'a> {
+    /// Map the game runs on
+    pub game_map: &'a GameMap,
+    /// The player unit
+    pub player: &'a Unit,
+    /// Seconds since the last tick
+    pub delta: f32,
+}
+
+/// Logic that moves one mob every tick.
+///
+/// Speeds are in pixels per tick, as `speed` of map behaviours.
+pub trait MobBehaviour: fmt::Debug + Send {
+    /// Moves the unit for one tick.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - Unit of the mob, its speed should be left at the movement of this tick
+    /// * `context` - The player and the map
+    fn update(&mut self, unit: &mut Unit, context: &BehaviourContext);
+}
+
+/// Creates the behaviour of one mob from its map definition.
+///
+/// Returns a description of the problem if the parameters are wrong.
+pub type BehaviourFactory =
+    Box<dyn Fn(&Behaviour) -> Result<Box<dyn MobBehaviour>, String> + Send + Sync>;
+
+/// Behaviour factories by behaviour type name.
+///
+/// A new registry knows the built-in `walker`, `patrol`, `wander`, `chase`
+/// and `flee` behaviours. Games add their own with [`BehaviourRegistry::register`],
+/// registering a built-in name replaces it.
+pub struct BehaviourRegistry {
+    /// Factories by behaviour type name
+    factories: HashMap<String, BehaviourFactory>,
+}
+
+impl BehaviourRegistry {
+    /// Creates a registry with the built-in behaviours.
+    ///
+    /// # Returns
+    ///
+    /// A new `BehaviourRegistry` instance.
+    pub fn new() -> Self {
+        let mut registry = Self { factories: HashMap::new() };
+        registry.register("walker", |behaviour| Ok(Box::new(Walker::from_behaviour(behaviour)?)));
+        registry.register("patrol", |behaviour| Ok(Box::new(Patrol::from_behaviour(behaviour)?)));
+        registry.register("wander", |behaviour| Ok(Box::new(Wander::from_behaviour(behaviour)?)));
+        registry.register("chase", |behaviour| Ok(Box::new(Chase::from_behaviour(behaviour)?)));
+        registry.register("flee", |behaviour| Ok(Box::new(Flee::from_behaviour(behaviour)?)));
+        registry
+    }
+
+    /// Registers a behaviour type.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Behaviour type name used in map files
+    /// * `factory` - Creates the behaviour of a mob from its definition
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&Behaviour) -> Result<Box<dyn MobBehaviour>, String> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    /// Checks if a behaviour type is registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Behaviour type name
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the type has a factory, false otherwise.
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    /// Returns the registered behaviour type names.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<&str>` - Sorted names.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Creates the behaviour of a mob.
+    ///
+    /// # Arguments
+    ///
+    /// * `behaviour` - Behaviour definition from the map
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Box<dyn MobBehaviour>>, String>` - Ok(None) for `controlled`, which
+    ///   follows input, the created behaviour otherwise, problem description on failure.
+    pub fn create(&self, behaviour: &Behaviour) -> Result<Option<Box<dyn MobBehaviour>>, String> {
+        if behaviour.behaviour_type == BehaviourType::Controlled {
+            return Ok(None);
+        }
+        let name = behaviour.behaviour_type.name();
+        let factory =
+            self.factories.get(name).ok_or_else(|| format!("unknown behaviour `{name}`"))?;
+        factory(behaviour).map(Some)
+    }
+
+    /// Checks the behaviours of map mobs and spawner templates.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_map` - Map to check
+    ///
+    /// # Returns
+    ///
+    /// * `MapReport` - Unknown and invalid behaviours, mobs in name order, then spawners.
+    pub fn validate(&self, game_map: &GameMap) -> MapReport {
+        let mut mobs: Vec<_> = game_map
+            .iter_mobs()
+            .map(|mob| (MapEntity::Mob(mob.name.clone()), mob.behaviour.as_ref()))
+            .collect();
+        mobs.sort_by_key(|(entity, _)| entity.name());
+        let mut spawners: Vec<_> = game_map
+            .iter_spawners()
+            .map(|spawner| {
+                (MapEntity::Spawner(spawner.name.clone()), spawner.template.behaviour.as_ref())
+            })
+            .collect();
+        spawners.sort_by_key(|(entity, _)| entity.name());
+
+        let mut issues = Vec::new();
+        for (entity, behaviour) in mobs.into_iter().chain(spawners) {
+            let Some(behaviour) = behaviour else {
+                continue;
+            };
+            let name = behaviour.behaviour_type.name();
+            if behaviour.behaviour_type != BehaviourType::Controlled && !self.contains(name) {
+                issues.push(MapIssue::UnknownBehaviour { entity, behaviour: name.to_string() });
+            } else if let Err(reason) = self.create(behaviour) {
+                issues.push(MapIssue::InvalidBehaviour { entity, reason });
+            }
+        }
+        MapReport { issues }
+    }
+}
+
+impl Default for BehaviourRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for BehaviourRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BehaviourRegistry").field("names", &self.names()).finish()
+    }
+}
+
+// ============================
+// Built-in behaviours
+// ============================
+
+/// Walks in one direction forever.
+///
+/// Uses `direction` (`left`, `right`, `up` or `down`) and `speed`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Walker {
+    /// Horizontal movement per tick
+    x_speed: f32,
+    /// Vertical movement per tick
+    y_speed: f32,
+}
+
+/// Walks from waypoint to waypoint and starts over after the last one.
+///
+/// Uses `speed` and the `waypoints` parameter, a list of `[x, y]` world pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patrol {
+    /// Points to visit in order
+    waypoints: Vec<(f32, f32)>,
+    /// Movement per tick
+    speed: f32,
+    /// Index of the waypoint walked to
+    next: usize,
+}
+
+/// Walks to random points around the position of its first update.
+///
+/// Uses `speed` and the `radius` (default 32) and `seed` (default 0)
+/// parameters. Mobs with the same seed still walk differently when they
+/// start at different positions.
+#[derive(Debug, Clone)]
+pub struct Wander {
+    /// Largest distance from home in pixels
+    radius: f32,
+    /// Movement per tick
+    speed: f32,
+    /// Seed given in the map
+    seed: u64,
+    /// Position of the first update
+    home: Option<(f32, f32)>,
+    /// Point walked to
+    target: Option<(f32, f32)>,
+    /// Source of target points
+    rng: Rng,
+}
+
+/// Walks towards the player.
+///
+/// Uses `speed` and the `range` (default unlimited) and `distance`
+/// (default 0) parameters: the player is followed while closer than
+/// `range`, and approached no nearer than `distance`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chase {
+    /// Movement per tick
+    speed: f32,
+    /// Largest distance to the player that starts the chase
+    range: Option<f32>,
+    /// Distance to keep from the player
+    distance: f32,
+}
+
+/// Walks away from the player while it is closer than `range`.
+///
+/// Uses `speed` and the `range` parameter (default 64).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flee {
+    /// Movement per tick
+    speed: f32,
+    /// Distance to the player that makes the mob run
+    range: f32,
+}
+
+impl Walker {
+    /// Builds a walker from its map definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `behaviour` - Behaviour definition from the map
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - Ok(Walker), problem description for unknown directions.
+    pub fn from_behaviour(behaviour: &Behaviour) -> Result<Self, String> {
+        let speed = behaviour.speed.unwrap_or(0.0);
+        let (x_speed, y_speed) = match behaviour.direction.as_deref() {
+            Some("left") => (-speed, 0.0),
+            Some("right") => (speed, 0.0),
+            Some("up") => (0.0, -speed),
+            Some("down") => (0.0, speed),
+            None => (0.0, 0.0),
+            Some(direction) => return Err(format!("unknown direction `{direction}`")),
+        };
+        Ok(Self { x_speed, y_speed })
+    }
+}
+
+impl MobBehaviour for Walker {
+    fn update(&mut self, unit: &mut Unit, _context: &BehaviourContext) {
+        step(unit, self.x_speed, self.y_speed);
+    }
+}
+
+impl Patrol {
+    /// Builds a patrol from its map definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `behaviour` - Behaviour definition from the map
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - Ok(Patrol), problem description for missing or broken waypoints.
+    pub fn from_behaviour(behaviour: &Behaviour) -> Result<Self, String> {
+        let points = behaviour.params.get_array("waypoints").unwrap_or_default();
+        let waypoints: Option<Vec<(f32, f32)>> = points.iter().map(point).collect();
+        match waypoints {
+            Some(waypoints) if !waypoints.is_empty() => {
+                Ok(Self { waypoints, speed: behaviour.speed.unwrap_or(0.0), next: 0 })
+            }
+            _ => Err("`waypoints` must be a non-empty list of [x, y] points".to_string()),
+        }
+    }
+}
+
+impl MobBehaviour for Patrol {
+    fn update(&mut self, unit: &mut Unit, _context: &BehaviourContext) {
+        if move_towards(unit, self.waypoints[self.next], self.speed) {
+            self.next = (self.next + 1) % self.waypoints.len();
+        }
+    }
+}
+
+impl Wander {
+    /// Builds a wanderer from its map definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `behaviour` - Behaviour definition from the map
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - Ok(Wander), problem description for a broken radius or seed.
+    pub fn from_behaviour(behaviour: &Behaviour) -> Result<Self, String> {
+        let radius = param(behaviour, "radius", 32.0)?;
+        if radius < 0.0 {
+            return Err(format!("`radius` must not be negative, got {radius}"));
+        }
+        let seed = match behaviour.params.get("seed") {
+            None => 0,
+            Some(seed) => seed.as_i64().ok_or("`seed` must be a whole number")? as u64,
+        };
+        let speed = behaviour.speed.unwrap_or(0.0);
+        Ok(Self { radius, speed, seed, home: None, target: None, rng: Rng::new(seed) })
+    }
+}
+
+impl MobBehaviour for Wander {
+    fn update(&mut self, unit: &mut Unit, _context: &BehaviourContext) {
+        let (home_x, home_y) = *self.home.get_or_insert_with(|| {
+            let position = ((unit.x.to_bits() as u64) << 32) | unit.y.to_bits() as u64;
+            self.rng = Rng::new(self.seed ^ position);
+            (unit.x, unit.y)
+        });
+
+        let rng = &mut self.rng;
+        let radius = self.radius;
+        let target = *self.target.get_or_insert_with(|| {
+            let angle = rng.range_f32(0.0, std::f32::consts::TAU);
+            let distance = radius * rng.next_f32().sqrt();
+            (home_x + angle.cos() * distance, home_y + angle.sin() * distance)
+        });
+        if move_towards(unit, target, self.speed) {
+            self.target = None;
+        }
+    }
+}
+
+impl Chase {
+    /// Builds a chaser from its map definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `behaviour` - Behaviour definition from the map
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - Ok(Chase), problem description for a broken range or distance.
+    pub fn from_behaviour(behaviour: &Behaviour) -> Result<Self, String> {
+        let range = match behaviour.params.get("range") {
+            None => None,
+            Some(_) => Some(param(behaviour, "range", 0.0)?),
+        };
+        let distance = param(behaviour, "distance", 0.0)?;
+        Ok(Self { speed: behaviour.speed.unwrap_or(0.0), range, distance })
+    }
+}
+
+impl MobBehaviour for Chase {
+    fn update(&mut self, unit: &mut Unit, context: &BehaviourContext) {
+        let (dx, dy) = (context.player.x - unit.x, context.player.y - unit.y);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length <= self.distance || self.range.is_some_and(|range| length > range) {
+            step(unit, 0.0, 0.0);
+            return;
+        }
+
+        let speed = self.speed.min(length - self.distance);
+        step(unit, dx / length * speed, dy / length * speed);
+    }
+}
+
+impl Flee {
+    /// Builds a fleeing behaviour from its map definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `behaviour` - Behaviour definition from the map
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, String>` - Ok(Flee), problem description for a broken range.
+    pub fn from_behaviour(behaviour: &Behaviour) -> Result<Self, String> {
+        let range = param(behaviour, "range", 64.0)?;
+        Ok(Self { speed: behaviour.speed.unwrap_or(0.0), range })
+    }
+}
+
+impl MobBehaviour for Flee {
+    fn update(&mut self, unit: &mut Unit, context: &BehaviourContext) {
+        let (dx, dy) = (unit.x - context.player.x, unit.y - context.player.y);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length >= self.range {
+            step(unit, 0.0, 0.0);
+        } else if length > 0.0 {
+            step(unit, dx / length * self.speed, dy / length * self.speed);
+        } else {
+            // Standing on the player, any direction leads away
+            step(unit, self.speed, 0.0);
+        }
+    }
+}
+
+/// Reads a number parameter of a behaviour.
+///
+/// # Arguments
+///
+/// * `behaviour` - Behaviour definition from the map
+/// * `key` - Name of the parameter
+/// * `default` - Value of a missing parameter
+///
+/// # Returns
+///
+/// * `Result<f32, String>` - The value, problem description if it is not a number.
+fn param(behaviour: &Behaviour, key: &str, default: f32) -> Result<f32, String> {
+    match behaviour.params.get(key) {
+        None => Ok(default),
+        Some(value) => value.as_f64().map(|v| v as f32).ok_or(format!("`{key}` must be a number")),
+    }
+}
+
+/// Reads an `[x, y]` point.
+fn point(value: &Property) -> Option<(f32, f32)> {
+    match value.as_array()? {
+        [x, y] => Some((x.as_f64()? as f32, y.as_f64()? as f32)),
+        _ => None,
+    }
+}
+
+/// Moves a unit and stores the movement as its speed.
+fn step(unit: &mut Unit, x_speed: f32, y_speed: f32) {
+    unit.x_speed = x_speed;
+    unit.y_speed = y_speed;
+    unit.x += x_speed;
+    unit.y += y_speed;
+}
+
+/// Moves a unit towards a point without passing it.
+///
+/// # Returns
+///
+/// * `bool` - true if the unit stands on the point after the move.
+fn move_towards(unit: &mut Unit, target: (f32, f32), speed: f32) -> bool {
+    let (dx, dy) = (target.0 - unit.x, target.1 - unit.y);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length <= speed {
+        step(unit, dx, dy);
+        (unit.x, unit.y) = target;
+        return true;
+    }
+    step(unit, dx / length * speed, dy / length * speed);
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::State;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    const MAP: &str = r#"{
+        "meta": {"name": "behaviours", "tile_size": 16, "size": [8, 8]},
+        "objects": {},
+        "mobs": {
+            "player": {"x_start": 0, "y_start": 0, "asset": "knight", "is_player": true,
+                       "behaviour": {"type": "controlled"}},
+            "a_walker": {"x_start": 10, "y_start": 0, "asset": "imp",
+                         "behaviour": {"type": "walker", "direction": "down", "speed": 2}},
+            "b_patrol": {"x_start": 0, "y_start": 0, "asset": "imp",
+                         "behaviour": {"type": "patrol", "speed": 3,
+                                       "waypoints": [[4, 0], [4, 4]]}},
+            "c_wander": {"x_start": 50, "y_start": 50, "asset": "imp",
+                         "behaviour": {"type": "wander", "speed": 1, "radius": 8, "seed": 3}},
+            "d_chase": {"x_start": 30, "y_start": 40, "asset": "imp",
+                        "behaviour": {"type": "chase", "speed": 5, "distance": 10}},
+            "e_flee": {"x_start": 0, "y_start": 20, "asset": "imp",
+                       "behaviour": {"type": "flee", "speed": 1, "range": 30}},
+            "f_dance": {"x_start": 0, "y_start": 0, "asset": "imp",
+                        "behaviour": {"type": "dance", "speed": 1}},
+            "g_broken": {"x_start": 0, "y_start": 0, "asset": "imp",
+                         "behaviour": {"type": "patrol", "waypoints": [[1]]}}
+        }
+    }"#;
+
+    /// Spins in place, counting its updates.
+    #[derive(Debug)]
+    struct Dance {
+        steps: u32,
+    }
+
+    impl MobBehaviour for Dance {
+        fn update(&mut self, unit: &mut Unit, context: &BehaviourContext) {
+            self.steps += 1;
+            unit.x = self.steps as f32 * context.delta;
+        }
+    }
+
+    /// Finds a mob unit of the test map by its name, states keep the map mob order.
+    fn unit<'a>(map: &GameMap, state: &'a State, name: &str) -> &'a Unit {
+        let index = map.iter_mobs().filter(|mob| !mob.is_player).position(|mob| mob.name == name);
+        state.mobs().nth(index.unwrap()).unwrap()
+    }
+
+    // Test that each built-in behaviour moves its mob as configured
+    #[test]
+    fn test_builtin_behaviours() {
+        let map = GameMap::from_str(MAP).unwrap();
+        let mut state = State::new(&map);
+        assert_eq!(state.mob_count(), 7);
+        assert_eq!(state.iter_mobs().filter(|(id, _)| state.has_behaviour(*id)).count(), 5);
+
+        for _ in 0..3 {
+            state.tick(&map, 0.1);
+        }
+
+        let walker = unit(&map, &state, "a_walker");
+        assert_eq!((walker.x, walker.y, walker.y_speed), (10.0, 6.0, 2.0));
+
+        // Two ticks to stop on the first waypoint, then on towards the second
+        let patrol = unit(&map, &state, "b_patrol");
+        assert_eq!((patrol.x, patrol.y), (4.0, 3.0));
+
+        let wander = unit(&map, &state, "c_wander");
+        let home = ((wander.x - 50.0).powi(2) + (wander.y - 50.0).powi(2)).sqrt();
+        assert!(home <= 8.0 + 1e-4);
+
+        // Stops 10 px before the player at (0, 0)
+        let chase = unit(&map, &state, "d_chase");
+        assert!(((chase.x.powi(2) + chase.y.powi(2)).sqrt() - 35.0).abs() < 1e-4);
+        for _ in 0..10 {
+            state.tick(&map, 0.1);
+        }
+        let chase = unit(&map, &state, "d_chase");
+        assert!(((chase.x.powi(2) + chase.y.powi(2)).sqrt() - 10.0).abs() < 1e-4);
+
+        // Runs until it is out of range
+        let flee = unit(&map, &state, "e_flee");
+        assert_eq!((flee.x, flee.y, flee.y_speed), (0.0, 30.0, 0.0));
+
+        let dance = unit(&map, &state, "f_dance");
+        assert_eq!((dance.x, dance.y), (0.0, 0.0));
+    }
+
+    // Test that wandering is reproducible for the same seed and position
+    #[test]
+    fn test_wander_is_seeded() {
+        let map = GameMap::from_str(MAP).unwrap();
+        let run = || {
+            let mut state = State::new(&map);
+            (0..50)
+                .map(|_| {
+                    state.tick(&map, 0.1);
+                    let wander = unit(&map, &state, "c_wander");
+                    (wander.x, wander.y)
+                })
+                .collect::<Vec<_>>()
+        };
+        let path = run();
+        assert_eq!(path, run());
+        assert!(path.iter().any(|&point| point != path[0]));
+    }
+
+    // Test custom registrations and behaviour checks of maps
+    #[test]
+    fn test_registry() {
+        let map = GameMap::from_str(MAP).unwrap();
+        let mut registry = BehaviourRegistry::new();
+        assert_eq!(registry.names(), vec!["chase", "flee", "patrol", "walker", "wander"]);
+
+        let report = registry.validate(&map);
+        assert_eq!(
+            report.issues,
+            vec![
+                MapIssue::UnknownBehaviour {
+                    entity: MapEntity::Mob("f_dance".into()),
+                    behaviour: "dance".into(),
+                },
+                MapIssue::InvalidBehaviour {
+                    entity: MapEntity::Mob("g_broken".into()),
+                    reason: "`waypoints` must be a non-empty list of [x, y] points".into(),
+                },
+            ]
+        );
+
+        registry.register("dance", |_| Ok(Box::new(Dance { steps: 0 })));
+        assert!(registry.contains("dance"));
+        assert_eq!(registry.validate(&map).issues.len(), 1);
+
+        // Directions are checked by the behaviour that uses them
+        let mut sideways = map.clone();
+        let walker = sideways.mobs.get_mut("a_walker").unwrap().behaviour.as_mut().unwrap();
+        walker.direction = Some("sideways".into());
+        assert!(registry.validate(&sideways).issues.contains(&MapIssue::InvalidBehaviour {
+            entity: MapEntity::Mob("a_walker".into()),
+            reason: "unknown direction `sideways`".into(),
+        }));
+
+        let mut state = State::with_registry(&map, Arc::new(registry));
+        state.tick(&map, 0.5);
+        state.tick(&map, 0.5);
+        assert_eq!(unit(&map, &state, "f_dance").x, 1.0);
+
+        state.spawn(0.0, 0.0, map.get_mob("f_dance").unwrap().behaviour.as_ref());
+        state.tick(&map, 2.0);
+        assert_eq!(state.mobs().last().unwrap().x, 2.0);
+    }
+}
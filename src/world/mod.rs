@@ -1,5 +1,14 @@
+mod behaviour;
 mod camera;
+mod regions;
+mod spawner;
 mod state;
 
 pub use self::state::*;
+pub use behaviour::{
+    BehaviourContext, BehaviourFactory, BehaviourRegistry, Chase, Flee, MobBehaviour, Patrol,
+    Walker, Wander,
+};
 pub use camera::Camera;
+pub use regions::{RegionEvent, RegionEventKind, RegionSubject, RegionTracker};
+pub use spawner::SpawnerSystem;
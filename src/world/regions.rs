@@ -0,0 +1,238 @@
+use std::collections::BTreeSet;
+
+use super::State;
+use crate::assets::{GameMap, Region};
+
+/// Unit that region events refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RegionSubject {
+    /// The player unit
+    Player,
+    /// Mob unit with the id given by `State::spawn`, which stays the same while mobs come and go
+    Mob(u64),
+}
+
+/// Kind of change between two ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionEventKind {
+    /// The unit was outside last tick and is inside now
+    Enter,
+    /// The unit was inside last tick and is outside now
+    Exit,
+    /// The unit was inside last tick and still is
+    Stay,
+}
+
+/// Report of a unit being in or crossing the border of a region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionEvent {
+    /// Name of the region
+    pub region: String,
+    /// Unit the event is about
+    pub subject: RegionSubject,
+    /// What happened
+    pub kind: RegionEventKind,
+}
+
+/// Tracks which units are inside which map regions.
+///
+/// Call [`RegionTracker::update`] once per tick after moving units. Events
+/// come sorted by region name, the player before mobs, mobs by id.
+#[derive(Debug, Default)]
+pub struct RegionTracker {
+    /// Sorted names of all regions seen so far, `inside` refers to them by index
+    names: Vec<String>,
+    /// Region index and unit pairs that were inside at the last update
+    inside: BTreeSet<(usize, RegionSubject)>,
+}
+
+impl RegionTracker {
+    /// Creates a tracker with no unit inside any region.
+    ///
+    /// # Returns
+    ///
+    /// A new `RegionTracker` instance, its first update reports `Enter` for units already inside.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks unit positions against the map regions.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_map` - Map with the regions
+    /// * `state` - Current unit positions
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<RegionEvent>` - Enter, exit and stay events of this tick.
+    pub fn update(&mut self, game_map: &GameMap, state: &State) -> Vec<RegionEvent> {
+        let units = std::iter::once((RegionSubject::Player, &state.player))
+            .chain(state.iter_mobs().map(|(id, unit)| (RegionSubject::Mob(id), unit)));
+
+        let regions = self.resolve(game_map);
+
+        let mut inside = BTreeSet::new();
+        for (subject, unit) in units {
+            for &(index, region) in &regions {
+                if game_map.region_contains(region, unit.x, unit.y) {
+                    inside.insert((index, subject));
+                }
+            }
+        }
+
+        let events = self
+            .inside
+            .union(&inside)
+            .map(|key @ (index, subject)| {
+                let kind = match (self.inside.contains(key), inside.contains(key)) {
+                    (true, true) => RegionEventKind::Stay,
+                    (false, _) => RegionEventKind::Enter,
+                    (true, false) => RegionEventKind::Exit,
+                };
+                RegionEvent { region: self.names[*index].clone(), subject: *subject, kind }
+            })
+            .collect();
+
+        self.inside = inside;
+        events
+    }
+
+    /// Forgets all units, e.g. after switching to another map.
+    ///
+    /// Units still inside a region get an `Enter` event on the next update.
+    pub fn reset(&mut self) {
+        self.inside.clear();
+    }
+
+    /// Checks if a unit was inside a region at the last update.
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - Name of the region
+    /// * `subject` - Unit to check
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the unit was inside, false otherwise.
+    pub fn is_inside(&self, region: &str, subject: RegionSubject) -> bool {
+        self.index(region).is_some_and(|index| self.inside.contains(&(index, subject)))
+    }
+
+    /// Finds the index of a region name.
+    fn index(&self, region: &str) -> Option<usize> {
+        self.names.binary_search_by(|name| name.as_str().cmp(region)).ok()
+    }
+
+    /// Pairs the map regions with their indexes, adding names not seen before.
+    ///
+    /// Names of removed regions are kept, so units inside them get `Exit` events.
+    fn resolve<'a>(&mut self, game_map: &'a GameMap) -> Vec<(usize, &'a Region)> {
+        let new_names: Vec<String> = game_map
+            .iter_regions()
+            .filter(|region| self.index(&region.name).is_none())
+            .map(|region| region.name.clone())
+            .collect();
+        if !new_names.is_empty() {
+            let old_names = self.names.clone();
+            self.names.extend(new_names);
+            self.names.sort();
+            self.inside = std::mem::take(&mut self.inside)
+                .into_iter()
+                .map(|(index, subject)| (self.index(&old_names[index]).unwrap(), subject))
+                .collect();
+        }
+        game_map
+            .iter_regions()
+            .map(|region| (self.index(&region.name).expect("region names are resolved"), region))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Unit;
+    use std::str::FromStr;
+
+    // World (72, 44) is the centre of cell (0, 0), (88, 60) of cell (3, 1)
+    const MAP: &str = r#"{
+        "meta": {"name": "regions", "tile_size": 16, "size": [4, 4]},
+        "objects": {},
+        "mobs": {},
+        "regions": {
+            "corner": {"rect": [0, 0, 1, 1]},
+            "gate": {"space": "world", "rect": [80, 52, 16, 16], "tags": ["exit"]}
+        }
+    }"#;
+
+    fn event(region: &str, subject: RegionSubject, kind: RegionEventKind) -> RegionEvent {
+        RegionEvent { region: region.to_string(), subject, kind }
+    }
+
+    // Test enter, stay and exit events of the player and mobs across ticks
+    #[test]
+    fn test_region_events() {
+        use RegionEventKind::*;
+        use RegionSubject::*;
+
+        let map = GameMap::from_str(MAP).unwrap();
+        let mut state = State::default();
+        state.player = Unit::new(72.0, 44.0, 0.0, 0.0);
+        state.spawn(0.0, 0.0, None);
+        state.spawn(88.0, 60.0, None);
+        let mut tracker = RegionTracker::new();
+
+        let events = tracker.update(&map, &state);
+        assert_eq!(events, vec![event("corner", Player, Enter), event("gate", Mob(1), Enter)]);
+        assert!(tracker.is_inside("corner", Player));
+
+        state.player = Unit::new(88.0, 60.0, 0.0, 0.0);
+        let events = tracker.update(&map, &state);
+        assert_eq!(
+            events,
+            vec![
+                event("corner", Player, Exit),
+                event("gate", Player, Enter),
+                event("gate", Mob(1), Stay)
+            ]
+        );
+
+        // Mobs spawned again get new ids, so the old ones leave and the new ones enter
+        state.despawn(0);
+        state.despawn(1);
+        state.spawn(88.0, 60.0, None);
+        let events = tracker.update(&map, &state);
+        assert_eq!(
+            events,
+            vec![
+                event("gate", Player, Stay),
+                event("gate", Mob(1), Exit),
+                event("gate", Mob(2), Enter)
+            ]
+        );
+
+        tracker.reset();
+        assert_eq!(
+            tracker.update(&map, &state),
+            vec![event("gate", Player, Enter), event("gate", Mob(2), Enter)]
+        );
+        assert!(!tracker.is_inside("corner", Player));
+
+        // Units inside a removed region exit it, new regions are tracked
+        let mut map = map;
+        let mut gate = map.regions.remove("gate").unwrap();
+        gate.name = "a_gate".to_string();
+        map.regions.insert(gate.name.clone(), gate);
+        assert_eq!(
+            tracker.update(&map, &state),
+            vec![
+                event("a_gate", Player, Enter),
+                event("a_gate", Mob(2), Enter),
+                event("gate", Player, Exit),
+                event("gate", Mob(2), Exit)
+            ]
+        );
+        assert!(tracker.is_inside("a_gate", Player) && !tracker.is_inside("gate", Player));
+    }
+}
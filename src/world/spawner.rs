@@ -0,0 +1,254 @@
+use super::State;
+use crate::assets::{GameMap, Spawner};
+use crate::rng::Rng;
+
+/// Progress of one map spawner.
+#[derive(Debug, Clone)]
+struct SpawnerRun {
+    /// Name of the spawner in the map
+    name: String,
+    /// Generator of spawn positions, seeded from the spawner
+    rng: Rng,
+    /// Number of mobs spawned so far
+    spawned: u32,
+    /// Ids of the spawned mobs still alive, ascending
+    alive: Vec<u64>,
+    /// Seconds collected towards the next spawn
+    timer: f32,
+}
+
+/// Creates mob units from the spawners of a map over time.
+///
+/// Spawned units are added with `State::spawn`. The first mob of a spawner
+/// appears on the first update, the next ones every `interval` seconds.
+/// The system remembers the ids of the mobs each spawner created. The game
+/// reports removed mobs by id with [`SpawnerSystem::despawned`] so that
+/// `max_alive` limits can be refilled.
+#[derive(Debug, Clone, Default)]
+pub struct SpawnerSystem {
+    /// Spawner progress sorted by spawner name
+    runs: Vec<SpawnerRun>,
+}
+
+impl SpawnerSystem {
+    /// Creates a system for the spawners of a map.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_map` - Map with the spawners
+    ///
+    /// # Returns
+    ///
+    /// A new `SpawnerSystem` instance with nothing spawned yet.
+    pub fn new(game_map: &GameMap) -> Self {
+        let mut spawners: Vec<&Spawner> = game_map.iter_spawners().collect();
+        spawners.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let runs = spawners
+            .into_iter()
+            .map(|spawner| SpawnerRun {
+                name: spawner.name.clone(),
+                rng: Rng::new(spawner.seed),
+                spawned: 0,
+                alive: Vec::new(),
+                timer: spawner.interval,
+            })
+            .collect();
+        Self { runs }
+    }
+
+    /// Advances spawn timers and spawns due mobs.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_map` - Map the system was created for
+    /// * `state` - State to add mob units to
+    /// * `delta` - Seconds since the last update
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - Number of mobs spawned by this update.
+    pub fn update(&mut self, game_map: &GameMap, state: &mut State, delta: f32) -> usize {
+        let mut total = 0;
+        for run in &mut self.runs {
+            let Some(spawner) = game_map.get_spawner(&run.name) else {
+                continue;
+            };
+            let max_alive = spawner.max_alive.unwrap_or(u32::MAX);
+
+            run.timer += delta;
+            let mut due = if spawner.interval > 0.0 {
+                (run.timer / spawner.interval) as u32
+            } else {
+                u32::MAX
+            };
+            let room = spawner
+                .count
+                .saturating_sub(run.spawned)
+                .min(max_alive.saturating_sub(run.alive.len() as u32));
+            due = due.min(room);
+
+            for _ in 0..due {
+                let (x, y) = game_map.spawn_point(spawner, &mut run.rng);
+                let behaviour = spawner.template.behaviour.as_ref();
+                run.alive.push(state.spawn(x, y, behaviour));
+            }
+            run.spawned += due;
+            total += due as usize;
+
+            // Time spent waiting for a free slot does not pile up into a burst
+            run.timer = (run.timer - due as f32 * spawner.interval).min(spawner.interval);
+        }
+        total
+    }
+
+    /// Reports that a mob was removed from the game.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Id of the removed mob
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - `true` if one of the spawners created the mob.
+    pub fn despawned(&mut self, id: u64) -> bool {
+        for run in &mut self.runs {
+            if let Ok(index) = run.alive.binary_search(&id) {
+                run.alive.remove(index);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns the number of mobs a spawner created so far.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the spawner
+    ///
+    /// # Returns
+    ///
+    /// * `u32` - Spawned mob count, 0 for unknown spawners.
+    pub fn spawned(&self, name: &str) -> u32 {
+        self.runs.iter().find(|run| run.name == name).map_or(0, |run| run.spawned)
+    }
+
+    /// Returns the number of alive mobs of a spawner.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the spawner
+    ///
+    /// # Returns
+    ///
+    /// * `u32` - Alive mob count, 0 for unknown spawners.
+    pub fn alive(&self, name: &str) -> u32 {
+        self.runs.iter().find(|run| run.name == name).map_or(0, |run| run.alive.len() as u32)
+    }
+
+    /// Returns the ids of the alive mobs of a spawner.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the spawner
+    ///
+    /// # Returns
+    ///
+    /// * `&[u64]` - Ascending mob ids, empty for unknown spawners.
+    pub fn alive_ids(&self, name: &str) -> &[u64] {
+        self.runs.iter().find(|run| run.name == name).map_or(&[], |run| &run.alive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Unit;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    const MAP: &str = r#"{
+        "meta": {"name": "spawn", "tile_size": 16, "size": [8, 8]},
+        "objects": {},
+        "mobs": {"player": {"x_start": 100, "y_start": 100, "asset": "knight", "is_player": true}},
+        "spawners": {
+            "wave": {"area": {"space": "world", "rect": [10, 20, 30, 40]}, "count": 5,
+                     "interval": 1.0, "max_alive": 2, "seed": 4,
+                     "template": {"asset": "imp_20",
+                                  "behaviour": {"type": "walker", "direction": "left", "speed": 0.5}}},
+            "burst": {"area": {"rect": [0, 0, 8, 8]}, "count": 100, "seed": 1,
+                      "template": {"asset": "ghost_30"}}
+        }
+    }"#;
+
+    // Removes the oldest alive wave mob from the state and the system
+    fn despawn_wave(spawners: &mut SpawnerSystem, state: &mut State) {
+        let id = spawners.alive_ids("wave")[0];
+        assert!(state.despawn(id).is_some());
+        assert!(spawners.despawned(id));
+        assert!(!spawners.despawned(id));
+    }
+
+    // Test intervals, alive limits and reproducible positions
+    #[test]
+    fn test_spawner_system() {
+        let map = GameMap::from_str(MAP).unwrap();
+        let mut state = State::new(&map);
+        let mut spawners = SpawnerSystem::new(&map);
+
+        assert_eq!(spawners.update(&map, &mut state, 0.0), 101);
+        assert_eq!((spawners.spawned("burst"), spawners.spawned("wave")), (100, 1));
+        let wave: Vec<&Unit> = state.mobs().filter(|unit| unit.x_speed != 0.0).collect();
+        assert_eq!(wave.len(), 1);
+        assert!((10.0..40.0).contains(&wave[0].x) && (20.0..60.0).contains(&wave[0].y));
+        assert_eq!(wave[0].x_speed, -0.5);
+
+        assert_eq!(spawners.update(&map, &mut state, 0.5), 0);
+        assert_eq!(spawners.update(&map, &mut state, 0.5), 1);
+        let wave_ids: Vec<u64> =
+            state.iter_mobs().filter(|(_, unit)| unit.x_speed != 0.0).map(|(id, _)| id).collect();
+        assert_eq!(spawners.alive_ids("wave"), wave_ids);
+        assert_eq!(spawners.alive_ids("burst").len(), 100);
+        // Two alive, waiting for a slot does not store up spawns
+        assert_eq!(spawners.update(&map, &mut state, 10.0), 0);
+        despawn_wave(&mut spawners, &mut state);
+        assert_eq!(spawners.alive("wave"), 1);
+        assert_eq!(spawners.update(&map, &mut state, 0.1), 1);
+        assert_eq!(spawners.update(&map, &mut state, 0.1), 0);
+        despawn_wave(&mut spawners, &mut state);
+        despawn_wave(&mut spawners, &mut state);
+        assert_eq!(spawners.update(&map, &mut state, 5.0), 2);
+        assert_eq!(spawners.spawned("wave"), 5);
+        despawn_wave(&mut spawners, &mut state);
+        assert_eq!(spawners.update(&map, &mut state, 5.0), 0);
+
+        // A count lowered below the spawned number stops the spawner
+        let mut smaller = map.clone();
+        smaller.spawners.get_mut("wave").unwrap().count = 2;
+        despawn_wave(&mut spawners, &mut state);
+        assert_eq!(spawners.update(&smaller, &mut state, 5.0), 0);
+
+        let run = || {
+            let mut state = State::new(&map);
+            SpawnerSystem::new(&map).update(&map, &mut state, 0.0);
+            state.mobs().map(|unit| (unit.x, unit.y)).collect::<Vec<_>>()
+        };
+        assert_eq!(run(), run());
+    }
+
+    // Test that the crowd example declares the former benchmark mobs
+    #[test]
+    fn test_crowd_example() {
+        let project_root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let map = GameMap::load(project_root.join("examples/crowd.json")).unwrap();
+        let mut state = State::new(&map);
+        let mut spawners = SpawnerSystem::new(&map);
+
+        assert_eq!(spawners.update(&map, &mut state, 0.0), 1000);
+        for mob in state.mobs() {
+            assert!((100.0..600.0).contains(&mob.x) && (100.0..600.0).contains(&mob.y));
+            assert!(mob.x_speed.abs() == 0.5 || mob.x_speed.abs() == 0.42);
+        }
+    }
+}
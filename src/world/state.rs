@@ -1,15 +1,36 @@
-use crate::assets::GameMap;
+use std::sync::Arc;
+
+use super::behaviour::{BehaviourContext, BehaviourRegistry, MobBehaviour};
+use crate::assets::{Behaviour, GameMap, Mob};
 
 /// Represents the current game state containing all units.
 ///
 /// The `State` struct manages the player unit and all mob units in the game,
 /// tracking their positions and movement speeds for game simulation.
+///
+/// Mobs are added with [`State::spawn`] and removed with [`State::despawn`],
+/// each one keeps its unit, behaviour and id together.
 #[derive(Debug, Default)]
 pub struct State {
     /// The player-controlled unit
     pub player: Unit,
-    /// Collection of all non-player mobile units
-    pub mobs: Vec<Unit>,
+    /// Creates the behaviours of loaded and spawned mobs
+    pub registry: Arc<BehaviourRegistry>,
+    /// Non-player units in spawn order, so ids are ascending
+    mobs: Vec<MobSlot>,
+    /// Id of the next spawned mob
+    next_mob_id: u64,
+}
+
+/// Non-player unit with its behaviour and id.
+#[derive(Debug)]
+struct MobSlot {
+    /// Position and speed of the mob
+    unit: Unit,
+    /// Moves the mob every tick, None for mobs that do not move by themselves
+    behaviour: Option<Box<dyn MobBehaviour>>,
+    /// Id of the mob, never given to another mob
+    id: u64,
 }
 
 /// Represents a unit entity in the game world with position and movement capabilities.
@@ -74,9 +95,27 @@ impl State {
     ///   - "down": positive y_speed
     ///   - Mobs without behavior definitions get zero movement speed
     /// - Mobs without specified speed default to 0.0
+    /// - Mob behaviours are created by the built-in [`BehaviourRegistry`]
     pub fn new(game_map: &GameMap) -> Self {
+        Self::with_registry(game_map, Arc::new(BehaviourRegistry::new()))
+    }
+
+    /// Creates a new `State` whose mob behaviours come from a custom registry.
+    ///
+    /// Mobs whose behaviour is unknown to the registry or has broken parameters
+    /// get no behaviour, [`BehaviourRegistry::validate`] reports them.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_map` - Reference to the `GameMap` containing unit definitions
+    /// * `registry` - Behaviour factories, also used for mobs spawned later
+    ///
+    /// # Returns
+    ///
+    /// A new `State` instance.
+    pub fn with_registry(game_map: &GameMap, registry: Arc<BehaviourRegistry>) -> Self {
         let mut player: Option<Unit> = None;
-        let mut mobs: Vec<Unit> = Vec::new();
+        let mut state = Self { registry, ..Default::default() };
 
         for mob in game_map.iter_mobs() {
             if mob.is_player {
@@ -89,42 +128,208 @@ impl State {
                 continue;
             }
 
-            if let Some(beh) = &mob.behaviour {
-                let mob_direction = beh.direction.as_deref().unwrap_or("none");
-                let mob_speed = beh.speed.unwrap_or(0.0);
+            state.spawn_mob(mob);
+        }
 
-                mobs.push(Unit {
-                    x: mob.x_start as f32,
-                    y: mob.y_start as f32,
-                    x_speed: match mob_direction {
-                        "right" => mob_speed,
-                        "left" => -mob_speed,
-                        _ => 0.0,
-                    },
-                    y_speed: match mob_direction {
-                        "up" => -mob_speed,
-                        "down" => mob_speed,
-                        _ => 0.0,
-                    },
-                });
-            } else {
-                mobs.push(Unit {
-                    x: mob.x_start as f32,
-                    y: mob.y_start as f32,
-                    x_speed: 0.0,
-                    y_speed: 0.0,
-                });
+        state.player = player.unwrap();
+        state
+    }
+
+    /// Applies a reloaded map to the running state.
+    ///
+    /// The player keeps its position and speed. Mobs keep theirs unless mob or
+    /// spawner definitions changed between the maps, then map mobs are spawned
+    /// again and spawned mobs are removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_map` - Map the state was built from
+    /// * `new_map` - Reloaded map
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if mobs were spawned again, a `SpawnerSystem` must then start over.
+    pub fn reload(&mut self, old_map: &GameMap, new_map: &GameMap) -> bool {
+        if old_map.mobs == new_map.mobs && old_map.spawners == new_map.spawners {
+            return false;
+        }
+
+        self.mobs.clear();
+        for mob in new_map.iter_mobs().filter(|mob| !mob.is_player) {
+            self.spawn_mob(mob);
+        }
+        true
+    }
+
+    /// Adds a non-player unit with its behaviour.
+    ///
+    /// Mobs spawned again on reload get new ids too.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - X-coordinate of the new unit
+    /// * `y` - Y-coordinate of the new unit
+    /// * `behaviour` - Behaviour definition, None for a unit standing still
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - Id of the new mob.
+    pub fn spawn(&mut self, x: f32, y: f32, behaviour: Option<&Behaviour>) -> u64 {
+        let unit = Self::behaviour_unit(x, y, behaviour);
+        let behaviour =
+            behaviour.and_then(|behaviour| self.registry.create(behaviour).ok().flatten());
+        self.push_mob(unit, behaviour)
+    }
+
+    /// Adds a non-player unit that moves only as the game moves it.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - Position and speed of the new unit
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - Id of the new mob.
+    pub fn spawn_unit(&mut self, unit: Unit) -> u64 {
+        self.push_mob(unit, None)
+    }
+
+    /// Removes a mob with its behaviour.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Id of the mob
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Unit>` - Some(Unit) that was removed, None for unknown ids.
+    pub fn despawn(&mut self, id: u64) -> Option<Unit> {
+        let index = self.mob_index(id)?;
+        Some(self.mobs.remove(index).unit)
+    }
+
+    /// Retrieves a mob unit by id.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Id of the mob
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&Unit>` - Some(&Unit) if the mob exists, None otherwise.
+    pub fn get_mob(&self, id: u64) -> Option<&Unit> {
+        self.mob_index(id).map(|index| &self.mobs[index].unit)
+    }
+
+    /// Retrieves a mob unit by id for changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Id of the mob
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&mut Unit>` - Some(&mut Unit) if the mob exists, None otherwise.
+    pub fn get_mob_mut(&mut self, id: u64) -> Option<&mut Unit> {
+        self.mob_index(id).map(|index| &mut self.mobs[index].unit)
+    }
+
+    /// Checks if a mob moves by itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Id of the mob
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - true if the mob exists and has a behaviour, false otherwise.
+    pub fn has_behaviour(&self, id: u64) -> bool {
+        self.mob_index(id).is_some_and(|index| self.mobs[index].behaviour.is_some())
+    }
+
+    /// Returns an iterator over mob units in spawn order.
+    pub fn mobs(&self) -> impl DoubleEndedIterator<Item = &Unit> + ExactSizeIterator {
+        self.mobs.iter().map(|mob| &mob.unit)
+    }
+
+    /// Returns an iterator over mob units in spawn order for changes.
+    pub fn mobs_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Unit> + ExactSizeIterator {
+        self.mobs.iter_mut().map(|mob| &mut mob.unit)
+    }
+
+    /// Returns an iterator over mob ids and units in spawn order.
+    pub fn iter_mobs(&self) -> impl DoubleEndedIterator<Item = (u64, &Unit)> + ExactSizeIterator {
+        self.mobs.iter().map(|mob| (mob.id, &mob.unit))
+    }
+
+    /// Returns the number of mobs.
+    pub fn mob_count(&self) -> usize {
+        self.mobs.len()
+    }
+
+    /// Moves every mob that has a behaviour by one tick.
+    ///
+    /// # Arguments
+    ///
+    /// * `game_map` - Map the game runs on
+    /// * `delta` - Seconds since the last tick
+    pub fn tick(&mut self, game_map: &GameMap, delta: f32) {
+        let context = BehaviourContext { game_map, player: &self.player, delta };
+        for mob in &mut self.mobs {
+            if let Some(behaviour) = &mut mob.behaviour {
+                behaviour.update(&mut mob.unit, &context);
             }
         }
+    }
+
+    /// Stores a new mob under the next id.
+    fn push_mob(&mut self, unit: Unit, behaviour: Option<Box<dyn MobBehaviour>>) -> u64 {
+        let id = self.next_mob_id;
+        self.next_mob_id += 1;
+        self.mobs.push(MobSlot { unit, behaviour, id });
+        id
+    }
+
+    /// Finds the position of a mob, ids ascend in spawn order.
+    fn mob_index(&self, id: u64) -> Option<usize> {
+        self.mobs.binary_search_by_key(&id, |mob| mob.id).ok()
+    }
+
+    /// Adds a non-player mob from its map definition.
+    fn spawn_mob(&mut self, mob: &Mob) {
+        self.spawn(mob.x_start as f32, mob.y_start as f32, mob.behaviour.as_ref());
+    }
 
-        Self { player: player.unwrap(), mobs }
+    /// Creates a non-player unit moving as its behaviour tells.
+    fn behaviour_unit(x: f32, y: f32, behaviour: Option<&Behaviour>) -> Unit {
+        let Some(beh) = behaviour else {
+            return Unit { x, y, x_speed: 0.0, y_speed: 0.0 };
+        };
+
+        let mob_direction = beh.direction.as_deref().unwrap_or("none");
+        let mob_speed = beh.speed.unwrap_or(0.0);
+
+        Unit {
+            x,
+            y,
+            x_speed: match mob_direction {
+                "right" => mob_speed,
+                "left" => -mob_speed,
+                _ => 0.0,
+            },
+            y_speed: match mob_direction {
+                "up" => -mob_speed,
+                "down" => mob_speed,
+                _ => 0.0,
+            },
+        }
     }
 }
 
 #[cfg(test)]
 mod state_tests {
-    use crate::assets::{Behaviour, BehaviourType, GameMap, Mob};
-    use crate::world::State;
+    use crate::assets::{Behaviour, BehaviourType, GameMap, Mob, Properties};
+    use crate::world::{State, Unit};
 
     fn make_test_map() -> GameMap {
         let mut mobs = std::collections::HashMap::new();
@@ -138,6 +343,7 @@ mod state_tests {
                 asset: "knight".to_string(),
                 is_player: true,
                 behaviour: None,
+                properties: Properties::new(),
             },
         );
 
@@ -153,7 +359,9 @@ mod state_tests {
                     behaviour_type: BehaviourType::Walker,
                     direction: Some("right".to_string()),
                     speed: Some(1.0),
+                    params: Properties::new(),
                 }),
+                properties: Properties::new(),
             },
         );
 
@@ -169,7 +377,9 @@ mod state_tests {
                     behaviour_type: BehaviourType::Walker,
                     direction: Some("up".to_string()),
                     speed: Some(0.5),
+                    params: Properties::new(),
                 }),
+                properties: Properties::new(),
             },
         );
 
@@ -180,6 +390,13 @@ mod state_tests {
             mobs,
             objects: std::collections::HashMap::new(),
             tiles: std::collections::HashMap::new(),
+            tileset: Vec::new(),
+            layers: Vec::new(),
+            regions: std::collections::HashMap::new(),
+            spawners: std::collections::HashMap::new(),
+            prefabs: std::collections::HashMap::new(),
+            instances: std::collections::HashMap::new(),
+            properties: Properties::new(),
         }
     }
 
@@ -193,15 +410,15 @@ mod state_tests {
         assert_eq!(state.player.x_speed, 10.0);
         assert_eq!(state.player.y_speed, 10.0);
 
-        assert_eq!(state.mobs.len(), 2);
+        assert_eq!(state.mob_count(), 2);
 
-        let mob_right = state.mobs.iter().find(|m| m.x_speed > 0.0).unwrap();
+        let mob_right = state.mobs().find(|m| m.x_speed > 0.0).unwrap();
         assert_eq!(mob_right.x_speed, 1.0);
         assert_eq!(mob_right.y_speed, 0.0);
         assert_eq!(mob_right.x, 10.0);
         assert_eq!(mob_right.y, 0.0);
 
-        let mob_up = state.mobs.iter().find(|m| m.y_speed < 0.0).unwrap();
+        let mob_up = state.mobs().find(|m| m.y_speed < 0.0).unwrap();
         assert_eq!(mob_up.x_speed, 0.0);
         assert_eq!(mob_up.y_speed, -0.5);
         assert_eq!(mob_up.x, 0.0);
@@ -216,7 +433,7 @@ mod state_tests {
 
         assert_eq!(state.player.x, 0.0);
         assert_eq!(state.player.y, 0.0);
-        assert!(state.mobs.is_empty());
+        assert!((state.mob_count() == 0));
     }
 
     #[test]
@@ -231,6 +448,7 @@ mod state_tests {
                 asset: "knight".to_string(),
                 is_player: true,
                 behaviour: None,
+                properties: Properties::new(),
             },
         );
         mobs.insert(
@@ -242,6 +460,7 @@ mod state_tests {
                 asset: "dummy".to_string(),
                 is_player: false,
                 behaviour: None,
+                properties: Properties::new(),
             },
         );
         mobs.insert(
@@ -253,10 +472,12 @@ mod state_tests {
                 asset: "dummy".to_string(),
                 is_player: false,
                 behaviour: Some(Behaviour {
-                    behaviour_type: BehaviourType::Unknown,
+                    behaviour_type: BehaviourType::Custom("dance".to_string()),
                     direction: Some("left".to_string()),
                     speed: Some(2.0),
+                    params: Properties::new(),
                 }),
+                properties: Properties::new(),
             },
         );
 
@@ -267,16 +488,23 @@ mod state_tests {
             mobs,
             objects: std::collections::HashMap::new(),
             tiles: std::collections::HashMap::new(),
+            tileset: Vec::new(),
+            layers: Vec::new(),
+            regions: std::collections::HashMap::new(),
+            spawners: std::collections::HashMap::new(),
+            prefabs: std::collections::HashMap::new(),
+            instances: std::collections::HashMap::new(),
+            properties: Properties::new(),
         };
 
         let state = State::new(&map);
-        assert_eq!(state.mobs.len(), 2);
+        assert_eq!(state.mob_count(), 2);
 
-        let mob_none = state.mobs.iter().find(|m| m.x == 5.0).unwrap();
+        let mob_none = state.mobs().find(|m| m.x == 5.0).unwrap();
         assert_eq!(mob_none.x_speed, 0.0);
         assert_eq!(mob_none.y_speed, 0.0);
 
-        let mob_unknown = state.mobs.iter().find(|m| m.x == 10.0).unwrap();
+        let mob_unknown = state.mobs().find(|m| m.x == 10.0).unwrap();
         assert_eq!(mob_unknown.x_speed, -2.0);
         assert_eq!(mob_unknown.y_speed, 0.0);
     }
@@ -290,4 +518,64 @@ mod state_tests {
         assert_eq!(state.player.x, player_map.x_start as f32);
         assert_eq!(state.player.y, player_map.y_start as f32);
     }
+
+    // Test that reloading keeps units unless mob definitions change
+    #[test]
+    fn test_reload_keeps_state() {
+        let old_map = make_test_map();
+        let mut state = State::new(&old_map);
+        state.player.x = 42.0;
+        state.get_mob_mut(0).unwrap().x = 7.5;
+
+        let mut new_map = old_map.clone();
+        new_map.tiles.clear();
+        assert!(!state.reload(&old_map, &new_map));
+        assert_eq!(state.player.x, 42.0);
+        assert_eq!(state.get_mob(0).unwrap().x, 7.5);
+
+        new_map.mobs.remove("mob_up");
+        assert!(state.reload(&old_map, &new_map));
+        assert_eq!(state.player.x, 42.0);
+        assert_eq!(state.mob_count(), 1);
+        assert_eq!(state.iter_mobs().map(|(id, mob)| (id, mob.x)).collect::<Vec<_>>(), [(2, 10.0)]);
+    }
+
+    // Test that ticks move mobs by their behaviours, spawned ones included
+    #[test]
+    fn test_tick_moves_mobs() {
+        let map = make_test_map();
+        let mut state = State::new(&map);
+        let walker = state.spawn(3.0, 3.0, map.get_mob("mob_right").unwrap().behaviour.as_ref());
+        let still = state.spawn(5.0, 5.0, None);
+        assert_eq!((walker, still), (2, 3));
+        assert!(state.has_behaviour(walker) && !state.has_behaviour(still));
+
+        state.tick(&map, 0.1);
+        state.tick(&map, 0.1);
+
+        let positions: Vec<(f32, f32)> = state.mobs().map(|mob| (mob.x, mob.y)).collect();
+        assert!(positions.contains(&(12.0, 0.0)));
+        assert!(positions.contains(&(0.0, 9.0)));
+        assert_eq!(&positions[2..], &[(5.0, 3.0), (5.0, 5.0)]);
+        assert_eq!((state.player.x, state.player.y), (0.0, 0.0));
+    }
+
+    // Test that despawned mobs leave with their behaviours and ids stay with their units
+    #[test]
+    fn test_despawn() {
+        let map = make_test_map();
+        let mut state = State::new(&map);
+        let extra = state.spawn_unit(Unit::new(1.0, 2.0, 0.0, 0.0));
+
+        assert!(state.despawn(0).is_some());
+        assert!(state.despawn(0).is_none());
+        assert_eq!(state.mob_count(), 2);
+        assert!(state.get_mob(0).is_none());
+        assert_eq!(state.iter_mobs().map(|(id, _)| id).collect::<Vec<_>>(), [1, extra]);
+        assert!(state.has_behaviour(1) && !state.has_behaviour(extra));
+
+        state.tick(&map, 1.0);
+        assert_eq!(state.get_mob(extra).unwrap().x, 1.0);
+        assert_eq!(state.spawn(0.0, 0.0, None), extra + 1);
+    }
 }